@@ -0,0 +1,168 @@
+/*!
+Corner actions.
+
+Besides the start menu and the desktop selector, a corner can press any key
+chord, launch a program, show the desktop, lock the workstation or do nothing
+at all:
+
+```toml
+corner_action = "show_desktop"
+corner_action = { keys = "Win+Shift+S" }
+corner_action = { launch = { program = "wt.exe", args = ["-d", "C:\\"] } }
+```
+
+[`CornerAction::perform`] carries an action out through a [`ShellBackend`], so
+a [`FakeShell`](crate::shell::FakeShell) can check what would have been sent.
+!*/
+
+use crate::shell::{vk, ShellBackend};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Key names accepted in a chord. The first name for a key is the one it is printed with.
+const KEY_NAMES: &[(&str, u8)] = &[
+    ("Win", vk::LWIN),
+    ("Ctrl", vk::CONTROL),
+    ("Control", vk::CONTROL),
+    ("Shift", vk::SHIFT),
+    ("Alt", vk::MENU),
+    ("Tab", vk::TAB),
+    ("Enter", vk::RETURN),
+    ("Return", vk::RETURN),
+    ("Esc", vk::ESCAPE),
+    ("Escape", vk::ESCAPE),
+    ("Space", vk::SPACE),
+    ("Backspace", vk::BACK),
+    ("Delete", vk::DELETE),
+    ("Del", vk::DELETE),
+    ("Insert", vk::INSERT),
+    ("Home", vk::HOME),
+    ("End", vk::END),
+    ("PageUp", vk::PRIOR),
+    ("PageDown", vk::NEXT),
+    ("Left", vk::LEFT),
+    ("Up", vk::UP),
+    ("Right", vk::RIGHT),
+    ("Down", vk::DOWN),
+    ("PrintScreen", vk::SNAPSHOT),
+];
+
+/// Keys pressed together, in order, like `Win+Shift+S`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Chord {
+    pub keys: Vec<u8>,
+}
+
+impl Chord {
+    pub fn new(keys: &[u8]) -> Chord {
+        Chord {
+            keys: keys.to_vec(),
+        }
+    }
+    pub fn parse(text: &str) -> Result<Chord, String> {
+        if text.trim().is_empty() {
+            return Err("Expected a key chord like Win+Shift+S".to_string());
+        }
+        let mut keys = vec![];
+        for name in text.split('+').map(str::trim) {
+            let key = Chord::parse_key(name)
+                .ok_or_else(|| format!("Unknown key '{}' in '{}'", name, text))?;
+            if keys.contains(&key) {
+                return Err(format!("'{}' appears twice in '{}'", name, text));
+            }
+            keys.push(key);
+        }
+        Ok(Chord { keys })
+    }
+    fn parse_key(name: &str) -> Option<u8> {
+        if let Some((_, key)) = KEY_NAMES
+            .iter()
+            .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        {
+            return Some(*key);
+        }
+        let mut chars = name.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(key), None) if key.is_ascii_alphanumeric() => {
+                Some(key.to_ascii_uppercase() as u8)
+            }
+            (Some('F'), Some(_)) | (Some('f'), Some(_)) => name[1..]
+                .parse::<u8>()
+                .ok()
+                .filter(|number| (1..=24).contains(number))
+                .map(|number| vk::F1 + number - 1),
+            _ => None,
+        }
+    }
+    fn key_name(key: u8) -> String {
+        match KEY_NAMES.iter().find(|(_, named)| *named == key) {
+            Some((name, _)) => name.to_string(),
+            None if key.is_ascii_uppercase() || key.is_ascii_digit() => (key as char).to_string(),
+            None if (vk::F1..vk::F1 + 24).contains(&key) => format!("F{}", key - vk::F1 + 1),
+            None => format!("{:#04x}", key),
+        }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<String> = self.keys.iter().map(|key| Chord::key_name(*key)).collect();
+        write!(f, "{}", names.join("+"))
+    }
+}
+
+impl TryFrom<String> for Chord {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Chord, String> {
+        Chord::parse(&text)
+    }
+}
+
+impl From<Chord> for String {
+    fn from(chord: Chord) -> String {
+        chord.to_string()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CornerAction {
+    StartMenu,
+    DesktopSelector,
+    ShowDesktop,
+    Lock,
+    Nothing,
+    Keys(Chord),
+    Launch {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+impl CornerAction {
+    pub fn perform(&self, shell: &mut impl ShellBackend) {
+        match self {
+            CornerAction::StartMenu => shell.send_keys(&[vk::LWIN]),
+            CornerAction::DesktopSelector => shell.send_keys(&[vk::LWIN, vk::TAB]),
+            CornerAction::ShowDesktop => shell.send_keys(&[vk::LWIN, b'D']),
+            CornerAction::Lock => {
+                if !shell.lock() {
+                    warn!("Could not lock the workstation");
+                }
+            }
+            CornerAction::Nothing => {}
+            CornerAction::Keys(chord) => shell.send_keys(&chord.keys),
+            CornerAction::Launch { program, args } => {
+                if !shell.launch(program, args) {
+                    warn!("Could not launch {}", program);
+                }
+            }
+        }
+    }
+}
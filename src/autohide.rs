@@ -0,0 +1,159 @@
+/*!
+The taskbar's auto-hide setting.
+
+Hiding the taskbar window doesn't give back the strip of screen it reserves
+unless Windows auto-hides the taskbar as well. With `manage_auto_hide = true`
+in the config file, win-gnome turns auto-hide on while it hides the taskbar and puts the setting
+back the way it found it when it stops.
+
+The setting found is also written to `auto-hide.toml` next to the config file,
+so a later run can put it back after a crash and `win-gnome --restore-auto-hide`
+can put it back on uninstall.
+
+[`AutoHide::plan`] decides what to change, the shell only reads and writes it.
+!*/
+
+use crate::shell::{ShellBackend, WindowId};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// `ABS_*` bits of `ABM_GETSTATE` and `ABM_SETSTATE`.
+pub const ABS_AUTOHIDE: u32 = 0x1;
+pub const ABS_ALWAYSONTOP: u32 = 0x2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppBarState {
+    pub auto_hide: bool,
+    pub always_on_top: bool,
+}
+
+impl AppBarState {
+    pub const fn new(auto_hide: bool, always_on_top: bool) -> AppBarState {
+        AppBarState {
+            auto_hide,
+            always_on_top,
+        }
+    }
+    pub fn from_bits(bits: u32) -> AppBarState {
+        AppBarState::new(bits & ABS_AUTOHIDE != 0, bits & ABS_ALWAYSONTOP != 0)
+    }
+    pub fn bits(self) -> u32 {
+        let mut bits = 0;
+        if self.auto_hide {
+            bits |= ABS_AUTOHIDE;
+        }
+        if self.always_on_top {
+            bits |= ABS_ALWAYSONTOP;
+        }
+        bits
+    }
+}
+
+/// Holds auto-hide on, remembering what it was before.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AutoHide {
+    /// The setting found before turning auto-hide on, None while it isn't held.
+    pub original: Option<AppBarState>,
+    /// Where `original` is kept between runs.
+    pub path: Option<PathBuf>,
+}
+
+impl AutoHide {
+    pub const fn new() -> AutoHide {
+        AutoHide {
+            original: None,
+            path: None,
+        }
+    }
+    /// Picks up a setting left at `path` by a run that didn't get to restore it.
+    pub fn open(path: Option<PathBuf>) -> AutoHide {
+        AutoHide {
+            original: path.as_deref().and_then(load),
+            path,
+        }
+    }
+    /// What to set the taskbar to now that `manage` says whether auto-hide should be held,
+    /// None to leave it as it is.
+    pub fn plan(&mut self, manage: bool, current: AppBarState) -> Option<AppBarState> {
+        match self.original {
+            None if manage => {
+                self.original = Some(current);
+                if current.auto_hide {
+                    None
+                } else {
+                    Some(AppBarState::new(true, current.always_on_top))
+                }
+            }
+            Some(original) if !manage => {
+                self.original = None;
+                if current.auto_hide == original.auto_hide {
+                    None
+                } else {
+                    Some(AppBarState::new(original.auto_hide, current.always_on_top))
+                }
+            }
+            _ => None,
+        }
+    }
+    /// Reads the setting of `taskbar`, plans and writes the change, keeping the file at
+    /// `path` in step.
+    pub fn apply(
+        &mut self,
+        manage: bool,
+        shell: &mut impl ShellBackend,
+        taskbar: WindowId,
+    ) -> Result<(), String> {
+        let current = shell
+            .app_bar_state(taskbar)
+            .ok_or("Could not read the taskbar's auto-hide setting")?;
+        let held = self.original;
+        let change = self.plan(manage, current);
+        let update = |path: &Path, result: io::Result<()>| {
+            result.map_err(|error| format!("Could not update {}: {}", path.display(), error))
+        };
+        // Saved before the change, so a crash during it still finds the setting
+        if let (Some(path), None, Some(original)) = (&self.path, held, self.original) {
+            update(path, save(path, original))?;
+        }
+        match change {
+            Some(state) if !shell.set_app_bar_state(taskbar, state) => {
+                // Still held, the file stays for the next try or the next run
+                self.original = self.original.or(held);
+                return Err("Could not change the taskbar's auto-hide setting".to_string());
+            }
+            _ => {}
+        }
+        // Removed only once the setting is back
+        if let (Some(path), Some(_), None) = (&self.path, held, self.original) {
+            update(path, remove(path))?;
+        }
+        Ok(())
+    }
+}
+
+/// `auto-hide.toml` next to the config file.
+pub fn default_path() -> Option<PathBuf> {
+    crate::config::Config::default_path()
+        .and_then(|config| Some(config.parent()?.join("auto-hide.toml")))
+}
+
+pub fn load(path: &Path) -> Option<AppBarState> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+pub fn save(path: &Path, state: AppBarState) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let text = toml::to_string(&state).map_err(|error| io::Error::other(error.to_string()))?;
+    fs::write(path, text)
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,198 @@
+/*!
+Configuration file.
+
+Every option lives in a TOML file, by default `%APPDATA%\WinGnome\config.toml`:
+
+```toml
+# What the hot corner does: "start_menu", "desktop_selector", or see `action`
+corner_action = "start_menu"
+# Hide the taskbar until the hot corner is used
+hide_tray = true
+# Set to true to turn on the taskbar's auto-hide while hiding it, see `autohide`
+manage_auto_hide = false
+# Size of the hot corner as a percent of the start button, 1-100
+sensitivity = 100
+# Or in pixels at 100% scaling, a percent of the start button that may be more
+# than 100, or a percent of the screen. Wins over sensitivity, see `geometry`
+corner_size = "32px"
+
+# Dwell time and pressure, see `trigger`
+[trigger]
+dwell = 150
+
+# Extra corners and edges, see `corners`
+[corners.top_right]
+action = "desktop_selector"
+
+# Corner shapes and where corners never fire, see `region`
+[regions]
+shape = "radius"
+
+# Key combinations and what they do, see `hotkey`
+[hotkeys]
+"Win+Esc" = "toggle"
+
+# Programs to stay out of, see `exclude`
+[[exclude]]
+process = "mstsc.exe"
+```
+
+Missing keys keep their defaults. Command line flags are collected into
+[`Overrides`] and win over whatever the file says.
+!*/
+
+use crate::corners::CornerMap;
+use crate::engine::{CornerAction, TrayAction};
+use crate::exclude::ExcludeList;
+use crate::geometry::CornerSize;
+use crate::hotkey::HotkeyMap;
+use crate::region::Regions;
+use crate::trigger::TriggerSettings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub corner_action: CornerAction,
+    pub hide_tray: bool,
+    /// Hold the taskbar's auto-hide setting on while `hide_tray` is. Off unless asked
+    /// for, it changes a Windows setting.
+    pub manage_auto_hide: bool,
+    /// Percent of the start button, 1-100.
+    pub sensitivity: i32,
+    /// Replaces `sensitivity` when set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub corner_size: Option<CornerSize>,
+    pub trigger: TriggerSettings,
+    pub corners: CornerMap,
+    pub regions: Regions,
+    pub hotkeys: HotkeyMap,
+    pub exclude: ExcludeList,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            corner_action: CornerAction::StartMenu,
+            hide_tray: true,
+            manage_auto_hide: false,
+            sensitivity: 100,
+            corner_size: None,
+            trigger: TriggerSettings::default(),
+            corners: CornerMap::new(),
+            regions: Regions::new(),
+            hotkeys: HotkeyMap::default(),
+            exclude: ExcludeList::new(),
+        }
+    }
+}
+
+/// Options given on the command line.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides {
+    pub corner_action: Option<CornerAction>,
+    pub hide_tray: Option<bool>,
+    pub sensitivity: Option<i32>,
+}
+
+pub fn validate_sensitivity(sensitivity: i32) -> Result<i32, &'static str> {
+    if sensitivity > 0 && sensitivity <= 100 {
+        Ok(sensitivity)
+    } else {
+        Err("Sensitivity must be between 1-100")
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(text).map_err(|error| error.to_string())?;
+        validate_sensitivity(config.sensitivity)?;
+        config.trigger.validate()?;
+        config.corners.validate()?;
+        config.regions.validate()?;
+        config.exclude.validate()?;
+        Ok(config)
+    }
+    /// Reads `path`, falling back to the defaults if it doesn't exist.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text),
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(error) => Err(error.to_string()),
+        }
+    }
+    /// `%APPDATA%\WinGnome\config.toml`, or `$XDG_CONFIG_HOME/win-gnome/config.toml` elsewhere.
+    pub fn default_path() -> Option<PathBuf> {
+        if cfg!(windows) {
+            std::env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join("WinGnome"))
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                })
+                .map(|dir| dir.join("win-gnome"))
+        }
+        .map(|dir| dir.join("config.toml"))
+    }
+    pub fn merge(mut self, overrides: &Overrides) -> Config {
+        if let Some(corner_action) = &overrides.corner_action {
+            self.corner_action = corner_action.clone();
+        }
+        if let Some(hide_tray) = overrides.hide_tray {
+            self.hide_tray = hide_tray;
+        }
+        if let Some(sensitivity) = overrides.sensitivity {
+            self.sensitivity = sensitivity;
+            self.corner_size = None;
+        }
+        self
+    }
+    /// Size of the start corner, `corner_size` or else `sensitivity` percent of the button.
+    pub fn corner_size(&self) -> CornerSize {
+        self.corner_size
+            .unwrap_or(CornerSize::Button(self.sensitivity))
+    }
+    /// Whether auto-hide should be held on, see `autohide::AutoHide::plan`.
+    pub fn manages_auto_hide(&self) -> bool {
+        self.manage_auto_hide && self.hide_tray
+    }
+    pub fn tray_action(&self) -> TrayAction {
+        if self.hide_tray {
+            TrayAction::Hide
+        } else {
+            TrayAction::NoHide
+        }
+    }
+}
+
+/// Notices when the config file is written to.
+pub struct ConfigWatcher {
+    pub path: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> ConfigWatcher {
+        let stamp = ConfigWatcher::stamp(&path);
+        ConfigWatcher { path, stamp }
+    }
+    fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+    /// Whether the file was created, changed or removed since the last call.
+    pub fn changed(&mut self) -> bool {
+        let stamp = ConfigWatcher::stamp(&self.path);
+        if stamp != self.stamp {
+            self.stamp = stamp;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,126 @@
+/*!
+Talking to a running instance.
+
+`win-gnome toggle` and friends don't start a second copy, they send a
+[`Request`] to the one already running and print the [`Reply`]. Both travel as
+one line of JSON each, over whatever stream connects the two processes: a
+named pipe on Windows, anything that implements `Read` and `Write` in tests.
+!*/
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// The pipe of the instance running in Windows session `session`, so users signed
+/// in at the same time each talk to their own.
+pub fn pipe_name(session: u32) -> String {
+    format!(r"\\.\pipe\win_gnome-{}", session)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    Enable,
+    Disable,
+    Toggle,
+    Status,
+    /// Read the config file again.
+    Reload,
+    Quit,
+}
+
+impl Request {
+    pub const ALL: [Request; 6] = [
+        Request::Enable,
+        Request::Disable,
+        Request::Toggle,
+        Request::Status,
+        Request::Reload,
+        Request::Quit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Request::Enable => "enable",
+            Request::Disable => "disable",
+            Request::Toggle => "toggle",
+            Request::Status => "status",
+            Request::Reload => "reload",
+            Request::Quit => "quit",
+        }
+    }
+    /// The request for a command line argument like `toggle`.
+    pub fn parse(name: &str) -> Option<Request> {
+        Request::ALL
+            .iter()
+            .cloned()
+            .find(|request| request.name() == name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reply {
+    Ok,
+    Status { enabled: bool, hide_tray: bool },
+    Error(String),
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reply::Ok => write!(f, "ok"),
+            Reply::Status { enabled, hide_tray } => write!(
+                f,
+                "{}, taskbar {}",
+                if *enabled { "enabled" } else { "disabled" },
+                if *hide_tray { "hidden" } else { "always shown" }
+            ),
+            Reply::Error(error) => write!(f, "error: {}", error),
+        }
+    }
+}
+
+/// Writes `message` as one line of JSON.
+pub fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()
+}
+
+/// Reads one line of JSON. The other end hanging up is an `UnexpectedEof`.
+pub fn read_message<T: DeserializeOwned>(reader: &mut impl BufRead) -> io::Result<T> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Connection closed before a message arrived",
+        ));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Sends `request` and waits for the reply.
+pub fn send<S: Read + Write>(stream: &mut S, request: Request) -> io::Result<Reply> {
+    write_message(stream, &request)?;
+    read_message(&mut BufReader::new(stream))
+}
+
+/// Answers one request on `stream` with `handle`, returns the request.
+pub fn serve<S: Read + Write>(
+    stream: &mut S,
+    handle: impl FnOnce(Request) -> Reply,
+) -> io::Result<Request> {
+    let request = match read_message(&mut BufReader::new(&mut *stream)) {
+        Ok(request) => request,
+        Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+            write_message(stream, &Reply::Error(format!("Bad request: {}", error)))?;
+            return Err(error);
+        }
+        Err(error) => return Err(error),
+    };
+    write_message(stream, &handle(request))?;
+    Ok(request)
+}
@@ -0,0 +1,158 @@
+/*!
+Per-corner actions.
+
+Out of the box only the corner holding the start button is hot, using the
+global corner action and size. A [`CornerMap`] adds the other corners and
+edges, or replaces the start corner's settings, one entry at a time:
+
+```toml
+[corners.top_right]
+action = "desktop_selector"
+sensitivity = 50
+
+[corners.bottom_right]
+size = "64px"
+
+[corners.bottom_left]
+enabled = false
+```
+!*/
+
+use crate::config::validate_sensitivity;
+use crate::engine::CornerAction;
+use crate::geometry::{Corner, CornerSize, TrayGeometry};
+use crate::region::{Regions, Shape};
+use serde::de::{value, IntoDeserializer};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CornerSettings {
+    pub action: CornerAction,
+    /// Percent of the global corner size for corners, of the screen side for edges.
+    pub sensitivity: i32,
+    /// Of a corner, instead of `sensitivity`. Edges don't have one.
+    pub size: Option<CornerSize>,
+    pub enabled: bool,
+}
+
+impl Default for CornerSettings {
+    fn default() -> CornerSettings {
+        CornerSettings {
+            action: CornerAction::StartMenu,
+            sensitivity: 100,
+            size: None,
+            enabled: true,
+        }
+    }
+}
+
+impl CornerSettings {
+    /// Whether `(x, y)` falls in `corner` cut to `shape`, sized by these settings.
+    pub fn contains(
+        &self,
+        geometry: &TrayGeometry,
+        corner: Corner,
+        shape: Shape,
+        x: i32,
+        y: i32,
+    ) -> bool {
+        match self.size {
+            Some(size) => geometry.is_in_sized(corner, shape, size, x, y),
+            None => geometry.is_in_shape(corner, shape, self.sensitivity, x, y),
+        }
+    }
+}
+
+/// Serialized as a table keyed by corner name, TOML can't key a table with an enum.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(
+    try_from = "BTreeMap<String, CornerSettings>",
+    into = "BTreeMap<Corner, CornerSettings>"
+)]
+pub struct CornerMap {
+    corners: BTreeMap<Corner, CornerSettings>,
+}
+
+impl TryFrom<BTreeMap<String, CornerSettings>> for CornerMap {
+    type Error = value::Error;
+
+    fn try_from(named: BTreeMap<String, CornerSettings>) -> Result<CornerMap, value::Error> {
+        let corners = named
+            .into_iter()
+            .map(|(name, settings)| Ok((Corner::deserialize(name.into_deserializer())?, settings)))
+            .collect::<Result<_, value::Error>>()?;
+        Ok(CornerMap { corners })
+    }
+}
+
+impl From<CornerMap> for BTreeMap<Corner, CornerSettings> {
+    fn from(map: CornerMap) -> BTreeMap<Corner, CornerSettings> {
+        map.corners
+    }
+}
+
+impl CornerMap {
+    pub const fn new() -> CornerMap {
+        CornerMap {
+            corners: BTreeMap::new(),
+        }
+    }
+    pub fn set(&mut self, corner: Corner, settings: CornerSettings) {
+        self.corners.insert(corner, settings);
+    }
+    pub fn get(&self, corner: Corner) -> Option<&CornerSettings> {
+        self.corners.get(&corner)
+    }
+    pub fn validate(&self) -> Result<(), &'static str> {
+        for settings in self.corners.values() {
+            validate_sensitivity(settings.sensitivity)?;
+        }
+        Ok(())
+    }
+    /// The enabled corner under `(x, y)` and what it should do.
+    ///
+    /// The start corner falls back to `start_action` and the tray's own hot area
+    /// unless the map has an entry for it.
+    pub fn hit<'a>(
+        &'a self,
+        geometry: &TrayGeometry,
+        start_action: &'a CornerAction,
+        x: i32,
+        y: i32,
+    ) -> Option<(Corner, &'a CornerAction)> {
+        self.hit_in(geometry, &Regions::new(), start_action, x, y)
+    }
+    /// `hit` with the corners cut to the shapes in `regions`, and nothing in its dead zones.
+    pub fn hit_in<'a>(
+        &'a self,
+        geometry: &TrayGeometry,
+        regions: &Regions,
+        start_action: &'a CornerAction,
+        x: i32,
+        y: i32,
+    ) -> Option<(Corner, &'a CornerAction)> {
+        if regions.is_dead(geometry, x, y) {
+            return None;
+        }
+        let start = geometry.start_corner();
+
+        Corner::ALL.iter().find_map(|&corner| {
+            let shape = regions.shape_of(corner);
+            match self.corners.get(&corner) {
+                Some(settings)
+                    if settings.enabled && settings.contains(geometry, corner, shape, x, y) =>
+                {
+                    Some((corner, &settings.action))
+                }
+                Some(_) => None,
+                None if corner == start && geometry.is_hot_shape(shape, x, y) => {
+                    Some((corner, start_action))
+                }
+                None => None,
+            }
+        })
+    }
+}
@@ -1,413 +1,451 @@
-use crate::window::win32_string;
-use winapi::shared::windef::{HWND, RECT};
-use winapi::um::winuser::{
-    keybd_event, FindWindowExW, FindWindowW, GetClassNameW, GetDesktopWindow, GetForegroundWindow,
-    GetWindowRect, ShowWindow, IsWindow, GetWindowLongW, GetParent, GetWindowTextW,
-    KEYEVENTF_KEYUP, SW_HIDE, SW_SHOW, VK_LWIN, VK_TAB, GWL_STYLE, GWL_EXSTYLE, WS_VISIBLE
-};
-use winapi::um::dwmapi::{DwmGetWindowAttribute, DwmFlush, DWMWA_CLOAKED};
-use winapi::ctypes::c_void;
-use std::ptr::null_mut;
-#[allow(dead_code)]
+use crate::engine::{Command, CornerAction, ShellState};
+use crate::exclude::ExcludeList;
+use crate::fullscreen::{self, WindowFacts};
+use crate::geometry::{CornerSize, TrayGeometry, TrayOrientation};
+use crate::monitor::{Layout, Monitor};
+use crate::rules::{Context, Role, Rules};
+use crate::shell::{ShellBackend, WindowId};
+use crate::taskbar::{self, ShellVersion};
+use log::{debug, error, info, log_enabled, warn, Level};
+use std::time::Duration;
+
+/// How long DWM takes to cloak the start menu once it loses focus.
+pub const COMPOSITOR_DELAY: Duration = Duration::from_millis(50);
 
-pub enum TrayOrientation {
-    Bottom,
-    Top,
-    Left,
-    Right,
-}
 pub struct Tray {
-    pub orientation: TrayOrientation,
-    pub bar: HWND,
-    pub start_button: HWND,
-    pub start_menu: HWND,
-    pub icon_overflow: HWND,
+    pub geometry: TrayGeometry,
+    pub version: ShellVersion,
+    pub bar: WindowId,
+    /// Null on Windows 11, which draws the button inside the taskbar.
+    pub start_button: WindowId,
+    pub start_menu: WindowId,
+    /// The search flyout, which counts as part of the start menu.
+    pub search: WindowId,
+    pub icon_overflow: WindowId,
     pub showing: bool,
     pub startmenu_showing: bool,
     pub overflow_showing: bool,
-    pub parent_width: i32,
-    pub parent_height: i32,
-    pub start_height: i32,
-    pub start_width: i32,
-    pub hot_width: i32,
-    pub hot_height: i32,
 }
 impl Tray {
-    fn new(parent: HWND, parent_width: i32, parent_height: i32) -> Result<Tray, &'static str> {
-        let bar = Desktop::find_window(Some("Shell_TrayWnd"), None)
-            .ok_or_else(|| "Could not find window for system tray")?;
-        let start_button =
-            Desktop::find_child(bar, "Start").ok_or_else(|| "Could not find start button")?;
-        let icon_overflow = Desktop::find_window(Some("NotifyIconOverflowWindow"), None)
-            .unwrap_or_else(|| null_mut());
+    fn new(
+        shell: &impl ShellBackend,
+        rules: &Rules,
+        (parent_width, parent_height): (i32, i32),
+        corner_size: CornerSize,
+    ) -> Result<Tray, &'static str> {
+        let mut context = Context::new(None, (parent_width, parent_height));
+        let bar = rules
+            .find(shell, Role::Taskbar, &context)
+            .ok_or("Could not find window for system tray")?;
+        let version = ShellVersion::detect(shell, rules, bar);
+        context.version = Some(version);
+        let (start_button, start_dimensions) =
+            taskbar::find_start_button(shell, rules, bar, version)
+                .ok_or("Could not find start button")?;
+        let icon_overflow = rules
+            .find(shell, Role::IconOverflow, &context)
+            .unwrap_or_default();
 
-        let (start_width, start_height) = Desktop::get_window_dimensions(start_button);
-        let (hot_width, hot_height) = (
-            Tray::apply_sensitivity(start_width, unsafe { super::SENSITIVITY }),
-            Tray::apply_sensitivity(start_height, unsafe { super::SENSITIVITY }),
-        );
         let orientation =
-            Tray::get_orientation(parent_width, parent_height, Desktop::get_window_pos(bar));
-        let start_menu = Desktop::find_window(Some("Windows.UI.Core.CoreWindow"), Some("Cortana"))
-            .or_else(|| {
-                Desktop::find_by_position(
-                    parent,
-                    "Windows.UI.Core.CoreWindow",
-                    Tray::get_menu_offsets(start_width, parent_width, &orientation),
-                )
-            })
-            .ok_or_else(|| "Unable to find start menu")?;
-
-        return Ok(Tray {
+            TrayGeometry::get_orientation(parent_width, parent_height, shell.window_pos(bar));
+        let geometry = TrayGeometry::new(
             orientation,
+            (parent_width, parent_height),
+            start_dimensions,
+            100,
+        )
+        .with_corner_size(corner_size);
+        let context = context.with_tray(&geometry);
+        let start_menu = rules
+            .find(shell, Role::StartMenu, &context)
+            .ok_or("Unable to find start menu")?;
+        let search = rules
+            .find(shell, Role::Search, &context)
+            .unwrap_or_default();
+
+        Ok(Tray {
+            geometry,
+            version,
             bar,
             start_button,
             start_menu,
+            search,
             icon_overflow,
-            parent_width,
-            parent_height,
-            start_width,
-            start_height,
-            hot_width,
-            hot_height,
             showing: true,
             startmenu_showing: false,
             overflow_showing: false,
-        });
+        })
+    }
+    /// A `Shell_SecondaryTrayWnd` on another monitor. These have no start menu of their own.
+    fn secondary(
+        shell: &impl ShellBackend,
+        rules: &Rules,
+        bar: WindowId,
+        monitor: &Monitor,
+        start_dimensions: (i32, i32),
+        corner_size: CornerSize,
+        context: &Context,
+    ) -> Tray {
+        let start_button = rules
+            .find_in(shell, Role::StartButton, bar, context)
+            .unwrap_or_default();
+        let start_dimensions = if start_button.is_null() {
+            start_dimensions
+        } else {
+            shell.window_dimensions(start_button)
+        };
+        let (width, height) = monitor.dimensions();
+        let orientation = TrayGeometry::get_orientation(
+            width,
+            height,
+            monitor.to_local_pos(shell.window_pos(bar)),
+        );
+
+        Tray {
+            geometry: TrayGeometry::new(orientation, (width, height), start_dimensions, 100)
+                .with_corner_size(corner_size)
+                .with_scale(monitor.scale()),
+            bar,
+            start_button,
+            ..Tray::default()
+        }
     }
     pub const fn default() -> Tray {
         Tray {
-            orientation: TrayOrientation::Bottom,
-            bar: null_mut(),
-            start_button: null_mut(),
-            start_menu: null_mut(),
-            icon_overflow: null_mut(),
-            parent_width: 0,
-            parent_height: 0,
-            start_width: 0,
-            start_height: 0,
-            hot_width: 0,
-            hot_height: 0,
+            geometry: TrayGeometry::default(),
+            version: ShellVersion::Windows10,
+            bar: WindowId::NULL,
+            start_button: WindowId::NULL,
+            start_menu: WindowId::NULL,
+            search: WindowId::NULL,
+            icon_overflow: WindowId::NULL,
             showing: true,
             startmenu_showing: false,
             overflow_showing: false,
         }
     }
-    fn apply_sensitivity(dimension: i32, sensitivity: i32) -> i32 {
-        let dimension = dimension as f32;
-        let sensitivity = sensitivity as f32;
-        ((dimension / 100 as f32) * sensitivity).floor() as i32
-    }
-
-    pub fn get_menu_offsets(
-        button_width: i32,
-        parent_width: i32,
-        orientation: &TrayOrientation,
-    ) -> (Option<i32>, Option<i32>, Option<i32>, Option<i32>) {
-        match orientation {
-            TrayOrientation::Bottom => (None, None, Some(button_width), None),
-            TrayOrientation::Top => (None, None, Some(button_width), None),
-            TrayOrientation::Left => (Some(0), None, Some(button_width), None),
-            TrayOrientation::Right => (Some(0), None, None, Some(parent_width - button_width)),
-        }
-    }
-    pub fn is_tray_open(&self) -> bool {
-        let (top, bottom, left, right) = Desktop::get_window_pos(self.bar);
-
-        match self.orientation {
-            TrayOrientation::Bottom => bottom == self.parent_height,
-            TrayOrientation::Top => top == 0,
-            TrayOrientation::Left => left == 0,
-            TrayOrientation::Right => right == self.parent_width,
-        }
+    /// The start menu or search.
+    pub fn is_menu(&self, window: WindowId) -> bool {
+        !window.is_null() && (window == self.start_menu || window == self.search)
     }
-    pub fn is_tray_region(&self, x: i32, y: i32) -> bool {
-        match self.orientation {
-            TrayOrientation::Bottom => y > self.parent_height - self.start_height,
-            TrayOrientation::Top => y < self.start_height,
-            TrayOrientation::Left => x < self.start_width,
-            TrayOrientation::Right => x > self.parent_width - self.start_width,
-        }
+    /// Whether the start menu and search are both put away.
+    pub fn menus_cloaked(&self, shell: &impl ShellBackend) -> bool {
+        shell.is_cloaked(self.start_menu)
+            && (self.search.is_null() || shell.is_cloaked(self.search))
     }
-    pub fn is_hot_corner(&self, x: i32, y: i32) -> bool {
-        match self.orientation {
-            TrayOrientation::Bottom => {
-                x < self.hot_width && y > self.parent_height - self.hot_height
-            }
-            TrayOrientation::Top => x < self.hot_width && y < self.hot_height,
-            TrayOrientation::Left => x < self.hot_width && y < self.hot_height,
-            TrayOrientation::Right => x > self.parent_width - self.hot_width && y < self.hot_height,
-        }
+    pub fn is_tray_open(&self, shell: &impl ShellBackend) -> bool {
+        self.geometry.is_tray_open(shell.window_pos(self.bar))
     }
-    pub fn hide(&mut self) -> bool {
-        if unsafe { IsWindow(self.bar) == 0 } {
+    pub fn hide(&mut self, shell: &mut impl ShellBackend) -> bool {
+        if !shell.set_visible(self.bar, false) {
             return false;
         }
-        unsafe { ShowWindow(self.bar, SW_HIDE) };
         self.showing = false;
         true
     }
-    pub fn show(&mut self) {
-        unsafe { ShowWindow(self.bar, SW_SHOW) };
+    pub fn show(&mut self, shell: &mut impl ShellBackend) {
+        shell.set_visible(self.bar, true);
         self.showing = true;
     }
-    fn get_orientation(
-        width: i32,
-        height: i32,
-        (top, bottom, left, right): (i32, i32, i32, i32),
-    ) -> TrayOrientation {
-        if right - left > bottom - top {
-            // top or bottom
-            if top < height / 2 {
-                TrayOrientation::Top
-            } else {
-                TrayOrientation::Bottom
-            }
-        } else {
-            if left < width / 2 {
-                TrayOrientation::Left
-            } else {
-                TrayOrientation::Right
-            }
-        }
-    }
 }
 
-pub struct Desktop {
+pub struct Desktop<B: ShellBackend> {
+    pub backend: B,
+    /// Size of the hot corner on every monitor.
+    pub corner_size: CornerSize,
     pub height: i32,
     pub width: i32,
-    pub enabled: bool,
-    pub last_window: HWND,
-    pub shell_window: HWND,
-    pub shell_parent: HWND,
-    pub hot_active: bool,
+    pub last_window: WindowId,
+    pub shell_window: WindowId,
+    pub shell_parent: WindowId,
+    /// How the windows above were found.
+    pub rules: Rules,
     pub tray: Tray,
+    /// Taskbars on the other monitors.
+    pub secondary_trays: Vec<Tray>,
+    pub layout: Layout,
+    /// Hot corner geometry for each monitor in `layout`, in that monitor's coordinates.
+    pub screens: Vec<TrayGeometry>,
+    pub exclusions: ExcludeList,
+    /// Whether `last_window` is on the exclusion list.
+    pub excluded: bool,
 }
 
-impl Desktop {
-    pub fn new() -> Result<Desktop, &'static str> {
-        let mut desktop = Desktop::default();
+impl<B: ShellBackend> Desktop<B> {
+    pub fn new(backend: B, sensitivity: i32) -> Result<Desktop<B>, &'static str> {
+        Desktop::with_rules(backend, CornerSize::Button(sensitivity), Rules::builtin())
+    }
+    /// A desktop whose windows are found by `rules` instead of the built-in ones.
+    pub fn with_rules(
+        backend: B,
+        corner_size: CornerSize,
+        rules: Rules,
+    ) -> Result<Desktop<B>, &'static str> {
+        let mut desktop = Desktop::with_backend(backend);
+        desktop.corner_size = corner_size;
+        desktop.rules = rules;
         desktop.refresh()?;
         Ok(desktop)
     }
-    pub const fn default() -> Desktop {
+    /// A desktop that has not looked for any windows yet.
+    pub const fn with_backend(backend: B) -> Desktop<B> {
         Desktop {
+            backend,
+            corner_size: CornerSize::Button(100),
             height: 0,
             width: 0,
-            enabled: true,
-            hot_active: true,
-            last_window: null_mut(),
-            shell_window: null_mut(),
-            shell_parent: null_mut(),
+            last_window: WindowId::NULL,
+            shell_window: WindowId::NULL,
+            shell_parent: WindowId::NULL,
+            rules: Rules::new(),
             tray: Tray::default(),
+            secondary_trays: Vec::new(),
+            layout: Layout::new(Vec::new()),
+            screens: Vec::new(),
+            exclusions: ExcludeList::new(),
+            excluded: false,
         }
     }
+    pub fn set_sensitivity(&mut self, sensitivity: i32) {
+        self.set_corner_size(CornerSize::Button(sensitivity));
+    }
+    pub fn set_corner_size(&mut self, corner_size: CornerSize) {
+        self.corner_size = corner_size;
+        self.tray.geometry = self.tray.geometry.with_corner_size(corner_size);
+        for tray in &mut self.secondary_trays {
+            tray.geometry = tray.geometry.with_corner_size(corner_size);
+        }
+        for screen in &mut self.screens {
+            *screen = screen.with_corner_size(corner_size);
+        }
+    }
+    /// Replaces the exclusion list and checks the foreground window against it.
+    pub fn set_exclusions(&mut self, exclusions: ExcludeList) {
+        self.exclusions = exclusions;
+        self.excluded = self.exclusions.excludes(&self.backend, self.last_window);
+    }
     pub fn refresh(&mut self) -> Result<bool, &'static str> {
-        let top_desktop = unsafe { GetDesktopWindow() };
-        let (width, height, shell_window, shell_parent) = Desktop::get_actual_desktop(top_desktop)?;
-        let tray = Tray::new(top_desktop, width, height)?;
+        let top_desktop = self.backend.desktop_window();
+        let dimensions = self.backend.window_dimensions(top_desktop);
+        let tray = Tray::new(&self.backend, &self.rules, dimensions, self.corner_size)?;
+        let (width, height, shell_window, shell_parent) =
+            self.get_actual_desktop(top_desktop, tray.version)?;
 
         self.height = height;
         self.width = width;
         self.shell_window = shell_window;
         self.shell_parent = shell_parent;
         self.tray = tray;
-        self.hot_active = true;
+        self.refresh_monitors();
+
+        self.foreground_changed(self.backend.foreground_window());
+        self._debug_window(shell_window);
+        self._debug_window(shell_parent);
+        self._debug_window(self.tray.bar);
+        self._debug_window(self.tray.start_menu);
+        self._debug_window(self.tray.search);
+        self._debug_window(self.tray.start_button);
+        Ok(true)
+    }
+    /// Finds the secondary taskbars and works out the hot corners of every monitor.
+    fn refresh_monitors(&mut self) {
+        let shell = &self.backend;
+        let mut monitors = shell.monitors();
+        if monitors.is_empty() {
+            monitors = Layout::single(self.width, self.height).monitors;
+        }
+        let layout = Layout::new(monitors);
+        let start_dimensions = (
+            self.tray.geometry.start_width,
+            self.tray.geometry.start_height,
+        );
 
-        self.foreground_changed(unsafe { GetForegroundWindow() });
-        #[cfg(debug_assertions)]
-        unsafe {
-            self._debug_window(shell_window);
-            self._debug_window(shell_parent);
-            self._debug_window(self.tray.bar);
-            self._debug_window(self.tray.start_menu);
-            self._debug_window(self.tray.start_button);
+        let mut secondary_trays = vec![];
+        let mut screens: Vec<Option<TrayGeometry>> = vec![None; layout.monitors.len()];
+        let primary = layout.monitor_for(shell.window_pos(self.tray.bar));
+        if let Some(primary) = primary {
+            self.tray.geometry = self
+                .tray
+                .geometry
+                .with_scale(layout.monitors[primary].scale());
+            screens[primary] = Some(self.tray.geometry);
         }
-        return Ok(true);
+        // The start button's size on another monitor, which may be scaled differently
+        let start_scale = self.tray.geometry.scale;
+        let start_on =
+            |monitor: &Monitor| monitor.scale().rescale_size(start_dimensions, start_scale);
+        let context = self.context();
+        for bar in self.rules.find_all(shell, Role::SecondaryTaskbar, &context) {
+            if let Some(index) = layout.monitor_for(shell.window_pos(bar)) {
+                let monitor = &layout.monitors[index];
+                let tray = Tray::secondary(
+                    shell,
+                    &self.rules,
+                    bar,
+                    monitor,
+                    start_on(monitor),
+                    self.corner_size,
+                    &context,
+                );
+                screens[index].get_or_insert(tray.geometry);
+                secondary_trays.push(tray);
+            }
+        }
+
+        // Monitors without a taskbar still get the corners a bottom taskbar would have.
+        self.screens = screens
+            .into_iter()
+            .zip(&layout.monitors)
+            .map(|(screen, monitor)| {
+                screen.unwrap_or_else(|| {
+                    TrayGeometry::new(
+                        TrayOrientation::Bottom,
+                        monitor.dimensions(),
+                        start_on(monitor),
+                        100,
+                    )
+                    .with_corner_size(self.corner_size)
+                    .with_scale(monitor.scale())
+                })
+            })
+            .collect();
+        self.secondary_trays = secondary_trays;
+        self.layout = layout;
+    }
+    /// `set_foreground` and `check_menus` in one go, for when nothing is waiting on the compositor.
+    pub fn foreground_changed(&mut self, window: WindowId) -> bool {
+        self.set_foreground(window) && self.check_menus()
     }
-    pub fn foreground_changed(&mut self, window: HWND) -> bool {
+    /// Notes the new foreground window. Returns true if that may have closed the start
+    /// menu or the overflow, which `check_menus` can tell after `COMPOSITOR_DELAY`.
+    pub fn set_foreground(&mut self, window: WindowId) -> bool {
         self.last_window = window;
+        self.excluded = self.exclusions.excludes(&self.backend, window);
 
-        if self.is_window(window, "Windows.UI.Core.CoreWindow", "Cortana")
-            && self.tray.start_menu != window
-        {
+        let context = self.context();
+        let is = |role| self.rules.is(&self.backend, window, role, &context);
+        if self.tray.start_menu != window && is(Role::StartMenu) {
             self.tray.start_menu = window;
+        } else if self.tray.search != window && is(Role::Search) {
+            self.tray.search = window;
         }
 
-        if self.tray.start_menu == window {
-            self.tray.show();
+        if self.tray.is_menu(window) {
+            self.tray.show(&mut self.backend);
             self.tray.startmenu_showing = true;
         } else if self.tray.icon_overflow == window {
             self.tray.overflow_showing = true;
         } else if self.tray.overflow_showing || self.tray.startmenu_showing {
-            std::thread::sleep(std::time::Duration::from_millis(50));
-            unsafe { DwmFlush() };
-            if self.tray.overflow_showing {
-                let styles = unsafe { GetWindowLongW(self.tray.icon_overflow, GWL_STYLE) } as u32;
-                if styles & WS_VISIBLE == 0 {
-                    self.tray.overflow_showing = false;
-                    return true;
-                }
-            }
-            if self.tray.startmenu_showing {
-                let mut cloaked: u32 = 0;
-                unsafe {
-                    DwmGetWindowAttribute(
-                        self.tray.start_menu,
-                        DWMWA_CLOAKED,
-                        &mut cloaked as *mut u32 as *mut c_void,
-                        std::mem::size_of::<u32>() as u32,
-                    );
-                }
-                if cloaked != 0 {
-                    self.tray.startmenu_showing = false;
-                    return true;
-                }
-            }
+            return true;
         }
         false
     }
-    pub fn toggle(&mut self) -> bool {
-        self.enabled = !self.enabled;
-        self.hot_active = self.enabled;
-        self.enabled
+    /// Whether the start menu or the overflow closed since they were last seen.
+    pub fn check_menus(&mut self) -> bool {
+        if !self.tray.overflow_showing && !self.tray.startmenu_showing {
+            return false;
+        }
+        self.backend.flush();
+        if self.tray.overflow_showing && !self.backend.is_visible(self.tray.icon_overflow) {
+            self.tray.overflow_showing = false;
+            return true;
+        }
+        if self.tray.startmenu_showing && self.tray.menus_cloaked(&self.backend) {
+            self.tray.startmenu_showing = false;
+            return true;
+        }
+        false
     }
-
     fn _tray_focused(&self) -> bool {
         self.last_window == self.tray.icon_overflow
             || self.last_window == self.tray.bar
-            || self.last_window == self.tray.start_menu
-    }
-    pub fn get_actual_desktop(top_desktop: HWND) -> Result<(i32, i32, HWND, HWND), &'static str> {
-        let (width, height) = Desktop::get_window_dimensions(top_desktop);
-        let with_shell = |shell_parent: Option<HWND>| match shell_parent {
-            Some(shell_parent) => Desktop::find_child(shell_parent, "SHELLDLL_DefView")
-                .and_then(|shell_window| Some((shell_parent, shell_window))),
-            None => None,
-        };
+            || self.tray.is_menu(self.last_window)
+    }
+    /// What the rules need to know about the windows found so far.
+    pub fn context(&self) -> Context {
+        Context::new(Some(self.tray.version), (self.width, self.height))
+            .with_tray(&self.tray.geometry)
+    }
+    pub fn get_actual_desktop(
+        &self,
+        top_desktop: WindowId,
+        version: ShellVersion,
+    ) -> Result<(i32, i32, WindowId, WindowId), &'static str> {
+        let shell = &self.backend;
+        let rules = &self.rules;
+        let (width, height) = shell.window_dimensions(top_desktop);
+        let context = Context::new(Some(version), (width, height));
 
-        let (shell_parent, shell_window) = with_shell(Desktop::find_by_dimensions(
-            top_desktop,
-            "WorkerW",
-            width,
-            height,
-        ))
-        .or_else(|| with_shell(Desktop::find_window(Some("Progman"), None)))
-        .ok_or_else(|| "Could not find shell window")?;
+        let (shell_parent, shell_window) = rules
+            .find_all(shell, Role::DesktopHost, &context)
+            .into_iter()
+            .find_map(|shell_parent| {
+                rules
+                    .find_in(shell, Role::DesktopView, shell_parent, &context)
+                    .map(|shell_window| (shell_parent, shell_window))
+            })
+            .ok_or("Could not find shell window")?;
 
         Ok((width, height, shell_window, shell_parent))
     }
-    pub fn find_by_dimensions(
-        parent: HWND,
-        class_name: &str,
-        width: i32,
-        height: i32,
-    ) -> Option<HWND> {
-        let search = win32_string(class_name).as_ptr();
-        let mut desktop_window = unsafe { FindWindowW(search, null_mut()) };
 
-        while !desktop_window.is_null() {
-            let (_width, _height) = Desktop::get_window_dimensions(desktop_window);
-            if _width == width && _height == height {
-                return Some(desktop_window);
+    /// Carries out an engine command. Timers are left to the caller.
+    pub fn execute(&mut self, command: Command) {
+        match command {
+            Command::ShowTray => self.show_tray(),
+            Command::HideTray => {
+                if !self.hide_tray() && self.shell_changed() {
+                    warn!("Desktop handle was invalid. Got new one and trying again");
+                    self.hide_tray();
+                }
             }
-            desktop_window = unsafe { FindWindowExW(parent, desktop_window, search, null_mut()) };
+            Command::Run(action) => action.perform(&mut self.backend),
+            Command::StartTimer(..) => {}
         }
-        None
     }
-    pub fn find_child(parent: HWND, class_name: &str) -> Option<HWND> {
-        let search = win32_string(class_name).as_ptr();
-        let child = unsafe { FindWindowExW(parent, null_mut(), search, null_mut()) };
-
-        if !child.is_null() {
-            Some(child)
-        } else {
-            None
+    pub fn show_tray(&mut self) {
+        self.tray.show(&mut self.backend);
+        for tray in &mut self.secondary_trays {
+            tray.show(&mut self.backend);
         }
     }
-    pub fn find_window(class_name: Option<&str>, title: Option<&str>) -> Option<HWND> {
-        let class_name = match class_name {
-            Some(name) => win32_string(name).as_ptr(),
-            None => null_mut(),
-        };
-        let title = match title {
-            Some(title_str) => win32_string(title_str).as_ptr(),
-            None => null_mut(),
-        };
-        let hwnd = unsafe { FindWindowW(class_name, title) };
-
-        if hwnd.is_null() {
-            None
-        } else {
-            Some(hwnd)
+    /// Hides every taskbar, returns false if the primary one is gone.
+    pub fn hide_tray(&mut self) -> bool {
+        for tray in &mut self.secondary_trays {
+            tray.hide(&mut self.backend);
         }
+        self.tray.hide(&mut self.backend)
     }
-    pub fn find_by_position(
-        parent: HWND,
-        class_name: &str,
-        (top, bottom, left, right): (Option<i32>, Option<i32>, Option<i32>, Option<i32>),
-    ) -> Option<HWND> {
-
-        let search = win32_string(class_name).as_ptr();
-        let mut desktop_window = unsafe { FindWindowW(search, null_mut()) };
-
-        while !desktop_window.is_null() {
-            let (t, b, l, r) = Desktop::get_window_pos(desktop_window);
-            #[cfg(debug_assertions)]
-            unsafe {
-                super::desktop._debug_window(desktop_window)
-            };
-            let top_match = match top {
-                Some(pos) => pos == t,
-                None => true,
-            };
-            let bottom_match = match bottom {
-                Some(pos) => pos == b,
-                None => true,
-            };
-            let left_match = match left {
-                Some(pos) => pos == l,
-                None => true,
-            };
-            let right_match = match right {
-                Some(pos) => pos == r,
-                None => true,
-            };
-
-            if top_match && bottom_match && left_match && right_match {
-                return Some(desktop_window);
-            }
-
-            desktop_window = unsafe { FindWindowExW(parent, desktop_window, search, null_mut()) };
-        }
-        None
+    pub fn open_start_menu(&mut self) {
+        CornerAction::StartMenu.perform(&mut self.backend);
     }
-
-    pub unsafe fn open_start_menu(&self) {
-        keybd_event(VK_LWIN as u8, 0, 0, 0);
-        keybd_event(VK_LWIN as u8, 0, KEYEVENTF_KEYUP, 0);
-    }
-
-    pub unsafe fn open_desktop_selector(&self) {
-        keybd_event(VK_LWIN as u8, 0, 0, 0);
-        keybd_event(VK_TAB as u8, 0, 0, 0);
-        keybd_event(VK_TAB as u8, 0, KEYEVENTF_KEYUP, 0);
-        keybd_event(VK_LWIN as u8, 0, KEYEVENTF_KEYUP, 0);
+    pub fn open_desktop_selector(&mut self) {
+        CornerAction::DesktopSelector.perform(&mut self.backend);
     }
+    /// Whether the foreground window has its monitor to itself, see `fullscreen`.
     pub fn full_screen_program(&self) -> bool {
         if self.last_window == self.shell_parent || self.last_window == self.shell_window {
             false
         } else {
-            let (top, bottom, left, right) = Desktop::get_window_pos(self.last_window);
-            top == 0 && left == 0 && bottom == self.height && right == self.width
+            let window = WindowFacts::of(&self.backend, self.last_window, &self.layout);
+            fullscreen::classify(&window, self.backend.notification_state()).is_full_screen()
         }
     }
-    pub unsafe fn shell_changed(&mut self) -> bool {
-        if IsWindow(self.shell_window) == 0 || GetParent(self.shell_window) != self.shell_parent {
+    /// Explorer restarted and broadcast `TaskbarCreated`: finds its new windows right away
+    /// instead of waiting for a stale handle to turn up. Follow with `Event::ShellRestarted`.
+    pub fn shell_restarted(&mut self) -> Result<(), &'static str> {
+        self.refresh()?;
+        info!("Explorer restarted, found the new taskbar");
+        Ok(())
+    }
+    pub fn shell_changed(&mut self) -> bool {
+        if !self.backend.is_window(self.shell_window)
+            || self.backend.parent(self.shell_window) != Some(self.shell_parent)
+        {
             match self.refresh() {
                 Ok(_) => true,
                 Err(e) => {
-                    println!("Could not refresh window handles: {}", e);
+                    error!("Could not refresh window handles: {}", e);
                     false
                 }
             }
@@ -416,75 +454,69 @@ impl Desktop {
         }
     }
 
-    pub fn get_window_dimensions(handle: HWND) -> (i32, i32) {
-        let (top, bottom, left, right) = Desktop::get_window_pos(handle);
-        (right - left, bottom - top)
-    }
-    pub fn get_window_pos(handle: HWND) -> (i32, i32, i32, i32) {
-        let mut dimensions = RECT {
-            top: 0,
-            left: 0,
-            right: 0,
-            bottom: 0,
-        };
-        unsafe {
-            GetWindowRect(handle, &mut dimensions as *mut RECT);
-            (
-                dimensions.top,
-                dimensions.bottom,
-                dimensions.left,
-                dimensions.right,
-            )
-        }
-    }
-    pub unsafe fn _debug_cur_window(&self) {
-        let current = GetForegroundWindow();
-        self._debug_window(current);
+    pub fn _debug_cur_window(&self) {
+        self._debug_window(self.backend.foreground_window());
     }
-    fn is_window(&self, window: HWND, class_name: &str, title: &str) -> bool {
-        self._get_class_name(window).unwrap_or("".to_string()) == class_name
-            && self._get_title_name(window).unwrap_or("".to_string()) == title
-    }
-    fn _get_class_name(&self, window: HWND) -> Option<String> {
-        let class_buffer: Vec<u16> = vec![0; 255];
-        let char_count = unsafe { GetClassNameW(window, class_buffer.as_ptr(), 255) } as usize;
-        if char_count != 0 {
-            String::from_utf16(&class_buffer[0..char_count]).ok()
-        } else {
-            None
-        }
-    }
-    fn _get_title_name(&self, window: HWND) -> Option<String> {
-        let mut class_buffer: Vec<u16> = vec![0; 255];
-        let char_count = unsafe { GetWindowTextW(window, class_buffer.as_mut_ptr(), 255) } as usize;
-        if char_count != 0 {
-            String::from_utf16(&class_buffer[0..char_count]).ok()
-        } else {
-            None
+    /// Logs what the shell knows about `window` at debug level.
+    pub fn _debug_window(&self, window: WindowId) {
+        if !log_enabled!(Level::Debug) {
+            return;
         }
-    }
-    pub unsafe fn _debug_window(&self, window: HWND) {
-        let class_name = self._get_class_name(window);
-        let title = self._get_title_name(window).unwrap_or("".to_owned());
-        let extended_styles = GetWindowLongW(window, GWL_EXSTYLE);
+        let class_name = self.backend.class_name(window);
+        let title = self.backend.title(window).unwrap_or_default();
+        let extended_styles = self.backend.ex_style(window);
 
         match class_name {
             Some(name) => {
-                let (t, b, l, r) = Desktop::get_window_pos(window);
-                let (w, h) = Desktop::get_window_dimensions(window);
-                if window == self.shell_parent {
-                    print!("[SHELLP] ");
+                let (t, b, l, r) = self.backend.window_pos(window);
+                let (w, h) = self.backend.window_dimensions(window);
+                let tag = if window == self.shell_parent {
+                    "[SHELLP] ".to_string()
                 } else if window == self.shell_window {
-                    print!("[SHELL] ");
+                    "[SHELL] ".to_string()
                 } else if name == "WorkerW" {
-                    print!("[WDESKHANDL: {:?}]", self.shell_parent);
-                }
-                println!(
-                    "class: '{}' | title: '{}' | handle: {:?} | style: {} | {}x{} | top: {}, bottom: {}, left: {}, right: {}",
-                    name, title, window, extended_styles, w, h, t, b, l, r
+                    format!("[WDESKHANDL: {:?}]", self.shell_parent)
+                } else {
+                    String::new()
+                };
+                debug!(
+                    "{}class: '{}' | title: '{}' | handle: {:?} | style: {} | {}x{} | top: {}, bottom: {}, left: {}, right: {}",
+                    tag, name, title, window, extended_styles, w, h, t, b, l, r
                 );
             }
-            None => println!("Couldn't get classname"),
+            None => debug!("Couldn't get classname of {:?}", window),
+        }
+    }
+}
+
+impl<B: ShellBackend> ShellState for Desktop<B> {
+    fn geometry(&self) -> &TrayGeometry {
+        &self.tray.geometry
+    }
+    fn locate(&self, x: i32, y: i32) -> (&TrayGeometry, i32, i32) {
+        match self.layout.to_local(x, y) {
+            Some((index, x, y)) if index < self.screens.len() => (&self.screens[index], x, y),
+            _ => (&self.tray.geometry, x, y),
+        }
+    }
+    fn full_screen_program(&mut self) -> bool {
+        if !Desktop::full_screen_program(self) {
+            false
+        } else if self.shell_changed() {
+            // full screen program && that full screen program might be new shell
+            warn!("Desktop handle was invalid. Got new one and trying again");
+            Desktop::full_screen_program(self)
+        } else {
+            true
         }
     }
+    fn excluded(&self) -> bool {
+        self.excluded
+    }
+    fn is_tray_open(&self) -> bool {
+        self.tray.is_tray_open(&self.backend)
+    }
+    fn start_menu_focused(&self) -> bool {
+        self.tray.is_menu(self.last_window)
+    }
 }
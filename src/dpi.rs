@@ -0,0 +1,75 @@
+/*!
+Converting between physical pixels and device-independent ones.
+
+win-gnome declares itself per-monitor DPI aware, so the mouse hook, window
+rects and monitor rects all come in physical pixels of whatever monitor they
+are on. Sizes meant to look the same everywhere, like the trigger pressure or
+a corner copied from the start button of another monitor, are kept in
+device-independent pixels (DIPs), a pixel at 100% scaling or 96 DPI, and
+converted with the [`Scale`] of the monitor they end up on.
+
+Conversions round to the nearest pixel, halves away from zero, the way Win32's
+`MulDiv` does, so a size converted there and back stays put at common scales.
+!*/
+
+/// DPI at 100% scaling.
+pub const DEFAULT_DPI: u32 = 96;
+
+/// `value * numerator / denominator`, rounded to the nearest integer.
+pub fn mul_div(value: i32, numerator: i32, denominator: i32) -> i32 {
+    if denominator == 0 {
+        return value;
+    }
+    let exact = f64::from(value) * f64::from(numerator) / f64::from(denominator);
+    exact.round() as i32
+}
+
+/// How many physical pixels a monitor packs into a DIP.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Scale {
+    pub dpi: u32,
+}
+
+impl Scale {
+    /// A DPI of 0, which some drivers report, counts as 100%.
+    pub const fn new(dpi: u32) -> Scale {
+        Scale {
+            dpi: if dpi == 0 { DEFAULT_DPI } else { dpi },
+        }
+    }
+    pub const fn default() -> Scale {
+        Scale::new(DEFAULT_DPI)
+    }
+    /// The scale as Windows shows it in display settings, like 150.
+    pub fn percent(self) -> u32 {
+        mul_div(self.dpi as i32, 100, DEFAULT_DPI as i32) as u32
+    }
+    pub fn factor(self) -> f64 {
+        f64::from(self.dpi) / f64::from(DEFAULT_DPI)
+    }
+    pub fn to_physical(self, dips: i32) -> i32 {
+        mul_div(dips, self.dpi as i32, DEFAULT_DPI as i32)
+    }
+    pub fn to_dips(self, pixels: i32) -> i32 {
+        mul_div(pixels, DEFAULT_DPI as i32, self.dpi as i32)
+    }
+    pub fn size_to_physical(self, (width, height): (i32, i32)) -> (i32, i32) {
+        (self.to_physical(width), self.to_physical(height))
+    }
+    pub fn size_to_dips(self, (width, height): (i32, i32)) -> (i32, i32) {
+        (self.to_dips(width), self.to_dips(height))
+    }
+    /// A length measured on a monitor at `from`, for one at this scale.
+    pub fn rescale(self, pixels: i32, from: Scale) -> i32 {
+        mul_div(pixels, self.dpi as i32, from.dpi as i32)
+    }
+    pub fn rescale_size(self, (width, height): (i32, i32), from: Scale) -> (i32, i32) {
+        (self.rescale(width, from), self.rescale(height, from))
+    }
+}
+
+impl Default for Scale {
+    fn default() -> Scale {
+        Scale::default()
+    }
+}
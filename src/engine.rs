@@ -0,0 +1,325 @@
+/*!
+Hot corner state machine.
+
+The engine knows nothing about Win32. Hooks translate what they observe into an
+[`Event`], the engine decides what should happen and answers with a list of
+[`Command`]s for the caller to carry out.
+
+Anything the engine needs to ask about the live shell goes through
+[`ShellState`], which tests can implement with plain fields.
+!*/
+
+pub use crate::action::CornerAction;
+use crate::corners::CornerMap;
+use crate::geometry::TrayGeometry;
+use crate::region::Regions;
+use crate::trigger::{Decision, TriggerFilter, TriggerSettings};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long the corner stays quiet after firing.
+pub const CORNER_DELAY: Duration = Duration::from_millis(300);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayAction {
+    Hide,
+    NoHide,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    MouseMoved {
+        x: i32,
+        y: i32,
+    },
+    /// The foreground window changed. `menu_closed` is set when this closed the
+    /// start menu or the notification overflow.
+    ForegroundChanged {
+        menu_closed: bool,
+    },
+    /// Switches the hot corner on or off.
+    Hotkey,
+    /// Does what the start corner does, wherever the mouse is.
+    TriggerCorner,
+    /// Shows the hidden taskbar, or hides it again if it is peeking already.
+    PeekTaskbar,
+    /// Explorer started over with a new, visible taskbar and the shell state has caught up.
+    ShellRestarted,
+    TimerElapsed(TimerKind),
+    Close,
+}
+
+/// What a timer was started for, so one still queued can't stand in for another.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TimerKind {
+    /// Checks whether the cursor dwelled in the corner long enough, see `trigger`.
+    Dwell,
+    /// Ends the `CORNER_DELAY` after the corner fired.
+    Debounce,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    ShowTray,
+    HideTray,
+    Run(CornerAction),
+    /// Deliver `Event::TimerElapsed` of the same kind once the duration has passed.
+    StartTimer(TimerKind, Duration),
+}
+
+/// Questions the engine asks about the shell while handling an event.
+pub trait ShellState {
+    /// Geometry of the primary monitor's tray.
+    fn geometry(&self) -> &TrayGeometry;
+    /// Geometry of the monitor under `(x, y)`, and the point in that monitor's coordinates.
+    fn locate(&self, x: i32, y: i32) -> (&TrayGeometry, i32, i32) {
+        (self.geometry(), x, y)
+    }
+    /// Whether the foreground window covers the whole screen.
+    ///
+    /// Takes `&mut self` so implementations can refresh stale handles first.
+    fn full_screen_program(&mut self) -> bool;
+    fn is_tray_open(&self) -> bool;
+    fn start_menu_focused(&self) -> bool;
+    /// Whether the foreground window is on the exclusion list, see `exclude`.
+    fn excluded(&self) -> bool {
+        false
+    }
+}
+
+pub struct HotCornerEngine {
+    /// What the start corner does unless `corners` says otherwise.
+    pub corner_action: CornerAction,
+    pub corners: CornerMap,
+    /// Shapes of the corners and where they never fire.
+    pub regions: Regions,
+    pub tray_action: TrayAction,
+    pub trigger: TriggerFilter,
+    pub enabled: bool,
+    pub hot_active: bool,
+    /// The taskbar was shown by `Event::PeekTaskbar` and stays until it is pressed again.
+    pub peeking: bool,
+    delayed: bool,
+    last_x: i32,
+    last_y: i32,
+    /// Milliseconds, as given to `handle_at`.
+    now: u64,
+}
+
+impl HotCornerEngine {
+    pub const fn new(corner_action: CornerAction, tray_action: TrayAction) -> HotCornerEngine {
+        HotCornerEngine {
+            corner_action,
+            corners: CornerMap::new(),
+            regions: Regions::new(),
+            tray_action,
+            trigger: TriggerFilter::new(TriggerSettings::default()),
+            enabled: true,
+            hot_active: true,
+            peeking: false,
+            delayed: false,
+            last_x: 0,
+            last_y: 0,
+            now: 0,
+        }
+    }
+    pub const fn default() -> HotCornerEngine {
+        HotCornerEngine::new(CornerAction::StartMenu, TrayAction::Hide)
+    }
+    /// Last cursor position seen by the engine.
+    pub fn last_position(&self) -> (i32, i32) {
+        (self.last_x, self.last_y)
+    }
+    /// Switches between hiding and leaving the tray alone, returns what that takes.
+    pub fn set_tray_action(&mut self, tray_action: TrayAction) -> Vec<Command> {
+        if tray_action == self.tray_action {
+            return vec![];
+        }
+        self.tray_action = tray_action;
+        self.hot_active = self.enabled;
+
+        match tray_action {
+            TrayAction::Hide if self.enabled => vec![Command::HideTray],
+            TrayAction::Hide => vec![],
+            TrayAction::NoHide => vec![Command::ShowTray],
+        }
+    }
+    /// Commands to run once the shell has been found.
+    pub fn start(&mut self) -> Vec<Command> {
+        self.hot_active = true;
+        if self.tray_action == TrayAction::Hide {
+            vec![Command::HideTray]
+        } else {
+            vec![]
+        }
+    }
+    /// Handles an event that happened `time` milliseconds after some fixed point.
+    ///
+    /// Dwell times are measured with these, `handle` reuses the last time given.
+    pub fn handle_at(
+        &mut self,
+        time: u64,
+        event: Event,
+        shell: &mut impl ShellState,
+    ) -> Vec<Command> {
+        self.now = self.now.max(time);
+        self.handle(event, shell)
+    }
+    pub fn handle(&mut self, event: Event, shell: &mut impl ShellState) -> Vec<Command> {
+        let mut commands = vec![];
+
+        match event {
+            Event::MouseMoved { x, y } => {
+                if self.enabled {
+                    self.mouse_move(x, y, true, shell, &mut commands);
+                }
+            }
+            Event::ForegroundChanged { menu_closed } => {
+                if self.enabled
+                    && menu_closed
+                    && !shell.excluded()
+                    && !HotCornerEngine::is_tray_region(shell, self.last_x, self.last_y)
+                {
+                    self.on_leaving_corner(true, shell, &mut commands);
+                }
+            }
+            Event::TimerElapsed(kind) => {
+                if kind == TimerKind::Debounce {
+                    self.delayed = false;
+                }
+                if self.enabled {
+                    self.mouse_move(self.last_x, self.last_y, false, shell, &mut commands);
+                }
+            }
+            Event::Hotkey => {
+                self.enabled = !self.enabled;
+                self.hot_active = self.enabled;
+                self.peeking = false;
+                if self.tray_action == TrayAction::Hide {
+                    commands.push(if self.enabled {
+                        Command::HideTray
+                    } else {
+                        Command::ShowTray
+                    });
+                }
+            }
+            Event::TriggerCorner => {
+                if self.enabled {
+                    let action = self.corner_action.clone();
+                    self.on_hot_corner(action, shell, &mut commands);
+                }
+            }
+            Event::PeekTaskbar => {
+                if self.enabled && self.tray_action == TrayAction::Hide {
+                    self.peeking = !self.peeking;
+                    commands.push(if self.peeking {
+                        Command::ShowTray
+                    } else {
+                        Command::HideTray
+                    });
+                }
+            }
+            Event::ShellRestarted => {
+                self.hot_active = self.enabled;
+                self.peeking = false;
+                if self.enabled && self.tray_action == TrayAction::Hide {
+                    commands.push(Command::HideTray);
+                }
+            }
+            Event::Close => {
+                self.enabled = false;
+                commands.push(Command::ShowTray);
+            }
+        }
+        commands
+    }
+
+    /// `moved` is false when re-checking the last position after a timer.
+    fn mouse_move(
+        &mut self,
+        x: i32,
+        y: i32,
+        moved: bool,
+        shell: &mut impl ShellState,
+        commands: &mut Vec<Command>,
+    ) {
+        self.last_x = x;
+        self.last_y = y;
+
+        if self.delayed || shell.excluded() {
+            return;
+        }
+
+        let (geometry, local_x, local_y) = shell.locate(x, y);
+        let hit = self
+            .corners
+            .hit_in(
+                geometry,
+                &self.regions,
+                &self.corner_action,
+                local_x,
+                local_y,
+            )
+            .map(|(_, action)| action.clone());
+        let tray_region = geometry.is_tray_region(local_x, local_y);
+        let overshoot = geometry.overshoot(local_x, local_y);
+
+        if self.hot_active {
+            self.trigger.scale = geometry.scale;
+            let decision = if moved {
+                self.trigger
+                    .sample(self.now, x, y, hit.is_some(), overshoot)
+            } else {
+                self.trigger.tick(self.now, hit.is_some())
+            };
+            match (hit, decision) {
+                (Some(action), Decision::Fire) => self.on_hot_corner(action, shell, commands),
+                (_, Decision::Wait(delay)) => {
+                    commands.push(Command::StartTimer(TimerKind::Dwell, delay))
+                }
+                _ => {}
+            }
+        } else if hit.is_none() && !self.peeking && !shell.start_menu_focused() && !tray_region {
+            self.on_leaving_corner(false, shell, commands);
+        }
+    }
+    fn is_tray_region(shell: &impl ShellState, x: i32, y: i32) -> bool {
+        let (geometry, x, y) = shell.locate(x, y);
+        geometry.is_tray_region(x, y)
+    }
+    fn on_hot_corner(
+        &mut self,
+        action: CornerAction,
+        shell: &mut impl ShellState,
+        commands: &mut Vec<Command>,
+    ) {
+        if shell.full_screen_program() {
+            return;
+        }
+        self.hot_active = false;
+        self.peeking = false;
+
+        if self.tray_action == TrayAction::Hide {
+            commands.push(Command::ShowTray);
+        }
+        commands.push(Command::Run(action));
+        self.delayed = true;
+        commands.push(Command::StartTimer(TimerKind::Debounce, CORNER_DELAY));
+    }
+    fn on_leaving_corner(
+        &mut self,
+        force: bool,
+        shell: &mut impl ShellState,
+        commands: &mut Vec<Command>,
+    ) {
+        if self.tray_action == TrayAction::Hide {
+            if force || !shell.is_tray_open() {
+                commands.push(Command::HideTray);
+                self.hot_active = true;
+            }
+        } else {
+            self.hot_active = true;
+        }
+    }
+}
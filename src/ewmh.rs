@@ -0,0 +1,324 @@
+/*!
+Desktops run by an EWMH window manager.
+
+There is no start button, start menu or Explorer on X11, so [`EwmhDesktop`]
+stands in for [`Desktop`](crate::desktop::Desktop) there. Panels like polybar,
+tint2 or xfce4-panel take the taskbar's place: each [`Dock`] is unmapped when
+the tray would be hidden, with its strut cleared so maximized windows can use
+the space, and put back when the hot corner fires. The struts are written to
+the [`DirtyMarker`](crate::recovery::DirtyMarker) before the docks are hidden,
+so a run that gets killed doesn't take them along. Docks mapped later are
+found as they appear, and the docks stay up while the pointer is over one or
+a menu of one is open.
+
+A monitor's corners follow the first dock on it the way they follow a Windows
+taskbar, sized by [`CORNER_SIZE`] and the dock's thickness. Monitors without a
+dock get the corners of a bottom taskbar.
+!*/
+
+use crate::engine::{Command, ShellState};
+use crate::exclude::ExcludeList;
+use crate::fullscreen::{self, WindowFacts};
+use crate::geometry::{CornerSize, TrayGeometry, TrayOrientation};
+use crate::monitor::{Layout, Monitor};
+use crate::recovery::HiddenDock;
+use crate::shell::{DockBackend, Strut, WindowId};
+
+/// Size of the hot corner at 100% sensitivity, about what a Windows start button is,
+/// in device-independent pixels.
+pub const CORNER_SIZE: (i32, i32) = (48, 40);
+
+pub struct Dock {
+    pub window: WindowId,
+    /// The strut the dock had before it was hidden.
+    pub strut: Option<Strut>,
+    pub showing: bool,
+    /// `(top, bottom, left, right)` when the docks were last read.
+    pub pos: (i32, i32, i32, i32),
+}
+
+impl Dock {
+    pub const fn new(window: WindowId) -> Dock {
+        Dock {
+            window,
+            strut: None,
+            showing: true,
+            pos: (0, 0, 0, 0),
+        }
+    }
+    fn contains(&self, x: i32, y: i32) -> bool {
+        let (top, bottom, left, right) = self.pos;
+        self.showing && x >= left && x < right && y >= top && y < bottom
+    }
+    pub fn hide(&mut self, shell: &mut impl DockBackend) -> bool {
+        if !self.showing {
+            return true;
+        }
+        // Unmapped first, a dock that stays up keeps its strut
+        let strut = shell.strut(self.window);
+        if !shell.set_visible(self.window, false) {
+            return false;
+        }
+        self.strut = strut;
+        if strut.is_some() {
+            shell.set_strut(self.window, None);
+        }
+        self.showing = false;
+        true
+    }
+    pub fn show(&mut self, shell: &mut impl DockBackend) -> bool {
+        if self.showing {
+            return true;
+        }
+        self.showing = shell.set_visible(self.window, true);
+        if let Some(strut) = self.strut.filter(|_| self.showing) {
+            shell.set_strut(self.window, Some(strut));
+        }
+        self.showing
+    }
+    /// The dock with the strut it has, or had before it was hidden.
+    pub fn hidden(&self, shell: &impl DockBackend) -> HiddenDock {
+        HiddenDock {
+            window: self.window,
+            strut: if self.showing {
+                shell.strut(self.window)
+            } else {
+                self.strut
+            },
+        }
+    }
+    /// Corners for `monitor` with this dock along one of its edges.
+    fn geometry(
+        &self,
+        shell: &impl DockBackend,
+        monitor: &Monitor,
+        corner_size: CornerSize,
+    ) -> TrayGeometry {
+        let (width, height) = monitor.dimensions();
+        let (top, bottom, left, right) = monitor.to_local_pos(shell.window_pos(self.window));
+        let orientation = TrayGeometry::get_orientation(width, height, (top, bottom, left, right));
+        let (corner_width, corner_height) = monitor.scale().size_to_physical(CORNER_SIZE);
+        let corner = match orientation {
+            TrayOrientation::Bottom | TrayOrientation::Top => {
+                (corner_width, corner_height.max(bottom - top))
+            }
+            TrayOrientation::Left | TrayOrientation::Right => {
+                (corner_width.max(right - left), corner_height)
+            }
+        };
+        TrayGeometry::new(orientation, (width, height), corner, 100)
+            .with_corner_size(corner_size)
+            .with_scale(monitor.scale())
+    }
+}
+
+pub struct EwmhDesktop<B: DockBackend> {
+    pub backend: B,
+    /// Size of the hot corner on every monitor.
+    pub corner_size: CornerSize,
+    pub last_window: WindowId,
+    pub docks: Vec<Dock>,
+    pub layout: Layout,
+    /// Hot corner geometry for each monitor in `layout`, in that monitor's coordinates.
+    pub screens: Vec<TrayGeometry>,
+    /// Geometry of the primary monitor.
+    pub geometry: TrayGeometry,
+    pub exclusions: ExcludeList,
+    /// Whether `last_window` is on the exclusion list.
+    pub excluded: bool,
+    /// Whether the docks are meant to be hidden, new ones included.
+    pub hidden: bool,
+    /// Mapped windows transient for a dock, like its menus.
+    pub popups: Vec<WindowId>,
+    /// Last position of the pointer, for telling whether it is over a dock.
+    pub pointer: (i32, i32),
+}
+
+impl<B: DockBackend> EwmhDesktop<B> {
+    pub fn new(backend: B, sensitivity: i32) -> EwmhDesktop<B> {
+        let mut desktop = EwmhDesktop {
+            backend,
+            corner_size: CornerSize::Button(sensitivity),
+            last_window: WindowId::NULL,
+            docks: Vec::new(),
+            layout: Layout::new(Vec::new()),
+            screens: Vec::new(),
+            geometry: TrayGeometry::default(),
+            exclusions: ExcludeList::new(),
+            excluded: false,
+            hidden: false,
+            popups: Vec::new(),
+            pointer: (0, 0),
+        };
+        desktop.refresh();
+        desktop
+    }
+    /// Reads the monitor layout, docks and the active window again.
+    ///
+    /// Docks already hidden are kept, they are unmapped so they can't be told apart by position.
+    pub fn refresh(&mut self) {
+        let mut monitors = self.backend.monitors();
+        if monitors.is_empty() {
+            let (width, height) = self
+                .backend
+                .window_dimensions(self.backend.desktop_window());
+            monitors = Layout::single(width, height).monitors;
+        }
+        let layout = Layout::new(monitors);
+
+        let hidden: Vec<WindowId> = self
+            .docks
+            .iter()
+            .filter(|dock| !dock.showing)
+            .map(|dock| dock.window)
+            .collect();
+        let backend = &self.backend;
+        self.docks
+            .retain(|dock| !dock.showing && backend.is_window(dock.window));
+        for window in self.backend.docks() {
+            if !hidden.contains(&window) {
+                let mut dock = Dock::new(window);
+                dock.pos = self.backend.window_pos(window);
+                self.docks.push(dock);
+            }
+        }
+
+        let mut screens: Vec<Option<TrayGeometry>> = vec![None; layout.monitors.len()];
+        for dock in &self.docks {
+            if let Some(index) = layout.monitor_for(self.backend.window_pos(dock.window)) {
+                let geometry =
+                    dock.geometry(&self.backend, &layout.monitors[index], self.corner_size);
+                screens[index].get_or_insert(geometry);
+            }
+        }
+        self.screens = screens
+            .into_iter()
+            .zip(&layout.monitors)
+            .map(|(screen, monitor)| {
+                screen.unwrap_or_else(|| {
+                    TrayGeometry::new(
+                        TrayOrientation::Bottom,
+                        monitor.dimensions(),
+                        monitor.scale().size_to_physical(CORNER_SIZE),
+                        100,
+                    )
+                    .with_corner_size(self.corner_size)
+                    .with_scale(monitor.scale())
+                })
+            })
+            .collect();
+        self.layout = layout;
+        self.geometry = self
+            .layout
+            .primary()
+            .map_or(TrayGeometry::default(), |primary| self.screens[primary]);
+        let window = self.backend.foreground_window();
+        self.foreground_changed(window);
+    }
+    pub fn set_sensitivity(&mut self, sensitivity: i32) {
+        self.set_corner_size(CornerSize::Button(sensitivity));
+    }
+    pub fn set_corner_size(&mut self, corner_size: CornerSize) {
+        self.corner_size = corner_size;
+        self.geometry = self.geometry.with_corner_size(corner_size);
+        for screen in &mut self.screens {
+            *screen = screen.with_corner_size(corner_size);
+        }
+    }
+    /// Replaces the exclusion list and checks the active window against it.
+    pub fn set_exclusions(&mut self, exclusions: ExcludeList) {
+        self.exclusions = exclusions;
+        self.excluded = self.exclusions.excludes(&self.backend, self.last_window);
+    }
+    pub fn foreground_changed(&mut self, window: WindowId) {
+        self.last_window = window;
+        self.excluded = self.exclusions.excludes(&self.backend, window);
+    }
+    pub fn pointer_moved(&mut self, x: i32, y: i32) {
+        self.pointer = (x, y);
+    }
+    /// Keeps track of the docks' menus, returns whether `window` is a dock not seen before.
+    pub fn window_mapped(&mut self, window: WindowId) -> bool {
+        let owner = self.backend.transient_for(window);
+        if owner.is_some_and(|owner| self.docks.iter().any(|dock| dock.window == owner)) {
+            if !self.popups.contains(&window) {
+                self.popups.push(window);
+            }
+            return false;
+        }
+        !self.docks.iter().any(|dock| dock.window == window) && self.backend.is_dock(window)
+    }
+    pub fn window_unmapped(&mut self, window: WindowId) {
+        self.popups.retain(|popup| *popup != window);
+    }
+    /// Carries out an engine command. Timers are left to the caller.
+    pub fn execute(&mut self, command: Command) {
+        match command {
+            Command::Run(action) => action.perform(&mut self.backend),
+            Command::ShowTray => {
+                self.show_docks();
+            }
+            Command::HideTray => self.hide_docks(),
+            Command::StartTimer(..) => {}
+        }
+    }
+    /// Returns whether every dock is back.
+    pub fn show_docks(&mut self) -> bool {
+        self.hidden = false;
+        let mut shown = true;
+        for dock in &mut self.docks {
+            shown &= dock.show(&mut self.backend);
+        }
+        shown
+    }
+    /// What `hide_docks` is about to take away, for the recovery marker.
+    pub fn hidden_docks(&self) -> Vec<HiddenDock> {
+        self.docks
+            .iter()
+            .map(|dock| dock.hidden(&self.backend))
+            .collect()
+    }
+    pub fn hide_docks(&mut self) {
+        self.hidden = true;
+        for dock in &mut self.docks {
+            dock.hide(&mut self.backend);
+        }
+        // Docks that went away don't need hiding or restoring any more
+        let backend = &self.backend;
+        self.docks.retain(|dock| backend.is_window(dock.window));
+    }
+    /// Whether the active window covers the whole monitor it is on.
+    pub fn full_screen_program(&self) -> bool {
+        if self.last_window.is_null() || self.last_window == self.backend.desktop_window() {
+            return false;
+        }
+        let window = WindowFacts::of(&self.backend, self.last_window, &self.layout);
+        fullscreen::classify(&window, self.backend.notification_state()).is_full_screen()
+    }
+}
+
+impl<B: DockBackend> ShellState for EwmhDesktop<B> {
+    fn geometry(&self) -> &TrayGeometry {
+        &self.geometry
+    }
+    fn locate(&self, x: i32, y: i32) -> (&TrayGeometry, i32, i32) {
+        match self.layout.to_local(x, y) {
+            Some((index, x, y)) if index < self.screens.len() => (&self.screens[index], x, y),
+            _ => (&self.geometry, x, y),
+        }
+    }
+    fn full_screen_program(&mut self) -> bool {
+        EwmhDesktop::full_screen_program(self)
+    }
+    /// While the pointer is over a dock or one of its menus is open.
+    fn is_tray_open(&self) -> bool {
+        let (x, y) = self.pointer;
+        !self.popups.is_empty() || self.docks.iter().any(|dock| dock.contains(x, y))
+    }
+    fn excluded(&self) -> bool {
+        self.excluded
+    }
+    fn start_menu_focused(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,144 @@
+/*!
+Programs the hot corner stays out of.
+
+Remote desktop clients, virtual machines and CAD tools use the screen corners
+themselves. While one of their windows is in the foreground the hot corner
+doesn't fire and the taskbar is neither hidden nor shown:
+
+```toml
+[[exclude]]
+process = "mstsc.exe"
+
+[[exclude]]
+class = "VMPlayerFrame"
+
+[[exclude]]
+process = "acad.exe"
+title = "*Layout*"
+```
+
+Every field of a rule is a pattern where `*` matches any run of characters and
+`?` any single one, compared without regard to case. A window is excluded when
+it matches all the fields of any one rule.
+!*/
+
+use crate::shell::{ShellBackend, WindowId};
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+/// What the rules are matched against.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowInfo {
+    /// File name of the program, like `mstsc.exe`.
+    pub process: Option<String>,
+    pub class: Option<String>,
+    pub title: Option<String>,
+}
+
+impl WindowInfo {
+    pub fn of(shell: &impl ShellBackend, window: WindowId) -> WindowInfo {
+        WindowInfo {
+            process: shell.process_name(window),
+            class: shell.class_name(window),
+            title: shell.title(window),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExcludeRule {
+    pub process: Option<String>,
+    pub class: Option<String>,
+    pub title: Option<String>,
+}
+
+impl ExcludeRule {
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        let field = |pattern: &Option<String>, value: &Option<String>| match pattern {
+            Some(pattern) => value
+                .as_deref()
+                .is_some_and(|value| matches_pattern(pattern, value)),
+            None => true,
+        };
+        field(&self.process, &window.process)
+            && field(&self.class, &window.class)
+            && field(&self.title, &window.title)
+    }
+    /// A rule without any fields would match every window.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.process.is_none() && self.class.is_none() && self.title.is_none() {
+            Err("An exclude rule needs a process, class or title")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Rules in the order they were written.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ExcludeList {
+    pub rules: Vec<ExcludeRule>,
+}
+
+impl ExcludeList {
+    pub const fn new() -> ExcludeList {
+        ExcludeList { rules: Vec::new() }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+    /// The first rule `window` matches.
+    pub fn matches(&self, window: &WindowInfo) -> Option<&ExcludeRule> {
+        self.rules.iter().find(|rule| rule.matches(window))
+    }
+    /// Whether `window` is excluded, without asking the shell about it if there are no rules.
+    pub fn excludes(&self, shell: &impl ShellBackend, window: WindowId) -> bool {
+        if self.is_empty() || window.is_null() {
+            return false;
+        }
+        let info = WindowInfo::of(shell, window);
+        match self.matches(&info) {
+            Some(rule) => {
+                debug!("Leaving {:?} alone, it matches {:?}", info, rule);
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn validate(&self) -> Result<(), &'static str> {
+        self.rules.iter().try_for_each(ExcludeRule::validate)
+    }
+}
+
+/// Whether `text` matches `pattern`, ignoring case. `*` matches any run of characters, `?` one.
+pub fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    // Backtracks to the last `*` on a mismatch, letting it swallow one more character
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,145 @@
+/*!
+Telling full screen programs apart from ordinary windows.
+
+The hot corner stays quiet while a game, video or presentation has the screen.
+Comparing the foreground window to the primary monitor isn't enough for that:
+borderless windows sit on other monitors or a pixel short of the edge, games in
+exclusive mode may not have a window that size at all, and a maximized window
+covers the whole monitor as soon as the taskbar is hidden.
+
+[`classify`] decides from [`WindowFacts`], everything the shell can tell about
+the foreground window, and the shell's [`NotificationState`], which Windows
+sets for exclusive mode Direct3D and presentation mode.
+!*/
+
+use crate::monitor::Layout;
+use crate::shell::{ShellBackend, WindowId};
+use serde::{Deserialize, Serialize};
+
+/// Window style bits looked at, as Win32 defines them.
+pub mod style {
+    pub const WS_CHILD: u32 = 0x4000_0000;
+    pub const WS_MAXIMIZE: u32 = 0x0100_0000;
+    /// Title bar, `WS_BORDER | WS_DLGFRAME`.
+    pub const WS_CAPTION: u32 = 0x00C0_0000;
+    pub const WS_THICKFRAME: u32 = 0x0004_0000;
+}
+
+/// How far short of a monitor edge a full screen window may stop.
+pub const TOLERANCE: i32 = 2;
+
+/// What `SHQueryUserNotificationState` says about the session.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationState {
+    /// The session is locked, or there's no shell to ask.
+    NotPresent,
+    /// A full screen program that isn't a presentation, or the screen saver.
+    Busy,
+    /// A Direct3D program in exclusive mode.
+    D3dFullScreen,
+    PresentationMode,
+    AcceptsNotifications,
+    /// The first hour after setting up Windows.
+    QuietTime,
+    /// A Windows Store app is running.
+    App,
+}
+
+impl NotificationState {
+    /// From a `QUERY_USER_NOTIFICATION_STATE` value.
+    pub fn from_quns(value: u32) -> NotificationState {
+        match value {
+            1 => NotificationState::NotPresent,
+            2 => NotificationState::Busy,
+            3 => NotificationState::D3dFullScreen,
+            4 => NotificationState::PresentationMode,
+            6 => NotificationState::QuietTime,
+            7 => NotificationState::App,
+            _ => NotificationState::AcceptsNotifications,
+        }
+    }
+    pub fn is_full_screen(self) -> bool {
+        matches!(
+            self,
+            NotificationState::Busy
+                | NotificationState::D3dFullScreen
+                | NotificationState::PresentationMode
+        )
+    }
+}
+
+/// What the shell knows about a window.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowFacts {
+    /// `(top, bottom, left, right)`
+    pub pos: (i32, i32, i32, i32),
+    /// Rect of the monitor the window is mostly on.
+    pub monitor: Option<(i32, i32, i32, i32)>,
+    pub style: u32,
+    pub visible: bool,
+    pub cloaked: bool,
+}
+
+impl WindowFacts {
+    pub fn of(shell: &impl ShellBackend, window: WindowId, layout: &Layout) -> WindowFacts {
+        let pos = shell.window_pos(window);
+        WindowFacts {
+            pos,
+            monitor: layout
+                .monitor_for(pos)
+                .map(|index| layout.monitors[index].pos),
+            style: shell.style(window),
+            visible: shell.is_visible(window),
+            cloaked: shell.is_cloaked(window),
+        }
+    }
+    fn covers_monitor(&self) -> bool {
+        let (top, bottom, left, right) = self.pos;
+        self.monitor.is_some_and(
+            |(monitor_top, monitor_bottom, monitor_left, monitor_right)| {
+                top <= monitor_top + TOLERANCE
+                    && bottom >= monitor_bottom - TOLERANCE
+                    && left <= monitor_left + TOLERANCE
+                    && right >= monitor_right - TOLERANCE
+            },
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The shell says a full screen program is running, whatever the window looks like.
+    Shell(NotificationState),
+    /// A window without a frame covering its monitor.
+    CoversMonitor,
+    /// Maximized with a title bar, it only covers the monitor because the taskbar is hidden.
+    Maximized,
+    /// Hidden, cloaked or not covering its monitor.
+    Windowed,
+}
+
+impl Verdict {
+    pub fn is_full_screen(self) -> bool {
+        matches!(self, Verdict::Shell(_) | Verdict::CoversMonitor)
+    }
+}
+
+pub fn classify(window: &WindowFacts, state: NotificationState) -> Verdict {
+    if state.is_full_screen() {
+        return Verdict::Shell(state);
+    }
+    if !window.visible || window.cloaked || window.style & style::WS_CHILD != 0 {
+        return Verdict::Windowed;
+    }
+    let framed = window.style & style::WS_CAPTION == style::WS_CAPTION
+        || window.style & style::WS_THICKFRAME != 0;
+    if framed && window.style & style::WS_MAXIMIZE != 0 {
+        return Verdict::Maximized;
+    }
+    if window.covers_monitor() {
+        Verdict::CoversMonitor
+    } else {
+        Verdict::Windowed
+    }
+}
@@ -0,0 +1,381 @@
+/*!
+Platform-independent tray geometry.
+
+Everything here works on plain screen coordinates so the hot corner math can be
+checked without a window system. [`CornerSize`] is how big the start corner is,
+[`TrayGeometry`] works that out in pixels for one monitor.
+!*/
+
+use crate::dpi::Scale;
+use crate::region::Shape;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TrayOrientation {
+    Bottom,
+    Top,
+    Left,
+    Right,
+}
+
+/// A corner of the screen, or one of its edges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    TopEdge,
+    BottomEdge,
+    LeftEdge,
+    RightEdge,
+}
+
+impl Corner {
+    /// Corners first, so they win over the edges they touch.
+    pub const ALL: [Corner; 8] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+        Corner::TopEdge,
+        Corner::BottomEdge,
+        Corner::LeftEdge,
+        Corner::RightEdge,
+    ];
+}
+
+/// How big a corner is.
+///
+/// Written as `"32px"` in pixels at 100% scaling, `"150%"` of the start button or
+/// `"2% screen"` of the screen's shorter side. Only percents of the button keep the
+/// button's shape, the others make the corner square.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum CornerSize {
+    /// Device-independent pixels, see `dpi`.
+    Pixels(i32),
+    /// Percent of the start button, may be more than 100.
+    Button(i32),
+    /// Percent of the shorter side of the screen.
+    Screen(i32),
+}
+
+impl CornerSize {
+    pub const MAX_PIXELS: i32 = 500;
+    pub const MAX_BUTTON: i32 = 500;
+    pub const MAX_SCREEN: i32 = 50;
+
+    pub fn parse(text: &str) -> Result<CornerSize, &'static str> {
+        let text = text.trim();
+        let number = |digits: &str| {
+            digits
+                .trim()
+                .parse::<i32>()
+                .map_err(|_| "Corner size must be like \"32px\", \"150%\" or \"2% screen\"")
+        };
+        let size = if let Some(digits) = text.strip_suffix("px") {
+            CornerSize::Pixels(number(digits)?)
+        } else if let Some(digits) = text.strip_suffix("screen") {
+            let digits = digits.trim_end().strip_suffix('%').unwrap_or("?");
+            CornerSize::Screen(number(digits)?)
+        } else {
+            CornerSize::Button(number(text.strip_suffix('%').unwrap_or("?"))?)
+        };
+        size.validate()
+    }
+    pub fn validate(self) -> Result<CornerSize, &'static str> {
+        let (value, max, error) = match self {
+            CornerSize::Pixels(pixels) => (
+                pixels,
+                CornerSize::MAX_PIXELS,
+                "Corner size must be between 1px and 500px",
+            ),
+            CornerSize::Button(percent) => (
+                percent,
+                CornerSize::MAX_BUTTON,
+                "Corner size must be between 1% and 500% of the start button",
+            ),
+            CornerSize::Screen(percent) => (
+                percent,
+                CornerSize::MAX_SCREEN,
+                "Corner size must be between 1% and 50% of the screen",
+            ),
+        };
+        if value > 0 && value <= max {
+            Ok(self)
+        } else {
+            Err(error)
+        }
+    }
+    /// The percent of the start button, what `sensitivity` used to be.
+    pub fn button_percent(self) -> Option<i32> {
+        match self {
+            CornerSize::Button(percent) => Some(percent),
+            _ => None,
+        }
+    }
+    /// Width and height in physical pixels for a start button of `start` on a `screen`
+    /// at `scale`, never more than the screen.
+    pub fn resolve(
+        self,
+        start: (i32, i32),
+        (width, height): (i32, i32),
+        scale: Scale,
+    ) -> (i32, i32) {
+        let (hot_width, hot_height) = match self {
+            CornerSize::Pixels(pixels) => scale.size_to_physical((pixels, pixels)),
+            CornerSize::Button(percent) => (
+                TrayGeometry::apply_sensitivity(start.0, percent),
+                TrayGeometry::apply_sensitivity(start.1, percent),
+            ),
+            CornerSize::Screen(percent) => {
+                let side = TrayGeometry::apply_sensitivity(width.min(height), percent);
+                (side, side)
+            }
+        };
+        (hot_width.min(width), hot_height.min(height))
+    }
+}
+
+impl Default for CornerSize {
+    fn default() -> CornerSize {
+        CornerSize::Button(100)
+    }
+}
+
+impl fmt::Display for CornerSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CornerSize::Pixels(pixels) => write!(f, "{}px", pixels),
+            CornerSize::Button(percent) => write!(f, "{}%", percent),
+            CornerSize::Screen(percent) => write!(f, "{}% screen", percent),
+        }
+    }
+}
+
+impl TryFrom<String> for CornerSize {
+    type Error = &'static str;
+
+    fn try_from(text: String) -> Result<CornerSize, &'static str> {
+        CornerSize::parse(&text)
+    }
+}
+
+impl From<CornerSize> for String {
+    fn from(size: CornerSize) -> String {
+        size.to_string()
+    }
+}
+
+/// Measurements of the tray and the screen it lives on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TrayGeometry {
+    pub orientation: TrayOrientation,
+    pub parent_width: i32,
+    pub parent_height: i32,
+    pub start_width: i32,
+    pub start_height: i32,
+    pub hot_width: i32,
+    pub hot_height: i32,
+    /// What `hot_width` and `hot_height` are worked out from.
+    pub corner_size: CornerSize,
+    /// Of the monitor the tray is on. Everything above is in that monitor's physical pixels.
+    pub scale: Scale,
+}
+
+impl TrayGeometry {
+    pub fn new(
+        orientation: TrayOrientation,
+        (parent_width, parent_height): (i32, i32),
+        (start_width, start_height): (i32, i32),
+        sensitivity: i32,
+    ) -> TrayGeometry {
+        TrayGeometry {
+            orientation,
+            parent_width,
+            parent_height,
+            start_width,
+            start_height,
+            hot_width: TrayGeometry::apply_sensitivity(start_width, sensitivity),
+            hot_height: TrayGeometry::apply_sensitivity(start_height, sensitivity),
+            corner_size: CornerSize::Button(sensitivity),
+            scale: Scale::default(),
+        }
+    }
+    /// The same tray on a monitor at `scale`, measurements stay as they are but a corner
+    /// sized in pixels is sized again.
+    pub fn with_scale(mut self, scale: Scale) -> TrayGeometry {
+        self.scale = scale;
+        self.with_corner_size(self.corner_size)
+    }
+    /// The same tray with the hot corner resized.
+    pub fn with_sensitivity(&self, sensitivity: i32) -> TrayGeometry {
+        self.with_corner_size(CornerSize::Button(sensitivity))
+    }
+    pub fn with_corner_size(mut self, corner_size: CornerSize) -> TrayGeometry {
+        let (hot_width, hot_height) = self.resolve(corner_size);
+        self.corner_size = corner_size;
+        self.hot_width = hot_width;
+        self.hot_height = hot_height;
+        self
+    }
+    /// Width and height of a corner `size` big on this screen, see `CornerSize::resolve`.
+    pub fn resolve(&self, size: CornerSize) -> (i32, i32) {
+        size.resolve(
+            (self.start_width, self.start_height),
+            (self.parent_width, self.parent_height),
+            self.scale,
+        )
+    }
+    pub const fn default() -> TrayGeometry {
+        TrayGeometry {
+            orientation: TrayOrientation::Bottom,
+            parent_width: 0,
+            parent_height: 0,
+            start_width: 0,
+            start_height: 0,
+            hot_width: 0,
+            hot_height: 0,
+            corner_size: CornerSize::Button(100),
+            scale: Scale::default(),
+        }
+    }
+    pub fn apply_sensitivity(dimension: i32, sensitivity: i32) -> i32 {
+        let dimension = dimension as f32;
+        let sensitivity = sensitivity as f32;
+        ((dimension / 100_f32) * sensitivity).floor() as i32
+    }
+    pub fn get_orientation(
+        width: i32,
+        height: i32,
+        (top, bottom, left, right): (i32, i32, i32, i32),
+    ) -> TrayOrientation {
+        if right - left > bottom - top {
+            // top or bottom
+            if top < height / 2 {
+                TrayOrientation::Top
+            } else {
+                TrayOrientation::Bottom
+            }
+        } else if left < width / 2 {
+            TrayOrientation::Left
+        } else {
+            TrayOrientation::Right
+        }
+    }
+    pub fn get_menu_offsets(
+        button_width: i32,
+        parent_width: i32,
+        orientation: TrayOrientation,
+    ) -> (Option<i32>, Option<i32>, Option<i32>, Option<i32>) {
+        match orientation {
+            TrayOrientation::Bottom => (None, None, Some(button_width), None),
+            TrayOrientation::Top => (None, None, Some(button_width), None),
+            TrayOrientation::Left => (Some(0), None, Some(button_width), None),
+            TrayOrientation::Right => (Some(0), None, None, Some(parent_width - button_width)),
+        }
+    }
+    /// Whether a tray at `(top, bottom, left, right)` is slid into view.
+    pub fn is_tray_open(&self, (top, bottom, left, right): (i32, i32, i32, i32)) -> bool {
+        match self.orientation {
+            TrayOrientation::Bottom => bottom == self.parent_height,
+            TrayOrientation::Top => top == 0,
+            TrayOrientation::Left => left == 0,
+            TrayOrientation::Right => right == self.parent_width,
+        }
+    }
+    pub fn is_tray_region(&self, x: i32, y: i32) -> bool {
+        match self.orientation {
+            TrayOrientation::Bottom => y > self.parent_height - self.start_height,
+            TrayOrientation::Top => y < self.start_height,
+            TrayOrientation::Left => x < self.start_width,
+            TrayOrientation::Right => x > self.parent_width - self.start_width,
+        }
+    }
+    /// The corner the start button sits in, which `is_hot_corner` tests.
+    pub fn start_corner(&self) -> Corner {
+        match self.orientation {
+            TrayOrientation::Bottom => Corner::BottomLeft,
+            TrayOrientation::Top | TrayOrientation::Left => Corner::TopLeft,
+            TrayOrientation::Right => Corner::TopRight,
+        }
+    }
+    /// How far `(x, y)` is from `corner` along each side, see `Shape::contains`.
+    /// Edges are measured from the top left.
+    pub fn corner_offsets(&self, corner: Corner, x: i32, y: i32) -> (i32, i32) {
+        match corner {
+            Corner::TopRight => (self.parent_width - x, y),
+            Corner::BottomLeft => (x, self.parent_height - y),
+            Corner::BottomRight => (self.parent_width - x, self.parent_height - y),
+            _ => (x, y),
+        }
+    }
+    /// Whether `(x, y)` falls in `corner` scaled to `sensitivity`.
+    ///
+    /// Corners are `sensitivity` percent of `corner_size`, edges are the outermost
+    /// row or column of pixels and cover `sensitivity` percent of that side, centered.
+    pub fn is_in_corner(&self, corner: Corner, sensitivity: i32, x: i32, y: i32) -> bool {
+        self.is_in_shape(corner, Shape::Rect, sensitivity, x, y)
+    }
+    /// `is_in_corner` with the corner cut to `shape`. Edges keep theirs.
+    pub fn is_in_shape(
+        &self,
+        corner: Corner,
+        shape: Shape,
+        sensitivity: i32,
+        x: i32,
+        y: i32,
+    ) -> bool {
+        let width = TrayGeometry::apply_sensitivity(self.hot_width, sensitivity);
+        let height = TrayGeometry::apply_sensitivity(self.hot_height, sensitivity);
+        let along = |position: i32, side: i32| {
+            let span = TrayGeometry::apply_sensitivity(side, sensitivity);
+            let start = (side - span) / 2;
+            position >= start && position < start + span
+        };
+
+        match corner {
+            Corner::TopLeft | Corner::TopRight | Corner::BottomLeft | Corner::BottomRight => {
+                shape.contains(self.corner_offsets(corner, x, y), (width, height))
+            }
+            Corner::TopEdge => y <= 0 && along(x, self.parent_width),
+            Corner::BottomEdge => y >= self.parent_height - 1 && along(x, self.parent_width),
+            Corner::LeftEdge => x <= 0 && along(y, self.parent_height),
+            Corner::RightEdge => x >= self.parent_width - 1 && along(y, self.parent_height),
+        }
+    }
+    /// `is_in_shape` for a corner `size` big instead of a percent of `corner_size`.
+    /// Edges have no size and cover their whole side.
+    pub fn is_in_sized(
+        &self,
+        corner: Corner,
+        shape: Shape,
+        size: CornerSize,
+        x: i32,
+        y: i32,
+    ) -> bool {
+        match corner {
+            Corner::TopLeft | Corner::TopRight | Corner::BottomLeft | Corner::BottomRight => {
+                shape.contains(self.corner_offsets(corner, x, y), self.resolve(size))
+            }
+            _ => self.is_in_shape(corner, shape, 100, x, y),
+        }
+    }
+    /// How many pixels `(x, y)` lies beyond the edges of the screen.
+    pub fn overshoot(&self, x: i32, y: i32) -> i32 {
+        let past = |position: i32, size: i32| (-position).max(position - (size - 1)).max(0);
+        past(x, self.parent_width) + past(y, self.parent_height)
+    }
+    pub fn is_hot_corner(&self, x: i32, y: i32) -> bool {
+        self.is_hot_shape(Shape::Rect, x, y)
+    }
+    /// `is_hot_corner` with the start corner cut to `shape`.
+    pub fn is_hot_shape(&self, shape: Shape, x: i32, y: i32) -> bool {
+        let offsets = self.corner_offsets(self.start_corner(), x, y);
+        shape.contains(offsets, (self.hot_width, self.hot_height))
+    }
+}
@@ -0,0 +1,271 @@
+/*!
+Global hotkeys.
+
+Win+Esc toggles the hot corner out of the box. The `[hotkeys]` table of the
+config file replaces that with any number of key combinations, each bound to a
+[`HotkeyCommand`]:
+
+```toml
+[hotkeys]
+"Win+Esc" = "toggle"
+"Ctrl+Alt+H" = "toggle"
+"Win+F12" = "trigger-corner"
+"Win+Shift+T" = "peek-taskbar"
+```
+
+A [`Hotkey`] is any number of modifiers and exactly one other key, spelled the
+way [`Chord`](crate::action::Chord) spells them. Another program may already
+own a combination, or the keyboard may have no such key, the platform code
+collects those in [`Unbound`] and shows the user its message.
+!*/
+
+use crate::action::Chord;
+use crate::control::Request;
+use crate::shell::vk;
+use serde::de::value;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Modifier bits, the same as Win32's `MOD_*`.
+pub mod modifiers {
+    pub const ALT: u32 = 0x1;
+    pub const CONTROL: u32 = 0x2;
+    pub const SHIFT: u32 = 0x4;
+    pub const WIN: u32 = 0x8;
+}
+
+/// Modifier keys, in the order they are printed.
+const MODIFIER_KEYS: [(u8, u32); 4] = [
+    (vk::LWIN, modifiers::WIN),
+    (vk::CONTROL, modifiers::CONTROL),
+    (vk::MENU, modifiers::ALT),
+    (vk::SHIFT, modifiers::SHIFT),
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Hotkey {
+    pub modifiers: u32,
+    /// Virtual key code of the key that isn't a modifier.
+    pub key: u8,
+}
+
+impl Hotkey {
+    pub const fn new(modifiers: u32, key: u8) -> Hotkey {
+        Hotkey { modifiers, key }
+    }
+    /// Reads a combination like `Ctrl+Alt+H`.
+    pub fn parse(text: &str) -> Result<Hotkey, String> {
+        let chord = Chord::parse(text)?;
+        let mut hotkey = Hotkey::new(0, 0);
+        for key in chord.keys {
+            match MODIFIER_KEYS.iter().find(|(modifier, _)| *modifier == key) {
+                Some((_, bit)) => hotkey.modifiers |= bit,
+                None if hotkey.key == 0 => hotkey.key = key,
+                None => {
+                    return Err(format!(
+                        "'{}' has more than one key besides modifiers",
+                        text
+                    ))
+                }
+            }
+        }
+        if hotkey.key == 0 {
+            return Err(format!("'{}' needs a key besides modifiers", text));
+        }
+        Ok(hotkey)
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut keys: Vec<u8> = MODIFIER_KEYS
+            .iter()
+            .filter(|(_, bit)| self.modifiers & bit != 0)
+            .map(|(key, _)| *key)
+            .collect();
+        keys.push(self.key);
+        write!(f, "{}", Chord::new(&keys))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HotkeyCommand {
+    Toggle,
+    Enable,
+    Disable,
+    /// Does what the start corner does.
+    TriggerCorner,
+    /// Shows the hidden taskbar until pressed again.
+    PeekTaskbar,
+    Reload,
+    Quit,
+}
+
+impl HotkeyCommand {
+    pub fn name(self) -> &'static str {
+        match self {
+            HotkeyCommand::Toggle => "toggle",
+            HotkeyCommand::Enable => "enable",
+            HotkeyCommand::Disable => "disable",
+            HotkeyCommand::TriggerCorner => "trigger-corner",
+            HotkeyCommand::PeekTaskbar => "peek-taskbar",
+            HotkeyCommand::Reload => "reload",
+            HotkeyCommand::Quit => "quit",
+        }
+    }
+    /// The same command as a control request, None for the ones only hotkeys have.
+    pub fn request(self) -> Option<Request> {
+        match self {
+            HotkeyCommand::Toggle => Some(Request::Toggle),
+            HotkeyCommand::Enable => Some(Request::Enable),
+            HotkeyCommand::Disable => Some(Request::Disable),
+            HotkeyCommand::Reload => Some(Request::Reload),
+            HotkeyCommand::Quit => Some(Request::Quit),
+            HotkeyCommand::TriggerCorner | HotkeyCommand::PeekTaskbar => None,
+        }
+    }
+}
+
+/// Serialized as a table keyed by the combination, bindings are kept sorted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    try_from = "BTreeMap<String, HotkeyCommand>",
+    into = "BTreeMap<String, HotkeyCommand>"
+)]
+pub struct HotkeyMap {
+    pub bindings: Vec<(Hotkey, HotkeyCommand)>,
+}
+
+impl HotkeyMap {
+    pub const fn new() -> HotkeyMap {
+        HotkeyMap {
+            bindings: Vec::new(),
+        }
+    }
+    /// Binds `hotkey`, or fails if it is bound already.
+    pub fn bind(&mut self, hotkey: Hotkey, command: HotkeyCommand) -> Result<(), String> {
+        if let Some((_, bound)) = self.bindings.iter().find(|(bound, _)| *bound == hotkey) {
+            return Err(format!(
+                "{} is bound to both {} and {}",
+                hotkey,
+                bound.name(),
+                command.name()
+            ));
+        }
+        self.bindings.push((hotkey, command));
+        self.bindings.sort_by_key(|(hotkey, _)| *hotkey);
+        Ok(())
+    }
+    pub fn command(&self, hotkey: Hotkey) -> Option<HotkeyCommand> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == hotkey)
+            .map(|(_, command)| *command)
+    }
+}
+
+impl Default for HotkeyMap {
+    fn default() -> HotkeyMap {
+        HotkeyMap {
+            bindings: vec![(
+                Hotkey::new(modifiers::WIN, vk::ESCAPE),
+                HotkeyCommand::Toggle,
+            )],
+        }
+    }
+}
+
+impl TryFrom<BTreeMap<String, HotkeyCommand>> for HotkeyMap {
+    type Error = value::Error;
+
+    fn try_from(named: BTreeMap<String, HotkeyCommand>) -> Result<HotkeyMap, value::Error> {
+        let mut map = HotkeyMap::new();
+        for (text, command) in named {
+            let hotkey = Hotkey::parse(&text).map_err(serde::de::Error::custom)?;
+            map.bind(hotkey, command)
+                .map_err(serde::de::Error::custom)?;
+        }
+        Ok(map)
+    }
+}
+
+impl From<HotkeyMap> for BTreeMap<String, HotkeyCommand> {
+    fn from(map: HotkeyMap) -> BTreeMap<String, HotkeyCommand> {
+        map.bindings
+            .into_iter()
+            .map(|(hotkey, command)| (hotkey.to_string(), command))
+            .collect()
+    }
+}
+
+/// The combinations the platform could not bind.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Unbound {
+    /// Already owned by another program.
+    pub taken: Vec<(Hotkey, HotkeyCommand)>,
+    /// Without a key on this keyboard.
+    pub unknown: Vec<(Hotkey, HotkeyCommand)>,
+}
+
+impl Unbound {
+    pub const fn new() -> Unbound {
+        Unbound {
+            taken: Vec::new(),
+            unknown: Vec::new(),
+        }
+    }
+    /// What to tell the user, a line for each kind of failure. None if everything bound.
+    pub fn message(&self) -> Option<String> {
+        let lines: Vec<String> = taken_message(&self.taken)
+            .into_iter()
+            .chain(unknown_message(&self.unknown))
+            .collect();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+}
+
+fn names(hotkeys: &[(Hotkey, HotkeyCommand)]) -> Vec<String> {
+    hotkeys
+        .iter()
+        .map(|(hotkey, command)| format!("{} ({})", hotkey, command.name()))
+        .collect()
+}
+
+/// What to tell the user about combinations another program already owns.
+pub fn taken_message(taken: &[(Hotkey, HotkeyCommand)]) -> Option<String> {
+    let names = names(taken);
+    match names.len() {
+        0 => None,
+        1 => Some(format!(
+            "{} is already taken by another program, pick another combination in [hotkeys]",
+            names[0]
+        )),
+        _ => Some(format!(
+            "{} are already taken by other programs, pick other combinations in [hotkeys]",
+            names.join(", ")
+        )),
+    }
+}
+
+/// What to tell the user about combinations with a key the keyboard doesn't have.
+pub fn unknown_message(unknown: &[(Hotkey, HotkeyCommand)]) -> Option<String> {
+    let names = names(unknown);
+    match names.len() {
+        0 => None,
+        1 => Some(format!(
+            "{} uses a key this keyboard doesn't have, pick another combination in [hotkeys]",
+            names[0]
+        )),
+        _ => Some(format!(
+            "{} use keys this keyboard doesn't have, pick other combinations in [hotkeys]",
+            names.join(", ")
+        )),
+    }
+}
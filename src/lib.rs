@@ -1 +1,26 @@
-pub mod errors;
\ No newline at end of file
+pub mod action;
+pub mod autohide;
+pub mod config;
+pub mod control;
+pub mod corners;
+pub mod desktop;
+pub mod dpi;
+pub mod engine;
+#[cfg(windows)]
+pub mod errors;
+pub mod ewmh;
+pub mod exclude;
+pub mod fullscreen;
+pub mod geometry;
+pub mod hotkey;
+pub mod logging;
+pub mod menu;
+pub mod monitor;
+pub mod record;
+pub mod recovery;
+pub mod region;
+pub mod rules;
+pub mod shell;
+pub mod taskbar;
+pub mod timer;
+pub mod trigger;
@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use log::{error, info, warn};
+
+use win_gnome::config::{Config, ConfigWatcher, Overrides};
+use win_gnome::engine::{Command, Event, HotCornerEngine, TimerKind};
+use win_gnome::ewmh::EwmhDesktop;
+use win_gnome::hotkey::{HotkeyCommand, HotkeyMap};
+use win_gnome::recovery::{self, DirtyMarker};
+use win_gnome::shell::x11::{X11Event, X11Shell};
+use win_gnome::timer::{Clock, SystemClock, Timers};
+
+/// How often the config file is checked for changes.
+const CONFIG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Logs `message` and shows it as a desktop notification, if something shows those.
+fn tell_user(message: String) {
+    warn!("{}", message);
+    // Waited on from a thread so the loop doesn't stall or leave a zombie behind
+    thread::spawn(move || {
+        let _ = process::Command::new("notify-send")
+            .args(["--app-name=win-gnome", "win-gnome", &message])
+            .status();
+    });
+}
+
+/// Set by SIGINT, SIGTERM and SIGHUP, the main loop then shows the docks and quits.
+static STOP: AtomicBool = AtomicBool::new(false);
+
+struct Session {
+    engine: HotCornerEngine,
+    desktop: EwmhDesktop<X11Shell>,
+    clock: SystemClock,
+    timers: Timers<TimerKind>,
+    hotkeys: HotkeyMap,
+    marker: DirtyMarker,
+}
+
+impl Session {
+    fn dispatch(&mut self, event: Event) {
+        for command in self
+            .engine
+            .handle_at(self.clock.now(), event, &mut self.desktop)
+        {
+            self.run_command(command);
+        }
+    }
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::StartTimer(kind, delay) => self.timers.schedule(self.clock.now(), delay, kind),
+            // The struts are saved before they are taken away
+            Command::HideTray => {
+                if let Err(error) = self.marker.hide_docks(&self.desktop.hidden_docks()) {
+                    warn!("Could not update {:?}: {}", self.marker.path, error);
+                }
+                self.desktop.hide_docks();
+            }
+            Command::ShowTray => {
+                if self.desktop.show_docks() {
+                    if let Err(error) = self.marker.clear() {
+                        warn!("Could not update {:?}: {}", self.marker.path, error);
+                    }
+                }
+            }
+            command => self.desktop.execute(command),
+        }
+    }
+    fn apply_config(&mut self, config: &Config) {
+        self.engine.corner_action = config.corner_action.clone();
+        self.engine.corners = config.corners.clone();
+        self.engine.regions = config.regions.clone();
+        self.engine.trigger.settings = config.trigger;
+        for command in self.engine.set_tray_action(config.tray_action()) {
+            self.run_command(command);
+        }
+        if self.desktop.exclusions != config.exclude {
+            self.desktop.set_exclusions(config.exclude.clone());
+        }
+        if self.desktop.corner_size != config.corner_size() {
+            self.desktop.set_corner_size(config.corner_size());
+        }
+        if self.hotkeys != config.hotkeys {
+            let unbound = self.desktop.backend.grab_hotkeys(&config.hotkeys);
+            if let Some(message) = unbound.message() {
+                tell_user(message);
+            }
+            self.hotkeys = config.hotkeys.clone();
+        }
+    }
+    /// Reads the docks again, hiding new ones along with the rest.
+    fn docks_changed(&mut self) {
+        self.desktop.refresh();
+        if self.desktop.hidden {
+            self.run_command(Command::HideTray);
+        }
+    }
+    /// Runs a hotkey's command, false if it asks to quit.
+    fn hotkey(&mut self, command: HotkeyCommand, reload: impl FnOnce(&mut Session)) -> bool {
+        match command {
+            HotkeyCommand::Toggle => self.dispatch(Event::Hotkey),
+            HotkeyCommand::Enable if !self.engine.enabled => self.dispatch(Event::Hotkey),
+            HotkeyCommand::Disable if self.engine.enabled => self.dispatch(Event::Hotkey),
+            HotkeyCommand::Enable | HotkeyCommand::Disable => {}
+            HotkeyCommand::TriggerCorner => self.dispatch(Event::TriggerCorner),
+            HotkeyCommand::PeekTaskbar => self.dispatch(Event::PeekTaskbar),
+            HotkeyCommand::Reload => reload(self),
+            HotkeyCommand::Quit => return false,
+        }
+        true
+    }
+}
+
+fn load_config(path: &Path, overrides: &Overrides) -> Option<Config> {
+    match Config::load(path) {
+        Ok(config) => {
+            info!("Reloaded {}", path.display());
+            Some(config.merge(overrides))
+        }
+        Err(error) => {
+            warn!("Invalid config file, keeping previous options: {}", error);
+            None
+        }
+    }
+}
+
+/// Shows the docks a run left hidden, from what it wrote to the marker.
+fn restore_docks(shell: &mut X11Shell) {
+    let mut marker = DirtyMarker::open(recovery::default_path());
+    match marker.recover_docks(shell) {
+        Ok(true) => warn!("The last run left the docks hidden, showed them again"),
+        Ok(false) => {}
+        Err(error) => warn!("Could not clear {:?}: {}", marker.path, error),
+    }
+}
+extern "C" fn stop(_signal: libc::c_int) {
+    STOP.store(true, Ordering::SeqCst);
+}
+fn catch_exits() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        error!("{}", info);
+        // The main connection may be what panicked
+        if let Ok(mut shell) = X11Shell::connect(None) {
+            restore_docks(&mut shell);
+        }
+        default_hook(info);
+    }));
+    let handler = stop as extern "C" fn(libc::c_int);
+    for signal in &[libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        unsafe { libc::signal(*signal, handler as libc::sighandler_t) };
+    }
+}
+
+// Same loop as on Windows, with X events read on a thread and timers run in between
+pub fn run(config: Config, config_path: Option<PathBuf>, overrides: Overrides) {
+    let mut shell = match X11Shell::connect(None) {
+        Ok(shell) => shell,
+        Err(error) => {
+            error!("Could not connect to the X server: {}", error);
+            return;
+        }
+    };
+    restore_docks(&mut shell);
+    catch_exits();
+    let events = shell.events();
+    let mut session = Session {
+        engine: HotCornerEngine::default(),
+        desktop: EwmhDesktop::new(shell, config.sensitivity),
+        clock: SystemClock::new(),
+        timers: Timers::new(),
+        hotkeys: HotkeyMap::new(),
+        marker: DirtyMarker::open(recovery::default_path()),
+    };
+    session.apply_config(&config);
+    for command in session.engine.start() {
+        session.run_command(command);
+    }
+
+    let (sender, received) = channel();
+    thread::spawn(move || loop {
+        match events.next() {
+            Ok(event) => {
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+            Err(error) => {
+                error!("Lost the connection to the X server: {}", error);
+                return;
+            }
+        }
+    });
+
+    let mut watcher = config_path.map(ConfigWatcher::new);
+    let mut next_config_check = session.clock.now();
+    while !STOP.load(Ordering::SeqCst) {
+        let now = session.clock.now();
+        let timeout = session
+            .timers
+            .delay_until_next(now)
+            .unwrap_or(CONFIG_INTERVAL)
+            .min(CONFIG_INTERVAL);
+
+        match received.recv_timeout(timeout) {
+            Ok(X11Event::MouseMoved { x, y }) => {
+                session.desktop.pointer_moved(x, y);
+                session.dispatch(Event::MouseMoved { x, y });
+            }
+            Ok(X11Event::ForegroundChanged(window)) => {
+                session.desktop.foreground_changed(window);
+                session.dispatch(Event::ForegroundChanged { menu_closed: false });
+            }
+            Ok(X11Event::Hotkey(command)) => {
+                let path = watcher.as_ref().map(|watcher| watcher.path.clone());
+                let keep_running = session.hotkey(command, |session| {
+                    if let Some(config) = path.and_then(|path| load_config(&path, &overrides)) {
+                        session.apply_config(&config);
+                    }
+                });
+                if !keep_running {
+                    break;
+                }
+            }
+            Ok(X11Event::WindowMapped(window)) => {
+                if session.desktop.window_mapped(window) {
+                    session.docks_changed();
+                }
+            }
+            Ok(X11Event::WindowUnmapped(window)) => session.desktop.window_unmapped(window),
+            Ok(X11Event::WindowsChanged) => session.docks_changed(),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        while let Some((_, kind)) = session.timers.pop_due(session.clock.now()) {
+            session.dispatch(Event::TimerElapsed(kind));
+        }
+        if session.clock.now() >= next_config_check {
+            next_config_check = session.clock.now() + CONFIG_INTERVAL.as_millis() as u64;
+            if let Some(watcher) = watcher.as_mut() {
+                if watcher.changed() {
+                    if let Some(config) = load_config(&watcher.path, &overrides) {
+                        session.apply_config(&config);
+                    }
+                }
+            }
+        }
+    }
+    session.dispatch(Event::Close);
+}
@@ -0,0 +1,285 @@
+/*!
+Log file.
+
+Release builds have no console, so everything worth knowing goes through the
+`log` macros into `win-gnome.log` next to the config file, where users can
+find it for bug reports:
+
+```text
+2026-10-18 09:41:07.112 WARN  win_gnome::desktop: Desktop handle was invalid, refreshing
+```
+
+What gets logged is picked with a [`Filter`] from `--log-level`, like `debug`
+or `info,win_gnome::engine=trace`. The file is rotated by [`RotatingFile`] once
+it grows past [`MAX_SIZE`].
+!*/
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Size a log file can grow to before it is rotated.
+pub const MAX_SIZE: u64 = 1024 * 1024;
+/// Rotated files kept around, as `win-gnome.log.1` up to `win-gnome.log.3`.
+pub const KEEP: usize = 3;
+
+/// Which levels to log, overall and for single modules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    pub default: LevelFilter,
+    /// Module paths and their level, the longest match wins.
+    pub modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    pub const fn default() -> Filter {
+        Filter {
+            default: LevelFilter::Info,
+            modules: Vec::new(),
+        }
+    }
+    /// Reads a comma separated list of levels and `module=level` pairs.
+    pub fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter::default();
+        for part in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            match part.split_once('=') {
+                Some((module, level)) => {
+                    let module = module.trim();
+                    if module.is_empty() {
+                        return Err(format!("Expected module=level, got '{}'", part));
+                    }
+                    filter
+                        .modules
+                        .push((module.to_string(), Filter::parse_level(level)?));
+                }
+                None => filter.default = Filter::parse_level(part)?,
+            }
+        }
+        Ok(filter)
+    }
+    fn parse_level(level: &str) -> Result<LevelFilter, String> {
+        level.trim().parse().map_err(|_| {
+            format!(
+                "Unknown log level '{}', expected off, error, warn, info, debug or trace",
+                level.trim()
+            )
+        })
+    }
+    /// The level for messages from `target`, a module path.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target == module
+                    || target.starts_with(module.as_str())
+                        && target[module.len()..].starts_with("::")
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+    /// The most verbose level anything is logged at.
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter::default()
+    }
+}
+
+/// A log file that moves itself to `<path>.1` once it reaches `max_size`.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    /// Appends to `path`, creating it and its directory if needed.
+    pub fn open(path: impl Into<PathBuf>, max_size: u64, keep: usize) -> io::Result<RotatingFile> {
+        let path = path.into();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            max_size,
+            keep,
+            file: Some(file),
+            size,
+        })
+    }
+    /// Where the `n`th most recent rotated file lives.
+    pub fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+    fn rotate(&mut self) -> io::Result<()> {
+        // Windows won't rename a file that is still open
+        self.file = None;
+        if self.keep > 0 {
+            let _ = fs::remove_file(self.rotated_path(self.keep));
+            for n in (1..self.keep).rev() {
+                let _ = fs::rename(self.rotated_path(n), self.rotated_path(n + 1));
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = Some(
+            OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&self.path)?,
+        );
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        if self.file.is_none() || self.size > 0 && self.size + buffer.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self
+            .file
+            .as_mut()
+            .map_or(Ok(0), |file| file.write(buffer))?;
+        self.size += written as u64;
+        Ok(written)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.as_mut().map_or(Ok(()), |file| file.flush())
+    }
+}
+
+/// `YYYY-MM-DD HH:MM:SS.mmm` in UTC, for a time since the Unix epoch.
+pub fn format_time(since_epoch: Duration) -> String {
+    let seconds = since_epoch.as_secs();
+    let (days, time) = ((seconds / 86400) as i64, seconds % 86400);
+
+    // Days to a civil date, from Howard Hinnant's `civil_from_days`
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+pub struct Logger {
+    filter: Filter,
+    sink: Mutex<Box<dyn Write + Send>>,
+    /// Print lines to stdout as well, for runs with a console.
+    echo: bool,
+}
+
+impl Logger {
+    pub fn new(filter: Filter, sink: Box<dyn Write + Send>) -> Logger {
+        Logger {
+            filter,
+            sink: Mutex::new(sink),
+            echo: false,
+        }
+    }
+    pub fn echo(mut self) -> Logger {
+        self.echo = true;
+        self
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level_for(metadata.target())
+    }
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let line = format!(
+            "{} {:<5} {}: {}\n",
+            format_time(now),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        // A closed or broken stdout mustn't take the program down with it
+        if self.echo {
+            let _ = io::stdout().write_all(line.as_bytes());
+        }
+        if let Ok(mut sink) = self.sink.lock() {
+            let _ = sink.write_all(line.as_bytes());
+        }
+    }
+    fn flush(&self) {
+        if let Ok(mut sink) = self.sink.lock() {
+            let _ = sink.flush();
+        }
+    }
+}
+
+/// `win-gnome.log` in the same directory as the default config file.
+pub fn default_path() -> Option<PathBuf> {
+    crate::config::Config::default_path()
+        .and_then(|config| Some(config.parent()?.join("win-gnome.log")))
+}
+
+/// Logs to a rotating file at `path`, if given, and to stdout when that is a console.
+pub fn init(filter: Filter, path: Option<&Path>) -> Result<(), String> {
+    let sink: Box<dyn Write + Send> =
+        match path {
+            Some(path) => Box::new(RotatingFile::open(path, MAX_SIZE, KEEP).map_err(|error| {
+                format!("Could not open log file {}: {}", path.display(), error)
+            })?),
+            None => Box::new(io::sink()),
+        };
+    let max_level = filter.max_level();
+    let logger = Logger::new(filter, sink);
+    let logger = if io::stdout().is_terminal() {
+        logger.echo()
+    } else {
+        logger
+    };
+    log::set_boxed_logger(Box::new(logger)).map_err(|error| error.to_string())?;
+    log::set_max_level(max_level);
+    Ok(())
+}
@@ -1,140 +1,576 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+#[cfg(windows)]
 extern crate winapi;
+#[cfg(windows)]
 pub mod errors;
+#[cfg(windows)]
 #[macro_use]
 mod hook;
-mod desktop;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(windows)]
+mod pipe;
+#[cfg(windows)]
+mod tray;
+#[cfg(windows)]
 mod window;
 
+use std::fs::File;
+use std::io::{BufReader, LineWriter};
+use std::path::PathBuf;
+#[cfg(windows)]
+use std::sync::mpsc::{channel, Receiver};
+#[cfg(windows)]
 use std::alloc::System;
-use std::ptr::null_mut;
-use std::thread;
+#[cfg(windows)]
+use std::ptr::{addr_of_mut, null_mut};
+#[cfg(windows)]
 use std::time::Duration;
 
+use log::{info, warn};
+#[cfg(windows)]
+use log::error;
+
+#[cfg(windows)]
 #[global_allocator]
 static A: System = System;
 
+#[cfg(windows)]
 use winapi::um::winuser::{
-    MessageBoxW, RegisterHotKey, MB_ICONEXCLAMATION, MB_OK, MOD_WIN, VK_ESCAPE, WM_MOUSEMOVE
+    MessageBoxW, PostMessageW, MB_ICONEXCLAMATION, MB_OK, WM_CLOSE, WM_LBUTTONUP, WM_MOUSEMOVE, WM_RBUTTONUP
 };
-use desktop::Desktop;
+#[cfg(windows)]
+use winapi::shared::minwindef::{BOOL, DWORD, TRUE};
+#[cfg(windows)]
+use winapi::um::consoleapi::SetConsoleCtrlHandler;
+#[cfg(windows)]
+use winapi::um::processenv::GetStdHandle;
+#[cfg(windows)]
+use winapi::um::winbase::STD_OUTPUT_HANDLE;
+#[cfg(windows)]
+use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
+#[cfg(windows)]
 use window::win32_string;
+#[cfg(windows)]
+use win_gnome::desktop::{Desktop, COMPOSITOR_DELAY};
+#[cfg(windows)]
+use win_gnome::shell::win32::{window_id, Win32Shell};
+#[cfg(windows)]
+use win_gnome::shell::WindowId;
+#[cfg(windows)]
+use win_gnome::timer::{Clock, SystemClock, Timers};
+#[cfg(windows)]
+use win_gnome::engine::{Command, Event, TimerKind};
+use win_gnome::config::{self, Config, Overrides};
+use win_gnome::control::Request;
+#[cfg(windows)]
+use win_gnome::control::Reply;
+#[cfg(windows)]
+use win_gnome::config::ConfigWatcher;
+use win_gnome::engine::CornerAction;
+#[cfg(windows)]
+use win_gnome::autohide::{self, AutoHide};
+#[cfg(windows)]
+use win_gnome::recovery::{self, DirtyMarker};
+#[cfg(windows)]
+use win_gnome::rules::{self, Context, Role, Rules};
+#[cfg(windows)]
+use win_gnome::hotkey::{HotkeyCommand, HotkeyMap, Unbound};
+use win_gnome::logging::{self, Filter};
+#[cfg(windows)]
+use win_gnome::menu::{self, MenuAction, MenuCommand, MenuEntry, MenuState};
+#[cfg(windows)]
+use win_gnome::engine::{HotCornerEngine, TrayAction};
+use win_gnome::record::{self, Recorder};
+#[cfg(windows)]
+use win_gnome::record::WindowTree;
+
+#[cfg(windows)]
+const IDENTIFIER: &str = "win_gnome";
+#[cfg(windows)]
+const CONFIG_TIMER: usize = 1;
+/// Goes off when the first timer in `Session::timers` is due.
+#[cfg(windows)]
+const QUEUE_TIMER: usize = 2;
+#[cfg(windows)]
+static CLOCK: SystemClock = SystemClock::new();
 
-enum CornerAction {
-    StartMenu,
-    DesktopSelector,
+#[cfg(windows)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Timer {
+    /// A `Command::StartTimer` from the engine.
+    Engine(TimerKind),
+    /// See if the window taking the foreground closed the start menu, once DWM has caught up.
+    MenuCheck(WindowId),
 }
 
-#[derive(PartialEq)]
-enum TrayAction {
-    Hide,
-    NoHide
+/// What the hooks and the message loop share, the counterpart of `linux::Session`.
+#[cfg(windows)]
+struct Session {
+    engine: HotCornerEngine,
+    timers: Timers<Timer>,
+    window: Option<window::Window>,
+    auto_hide: AutoHide,
+    marker: DirtyMarker,
+    /// Registered hotkeys, the id of each is its index.
+    hotkeys: HotkeyMap,
+    recorder: Option<Recording>,
+    desktop: Desktop<Win32Shell>,
+    /// Read again by the `reload` request and hotkey.
+    config_path: Option<PathBuf>,
+    overrides: Overrides,
+    watcher: Option<ConfigWatcher>,
+    /// Requests from the control pipe, see `pipe`.
+    requests: Option<Receiver<pipe::Pending>>,
 }
 
-const IDENTIFIER: &str = "win_gnome";
-pub static mut SENSITIVITY: i32 = 100; // %
-static mut DELAY: bool = false;
-static mut LASTX: i32 = 0;
-static mut LASTY: i32 = 0;
-static mut CORNER_ACTION: CornerAction = CornerAction::StartMenu;
-static mut TRAY_ACTION: TrayAction = TrayAction::Hide;
-#[allow(non_upper_case_globals)]
-pub static mut desktop: Desktop = Desktop::default();
-
-fn delay_next(ms: u64) {
-    unsafe {
-        DELAY = true;
-        thread::spawn(move || {
-            thread::sleep(Duration::from_millis(ms));
-            DELAY = false;
-            mouse_move(LASTX, LASTY);
+#[cfg(windows)]
+static mut SESSION: Session = Session {
+    engine: HotCornerEngine::default(),
+    timers: Timers::new(),
+    window: None,
+    auto_hide: AutoHide::new(),
+    marker: DirtyMarker::new(),
+    hotkeys: HotkeyMap::new(),
+    recorder: None,
+    desktop: Desktop::with_backend(Win32Shell),
+    config_path: None,
+    overrides: Overrides {
+        corner_action: None,
+        hide_tray: None,
+        sensitivity: None,
+    },
+    watcher: None,
+    requests: None,
+};
+
+/// The hooks, window callbacks and pipe requests all run on the main thread, one at a time.
+#[cfg(windows)]
+unsafe fn session() -> &'static mut Session {
+    &mut *addr_of_mut!(SESSION)
+}
+
+#[cfg(windows)]
+impl Session {
+    /// Finds the shell and starts answering the pipe, before the hooks are installed.
+    fn open(&mut self, config: &Config, config_path: Option<PathBuf>, overrides: Overrides) {
+        self.watcher = config_path.clone().map(ConfigWatcher::new);
+        self.config_path = config_path;
+        self.overrides = overrides;
+        let rules = load_rules();
+        self.marker = DirtyMarker::open(recovery::default_path());
+        match self
+            .marker
+            .recover(&mut Win32Shell, &recovery::find_taskbars(&Win32Shell, &rules))
+        {
+            Ok(true) => warn!("The last run left the taskbar hidden, showed it again"),
+            Ok(false) => {}
+            Err(error) => warn!("Could not clear {:?}: {}", self.marker.path, error),
+        }
+        if let Some(window) = self.window {
+            let (control_sender, requests) = channel();
+            pipe::start_server(&window, control_sender);
+            self.requests = Some(requests);
+        }
+        self.desktop = loop {
+            match Desktop::with_rules(Win32Shell, config.corner_size(), rules.clone()) {
+                Ok(next_desktop) => break next_desktop,
+                Err(error) => {
+                    warn!(
+                        "Could not fetch desktop: {}, trying again in 1 second",
+                        error
+                    );
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+            }
+        };
+    }
+    /// Takes on `config` and hides the taskbar, once the hooks are in.
+    fn start(&mut self, config: &Config) {
+        if let Some(window) = self.window {
+            window::set_timer(&window, CONFIG_TIMER, 1000);
+        }
+
+        self.engine.corner_action = config.corner_action.clone();
+        self.engine.corners = config.corners.clone();
+        self.engine.regions = config.regions.clone();
+        self.engine.trigger.settings = config.trigger;
+        self.engine.tray_action = config.tray_action();
+        self.desktop.set_exclusions(config.exclude.clone());
+        self.auto_hide = AutoHide::open(autohide::default_path());
+        self.hold_auto_hide(config.manages_auto_hide());
+        let (desktop, engine) = (&self.desktop, &self.engine);
+        record(&mut self.recorder, |recorder| {
+            recorder.start(desktop, engine)
         });
+        for command in self.engine.start() {
+            self.run_command(command);
+        }
+        self.add_tray_icon();
+        // After the icon, which shows the warning if a combination is taken
+        self.register_hotkeys(&config.hotkeys);
+    }
+    fn add_tray_icon(&self) {
+        if let Some(window) = self.window {
+            if !tray::add(&window, self.engine.enabled) {
+                warn!("Could not add the notification area icon");
+            }
+        }
+    }
+    fn dispatch(&mut self, event: Event) {
+        let enabled = self.engine.enabled;
+        for command in self.engine.handle_at(CLOCK.now(), event, &mut self.desktop) {
+            self.run_command(command);
+        }
+        if let Some(window) = self.window.filter(|_| self.engine.enabled != enabled) {
+            tray::set_enabled(&window, self.engine.enabled);
+        }
+    }
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::StartTimer(kind, delay) => self.schedule(delay, Timer::Engine(kind)),
+            command => {
+                if let Err(error) = self.marker.track(&command) {
+                    warn!("Could not update {:?}: {}", self.marker.path, error);
+                }
+                self.desktop.execute(command)
+            }
+        }
+    }
+    fn schedule(&mut self, delay: Duration, timer: Timer) {
+        self.timers.schedule(CLOCK.now(), delay, timer);
+        self.arm_queue_timer();
+    }
+    /// Points `QUEUE_TIMER` at the next deadline, or stops it if nothing is scheduled.
+    fn arm_queue_timer(&self) {
+        if let Some(window) = self.window {
+            match self.timers.delay_until_next(CLOCK.now()) {
+                // SetTimer rounds anything shorter up to USER_TIMER_MINIMUM
+                Some(delay) => window::set_timer(&window, QUEUE_TIMER, delay.as_millis() as u32),
+                None => window::kill_timer(&window, QUEUE_TIMER),
+            };
+        }
+    }
+    fn run_timers(&mut self) {
+        while let Some((_, timer)) = self.timers.pop_due(CLOCK.now()) {
+            match timer {
+                Timer::Engine(kind) => self.dispatch(Event::TimerElapsed(kind)),
+                Timer::MenuCheck(window) => {
+                    let menu_closed = self.desktop.check_menus();
+                    let desktop = &self.desktop;
+                    record(&mut self.recorder, |recorder| {
+                        recorder.foreground_changed(desktop, window)
+                    });
+                    self.dispatch(Event::ForegroundChanged { menu_closed });
+                }
+            }
+        }
+        self.arm_queue_timer();
+    }
+    fn apply_config(&mut self, config: &Config) {
+        self.engine.corner_action = config.corner_action.clone();
+        self.engine.corners = config.corners.clone();
+        self.engine.regions = config.regions.clone();
+        self.engine.trigger.settings = config.trigger;
+        for command in self.engine.set_tray_action(config.tray_action()) {
+            self.run_command(command);
+        }
+        if self.desktop.corner_size != config.corner_size() {
+            self.desktop.set_corner_size(config.corner_size());
+        }
+        if self.desktop.exclusions != config.exclude {
+            self.desktop.set_exclusions(config.exclude.clone());
+        }
+        if self.hotkeys != config.hotkeys {
+            self.register_hotkeys(&config.hotkeys);
+        }
+        self.hold_auto_hide(config.manages_auto_hide());
+    }
+    /// Turns the taskbar's auto-hide on, or back to what it was.
+    fn hold_auto_hide(&mut self, manage: bool) {
+        let taskbar = self.desktop.tray.bar;
+        if let Err(error) = self.auto_hide.apply(manage, &mut self.desktop.backend, taskbar) {
+            warn!("{}", error);
+        }
+    }
+    /// Registers `hotkeys` in place of the ones registered before.
+    fn register_hotkeys(&mut self, hotkeys: &HotkeyMap) {
+        let window = match self.window {
+            Some(window) => window,
+            None => return,
+        };
+        for id in 0..self.hotkeys.bindings.len() {
+            window::unregister_hotkey(&window, id);
+        }
+        let mut unbound = Unbound::new();
+        for (id, (hotkey, command)) in hotkeys.bindings.iter().enumerate() {
+            if !window::register_hotkey(&window, id, hotkey.modifiers, hotkey.key) {
+                unbound.taken.push((*hotkey, *command));
+            }
+        }
+        if let Some(message) = unbound.message() {
+            warn!("{}", message);
+            if !tray::notify(&window, &message) {
+                warn!("Could not show the hotkey warning from the notification area icon");
+            }
+        }
+        self.hotkeys = hotkeys.clone();
+    }
+    /// Runs the command bound to the hotkey with `id`.
+    fn handle_hotkey(&mut self, id: usize) {
+        let command = match self.hotkeys.bindings.get(id) {
+            Some((_, command)) => *command,
+            None => return,
+        };
+        match command {
+            HotkeyCommand::Quit => self.close_window(),
+            HotkeyCommand::TriggerCorner => {
+                record(&mut self.recorder, |recorder| recorder.trigger_corner());
+                self.dispatch(Event::TriggerCorner);
+            }
+            HotkeyCommand::PeekTaskbar => {
+                record(&mut self.recorder, |recorder| recorder.peek_taskbar());
+                self.dispatch(Event::PeekTaskbar);
+            }
+            command => {
+                let request = command.request().unwrap_or(Request::Status);
+                if let Reply::Error(error) = self.handle_request(request) {
+                    warn!("Hotkey for {} failed: {}", command.name(), error);
+                }
+            }
+        }
+    }
+    fn reload_config(&mut self) -> Result<(), String> {
+        let path = self
+            .config_path
+            .as_ref()
+            .ok_or("No config file to reload")?;
+        let config = Config::load(path)?.merge(&self.overrides);
+        self.apply_config(&config);
+        Ok(())
+    }
+    /// Answers a request from `win-gnome enable` and friends.
+    fn handle_request(&mut self, request: Request) -> Reply {
+        let enabled = match request {
+            Request::Enable => true,
+            Request::Disable => false,
+            Request::Toggle => !self.engine.enabled,
+            Request::Status => self.engine.enabled,
+            Request::Reload => {
+                return self
+                    .reload_config()
+                    .map_or_else(Reply::Error, |_| Reply::Ok)
+            }
+            // The pipe closes the window once the reply is out
+            Request::Quit => return Reply::Ok,
+        };
+        // Switching goes through the hotkey so recordings replay the same
+        if enabled != self.engine.enabled {
+            record(&mut self.recorder, |recorder| recorder.hotkey());
+            self.dispatch(Event::Hotkey);
+        }
+        Reply::Status {
+            enabled: self.engine.enabled,
+            hide_tray: self.engine.tray_action == TrayAction::Hide,
+        }
+    }
+    fn menu_state(&self) -> MenuState {
+        MenuState {
+            enabled: self.engine.enabled,
+            corner_action: self.engine.corner_action.clone(),
+            // A corner sized another way than by the button has no item to check
+            sensitivity: self.desktop.corner_size.button_percent().unwrap_or(0),
+            config_path: self.config_path.clone(),
+            log_path: logging::default_path().filter(|path| path.exists()),
+        }
+    }
+    /// Carries out an item chosen from the notification area icon's menu.
+    fn handle_menu(&mut self, action: MenuAction) {
+        match action {
+            MenuAction::Request(Request::Quit) => self.close_window(),
+            MenuAction::Request(request) => {
+                self.handle_request(request);
+            }
+            MenuAction::SetCornerAction(action) => self.engine.corner_action = action,
+            MenuAction::SetSensitivity(sensitivity) => self.desktop.set_sensitivity(sensitivity),
+            MenuAction::Run(action) => self.desktop.execute(Command::Run(action)),
+        }
+    }
+    fn close_window(&self) {
+        if let Some(window) = self.window {
+            unsafe { PostMessageW(window.handle, WM_CLOSE, 0, 0) };
+        }
     }
 }
 
-unsafe fn on_hot_corner() {
-    if !desktop.full_screen_program() {
-        desktop.hot_active = false;
-        
-        if TRAY_ACTION == TrayAction::Hide {
-            desktop.tray.show();
+#[cfg(windows)]
+impl window::Handler for Session {
+    fn hotkey(&mut self, id: usize) {
+        self.handle_hotkey(id);
+    }
+    fn timer(&mut self, id: usize) {
+        if id == QUEUE_TIMER {
+            self.run_timers();
         }
-        match CORNER_ACTION {
-            CornerAction::StartMenu => desktop.open_start_menu(),
-            CornerAction::DesktopSelector => desktop.open_desktop_selector(),
+        let changed = match self.watcher.as_mut() {
+            Some(watcher) if id == CONFIG_TIMER => watcher.changed().then(|| watcher.path.clone()),
+            _ => None,
+        };
+        if let Some(path) = changed {
+            match Config::load(&path) {
+                Ok(config) => {
+                    info!("Reloaded {}", path.display());
+                    self.apply_config(&config.merge(&self.overrides))
+                }
+                Err(error) => warn!("Invalid config file, keeping previous options: {}", error),
+            }
         }
-        delay_next(300);
-    } else if desktop.shell_changed() { // full screen program && that full screen program might be new shell
-        println!("Desktop handle was invalid. Got new one and trying again");
-        on_hot_corner();
     }
-}
-unsafe fn on_leaving_corner(force: bool){
-    if TRAY_ACTION == TrayAction::Hide {
-        if force || !desktop.tray.is_tray_open() {
-            if !desktop.tray.hide() && desktop.shell_changed() {
-                println!("Desktop handle was invalid. Got new one and trying again");
-                on_leaving_corner(force);
-            } else {
-                desktop.hot_active = true;
+    fn control(&mut self) {
+        while let Some((request, reply)) = self
+            .requests
+            .as_ref()
+            .and_then(|requests| requests.try_recv().ok())
+        {
+            let _ = reply.send(self.handle_request(request));
+        }
+    }
+    fn tray(&mut self, event: u32) -> Option<Vec<MenuEntry>> {
+        if self.window.is_some() && (event == WM_RBUTTONUP || event == WM_LBUTTONUP) {
+            Some(menu::build(&self.menu_state()))
+        } else {
+            None
+        }
+    }
+    fn menu(&mut self, command: MenuCommand) {
+        // Read again, a hotkey or the pipe may have changed it while the menu was open
+        let state = self.menu_state();
+        if let Some(action) = command.action(&state).filter(|_| self.window.is_some()) {
+            self.handle_menu(action);
+        }
+    }
+    /// The new taskbar and notification area come up empty and visible.
+    fn shell_restarted(&mut self) {
+        match self.desktop.shell_restarted() {
+            Ok(()) => {
+                record(&mut self.recorder, |recorder| recorder.shell_restarted());
+                self.dispatch(Event::ShellRestarted);
             }
+            Err(error) => warn!("Could not find the new taskbar: {}", error),
+        }
+        self.add_tray_icon();
+    }
+    /// Monitors added, moved or rescaled.
+    fn display_changed(&mut self) {
+        if let Err(error) = self.desktop.refresh() {
+            warn!("Could not measure the monitors again: {}", error);
+        }
+    }
+    fn close(&mut self) {
+        self.dispatch(Event::Close);
+        self.hold_auto_hide(false);
+        // Nothing acts on the window after this, a menu still open included
+        if let Some(window) = self.window.take() {
+            tray::remove(&window);
         }
-    } else {
-        desktop.hot_active = true;
     }
 }
-unsafe fn mouse_move(x: i32, y: i32) {
-    LASTX = x;
-    LASTY = y;
-
-    if DELAY {
-        return ();
+/// Shows the taskbar and puts auto-hide back without going through the session, for exits
+/// that skip `close_callback`.
+#[cfg(windows)]
+fn restore_taskbar() {
+    recovery::restore(
+        &mut Win32Shell,
+        &recovery::find_taskbars(&Win32Shell, &load_rules()),
+        recovery::default_path().as_deref(),
+        autohide::default_path().as_deref(),
+    );
+}
+#[cfg(windows)]
+fn catch_exits() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        error!("{}", info);
+        restore_taskbar();
+        default_hook(info);
+    }));
+    unsafe { SetConsoleCtrlHandler(Some(console_handler), TRUE) };
+    // Shutdown may still be called off, the taskbar hides again when the mouse moves
+    window::on_end_session(
+        || {
+            let taskbars = recovery::find_taskbars(&Win32Shell, &load_rules());
+            let _ = recovery::show_taskbars(&mut Win32Shell, &taskbars);
+        },
+        restore_taskbar,
+    );
+}
+/// Ctrl+C, closing the console, logging off or shutting down, on the console's own thread.
+#[cfg(windows)]
+unsafe extern "system" fn console_handler(_event: DWORD) -> BOOL {
+    restore_taskbar();
+    // Only the window handle is read, it is set before the handler is installed
+    if let Some(window) = (*addr_of_mut!(SESSION)).window {
+        PostMessageW(window.handle, WM_CLOSE, 0, 0);
     }
-
-    if desktop.hot_active {
-        if desktop.tray.is_hot_corner(x, y) {
-            on_hot_corner();
+    TRUE
+}
+/// The built-in rules for finding the shell, with the user's `rules.toml` over them.
+#[cfg(windows)]
+fn load_rules() -> Rules {
+    let path = rules::default_path();
+    match Rules::with_user_file(path.as_deref()) {
+        Ok(rules) => rules,
+        Err(error) => {
+            warn!("Invalid rules file, using the built-in rules: {}", error);
+            Rules::builtin()
         }
-    } else {
-        if desktop.last_window != desktop.tray.start_menu && !desktop.tray.is_tray_region(x, y) {
-            on_leaving_corner(false);
+    }
+}
+#[cfg(windows)]
+fn record(
+    recorder: &mut Option<Recording>,
+    write: impl FnOnce(&mut Recording) -> std::io::Result<()>,
+) {
+    if let Some(writer) = recorder.as_mut() {
+        if let Err(error) = write(writer) {
+            error!("Could not write recording, stopping: {}", error);
+            *recorder = None;
         }
     }
 }
 
+#[cfg(windows)]
 windows_hook! {
     pub fn mouse_hook(context: &mut MouseLL) {
-        if  unsafe { !desktop.enabled } {
-            return ();
-        }
-
         if context.message() == WM_MOUSEMOVE {
-            unsafe { mouse_move(context.pt_x(), context.pt_y()) };
+            let (x, y) = (context.pt_x(), context.pt_y());
+            let session = unsafe { session() };
+            record(&mut session.recorder, |recorder| recorder.mouse_moved(x, y));
+            session.dispatch(Event::MouseMoved { x, y });
         }
     }
 }
+#[cfg(windows)]
 winevent_hook! {
     pub fn fg_hook(context: &mut FgWinEvent) {
-        let hwnd = context.get_hwnd();
-        
-        unsafe{
-            if desktop.foreground_changed(hwnd) && !desktop.tray.is_tray_region(LASTX, LASTY) {
-                on_leaving_corner(true);
-            } 
-            #[cfg(debug_assertions)]
-            desktop._debug_window(hwnd)
-        };
+        let window = window_id(context.get_hwnd());
+        let session = unsafe { session() };
+
+        if session.desktop.set_foreground(window) {
+            session.schedule(COMPOSITOR_DELAY, Timer::MenuCheck(window));
+        } else {
+            let desktop = &session.desktop;
+            record(&mut session.recorder, |recorder| recorder.foreground_changed(desktop, window));
+            session.dispatch(Event::ForegroundChanged { menu_closed: false });
+        }
+        session.desktop._debug_window(window);
     }
 }
 
 fn get_sensitivity(value: &str) -> Result<i32, &'static str>{
     match value.parse::<i32>(){
-        Ok(sensitivity) => if sensitivity > 0 && sensitivity <= 100 {
-            Ok(sensitivity)
-        } else {
-            Err("Sensitivity must be between 1-100")
-        },
+        Ok(sensitivity) => config::validate_sensitivity(sensitivity),
         Err(_) => Err("Expected sensitivity=X, where X is 1-100")
     }
 }
@@ -153,36 +589,265 @@ fn get_property(argument: String) -> (String, String) {
     )
 }
 
+/// Value of `--flag=value`, or of `--flag value` if no `=` was given.
+fn value_or_next(prop: &str, value: &str, args: &mut impl Iterator<Item = String>) -> Result<String, String> {
+    if !value.is_empty() {
+        Ok(value.to_string())
+    } else {
+        args.next()
+            .ok_or_else(|| format!("Expected a value for {}: Use --help for a list of parameters.", prop))
+    }
+}
+
+enum Mode {
+    Run,
+    Replay(String),
+    /// Send a request to the running instance.
+    Control(Request),
+    /// Put back the auto-hide setting saved by a previous run.
+    RestoreAutoHide,
+    /// Write every window to a file, for when the shell can't be found.
+    DumpWindows(String),
+}
+
+/// Where `--record` writes to.
+type Recording = Recorder<LineWriter<File>>;
+
+struct Options {
+    mode: Mode,
+    config_path: Option<PathBuf>,
+    overrides: Overrides,
+    log_filter: Filter,
+    recorder: Option<Recording>,
+}
+
 fn main() {
-    for (index, (prop, value)) in std::env::args().map(|arg| get_property(arg)).enumerate() {
-        match (index, &prop[..], &value[..]) {
-            (index, _, _) if index == 0 => continue,
-            (_, "--selector", _) => unsafe { CORNER_ACTION = CornerAction::DesktopSelector },
-            (_, "--no-hide", _) => unsafe { TRAY_ACTION = TrayAction::NoHide },
-            (_, "--sensitivity", sensitivity) => match get_sensitivity(sensitivity){
-                Ok(sensitivity) => unsafe { SENSITIVITY = sensitivity },
-                Err(error) => {
-                    println!("Invalid sensitivity: {}", error);
-                    return ();
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            attach_console();
+            println!("{}", message);
+            return;
+        }
+    };
+    if !matches!(options.mode, Mode::Run) {
+        attach_console();
+    }
+    match options.mode {
+        Mode::Run => {
+            start_logging(options.log_filter);
+            run(options.config_path, options.overrides, options.recorder)
+        },
+        Mode::Replay(path) => replay(&path),
+        Mode::Control(request) => control(request),
+        Mode::RestoreAutoHide => restore_auto_hide(),
+        Mode::DumpWindows(path) => dump_windows(&path),
+    }
+}
+
+// A release build has no console of its own, so --help, the other modes and their
+// errors would print nowhere. Borrows the one it was started from, if any, unless
+// the output is already redirected somewhere. Never for a normal run, which would
+// then log into that console and quit along with it
+#[cfg(windows)]
+fn attach_console() {
+    unsafe {
+        if GetStdHandle(STD_OUTPUT_HANDLE).is_null() {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+#[cfg(windows)]
+fn control(request: Request) {
+    match pipe::send(request) {
+        Ok(reply) => {
+            if let Reply::Error(_) = reply {
+                eprintln!("{}", reply);
+                std::process::exit(1);
+            }
+            println!("{}", reply);
+        }
+        Err(error) => {
+            eprintln!("Could not {}: {}", request.name(), error);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn control(_request: Request) {
+    eprintln!("WinGnome only runs on Windows, there is no instance to talk to");
+    std::process::exit(1);
+}
+
+#[cfg(windows)]
+fn restore_auto_hide() {
+    let mut auto_hide = AutoHide::open(autohide::default_path());
+    let taskbar = load_rules()
+        .find(&Win32Shell, Role::Taskbar, &Context::default())
+        .unwrap_or_default();
+    if let Err(error) = auto_hide.apply(false, &mut Win32Shell, taskbar) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(windows))]
+fn restore_auto_hide() {}
+
+#[cfg(windows)]
+fn dump_windows(path: &str) {
+    let tree = WindowTree::capture(&Win32Shell);
+    let written = File::create(path).and_then(|file| {
+        serde_json::to_writer_pretty(file, &tree).map_err(std::io::Error::from)
+    });
+    match written {
+        Ok(()) => println!("Wrote {} windows to \"{}\"", tree.windows.len(), path),
+        Err(error) => {
+            println!("Could not write \"{}\": {}", path, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn dump_windows(_path: &str) {
+    println!("WinGnome can only dump the windows of a Windows desktop");
+    std::process::exit(1);
+}
+
+fn replay(path: &str) {
+    let actions = File::open(path).and_then(|file| record::replay(BufReader::new(file)));
+    match actions {
+        Ok(actions) => {
+            for action in actions {
+                println!("{:>8}ms  {:?} -> {:?}", action.time, action.event, action.command);
+            }
+        }
+        Err(error) => println!("Could not replay \"{}\": {}", path, error),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn run(config_path: Option<PathBuf>, overrides: Overrides, _recorder: Option<Recording>) {
+    let (config, config_path) = load_config(config_path, &overrides);
+    linux::run(config, config_path, overrides);
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn run(_config_path: Option<PathBuf>, _overrides: Overrides, _recorder: Option<Recording>) {
+    println!("WinGnome only runs on Windows and X11, use --replay to play back a recording");
+}
+
+/// Reads the command line, or returns what to print before exiting: the help or what's wrong.
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let mut options = Options {
+        mode: Mode::Run,
+        config_path: None,
+        overrides: Overrides::default(),
+        log_filter: Filter::default(),
+        recorder: None,
+    };
+
+    while let Some((prop, value)) = args.next().map(get_property) {
+        match (&prop[..], &value[..]) {
+            ("--selector", _) => options.overrides.corner_action = Some(CornerAction::DesktopSelector),
+            ("--no-hide", _) => options.overrides.hide_tray = Some(false),
+            ("--sensitivity", sensitivity) => match get_sensitivity(sensitivity){
+                Ok(sensitivity) => options.overrides.sensitivity = Some(sensitivity),
+                Err(error) => return Err(format!("Invalid sensitivity: {}", error)),
+            },
+            ("--record", path) => {
+                let path = value_or_next(&prop, path, &mut args)?;
+                match File::create(&path) {
+                    Ok(file) => options.recorder = Some(Recorder::new(LineWriter::new(file))),
+                    Err(error) => {
+                        return Err(format!("Could not create recording \"{}\": {}", path, error))
+                    }
+                }
+            },
+            ("--restore-auto-hide", _) => options.mode = Mode::RestoreAutoHide,
+            ("--dump-windows", path) => options.mode = Mode::DumpWindows(value_or_next(&prop, path, &mut args)?),
+            ("--replay", path) => options.mode = Mode::Replay(value_or_next(&prop, path, &mut args)?),
+            ("--log-level", spec) => {
+                match Filter::parse(&value_or_next(&prop, spec, &mut args)?) {
+                    Ok(filter) => options.log_filter = filter,
+                    Err(error) => return Err(format!("Invalid log level: {}", error)),
                 }
             },
-            (_, "--help", _) => {
-                println!("WinGnome 0.1");
-                println!(
-                    "\t--selector\tOpens Desktop selector on hot corner as opposed to opening menu\n\
+            ("--config", path) => {
+                options.config_path = Some(PathBuf::from(value_or_next(&prop, path, &mut args)?))
+            },
+            ("--help", _) => {
+                return Err("WinGnome 0.1\n\
+                     Usage: win-gnome [OPTIONS] [enable|disable|toggle|status|reload|quit]\n\n\
+                     \t--selector\tOpens Desktop selector on hot corner as opposed to opening menu\n\
                      \t--sensitivity=X\tSpecifies size of hot corner as percent of start button, must be between 1-100\n\
-                     \t--no-hide\tDon't hide tray"
-                );
-                return ();
+                     \t--no-hide\tDon't hide tray\n\
+                     \t--config FILE\tReads options from FILE instead of %APPDATA%\\WinGnome\\config.toml\n\
+                     \t--log-level SPEC\tLogs at SPEC, like debug or info,win_gnome::desktop=trace (default info)\n\
+                     \t--record FILE\tRecords mouse, foreground and hotkey events to FILE (Windows only)\n\
+                     \t--replay FILE\tPlays back a recording against a simulated shell and prints the resulting actions\n\
+                     \t--restore-auto-hide\tPuts the taskbar's auto-hide setting back the way it was before WinGnome, then exits\n\
+                     \t--dump-windows FILE\tWrites every window to FILE, to attach when WinGnome can't find the taskbar (Windows only)\n\
+                     \nCommands, sent to the instance that's already running:\n\
+                     \tenable, disable, toggle\tSwitches the hot corner like Win+Esc does\n\
+                     \tstatus\t\tPrints whether the hot corner is enabled\n\
+                     \treload\t\tReads the config file again\n\
+                     \tquit\t\tShows the taskbar and exits"
+                    .to_string());
+            },
+            (command, _) => match Request::parse(command) {
+                Some(request) if value.is_empty() => options.mode = Mode::Control(request),
+                _ => {
+                    return Err(format!(
+                        "Invalid argument \"{}\": Use --help for a list of parameters.",
+                        prop
+                    ))
+                }
             },
-            _ => {
-                println!("Invalid argument \"{}\": Use --help for a list of parameters.", prop);
-                return ();
-            }
         }
     }
+    Ok(options)
+}
+
+/// Logs to the log file next to the config, or only to stdout if it can't be opened.
+fn start_logging(filter: Filter) {
+    if let Err(error) = logging::init(filter.clone(), logging::default_path().as_deref()) {
+        let _ = logging::init(filter, None);
+        warn!("{}", error);
+    }
+    info!("WinGnome {} starting", env!("CARGO_PKG_VERSION"));
+}
+
+/// The config file merged with the command line, and where it came from.
+fn load_config(config_path: Option<PathBuf>, overrides: &Overrides) -> (Config, Option<PathBuf>) {
+    let config_path = config_path.or_else(Config::default_path);
+    let config = config_path
+        .as_ref()
+        .map_or(Ok(Config::default()), |path| Config::load(path))
+        .unwrap_or_else(|error| {
+            warn!("Invalid config file, using defaults: {}", error);
+            Config::default()
+        })
+        .merge(overrides);
+    (config, config_path)
+}
+
+#[cfg(windows)]
+fn run(config_path: Option<PathBuf>, overrides: Overrides, recorder: Option<Recording>) {
+    let (config, config_path) = load_config(config_path, &overrides);
 
     unsafe {
+        CLOCK.now();
+        if !win_gnome::shell::win32::set_dpi_awareness() {
+            warn!("Could not become DPI aware, corners may be off on scaled monitors");
+        }
         if window::previous_instance(IDENTIFIER) {
             MessageBoxW(
                 null_mut(),
@@ -190,53 +855,22 @@ fn main() {
                 win32_string("WinGnome").as_ptr(),
                 MB_OK | MB_ICONEXCLAMATION,
             );
-            return ();
+            return;
         }
 
         let _window = window::create_hidden_window(IDENTIFIER).unwrap();
-        desktop = loop {
-            match Desktop::new() {
-                Ok(next_desktop) => break next_desktop,
-                Err(error) => {
-                    println!("Could not fetch desktop: {}\nTrying again in 1 second...", error);
-                    std::thread::sleep(Duration::from_secs(1));
-                }
-            }
-        };
-
-        let hotkey_callback = || {
-            let on = !desktop.toggle();
-            if TRAY_ACTION == TrayAction::Hide {
-                if on {
-                    desktop.tray.show();
-                } else {
-                    desktop.tray.hide();
-                }
-            }
-            true
-        };
-
-        let close_callback = || {
-            desktop.enabled = false;
-            desktop.tray.show();
-            false
-        };
+        session().window = Some(_window);
+        session().recorder = recorder;
+        catch_exits();
+        session().open(&config, config_path, overrides);
 
         let _mhook = mouse_hook()
             .expect("Unable to install system-wide mouse hook");
         let _fhook = fg_hook()
             .expect("Unable to install system-side foreground hook");
-        RegisterHotKey(_window.handle, 0, MOD_WIN as u32, VK_ESCAPE as u32);
+        session().start(&config);
 
-        if TRAY_ACTION == TrayAction::Hide {
-            desktop.tray.hide();
-        }
-
-        loop {
-            if !window::handle_message(&_window, &hotkey_callback, &close_callback) {
-                break;
-            }
-        }
+        window::run(session());
     }
 
-}
\ No newline at end of file
+}
@@ -0,0 +1,226 @@
+/*!
+The notification area icon's menu.
+
+The menu is built from a [`MenuState`] as a list of [`MenuEntry`]s, and every
+item carries the [`MenuCommand`] it stands for. Platform code only has to draw
+the entries, map the chosen item back with [`MenuCommand::from_id`] and carry
+out the [`MenuAction`] it resolves to:
+
+```text
+[x] Enabled
+    Corner action   >  [x] Start menu / Desktop selector / Show desktop / Lock / Nothing
+    Sensitivity     >  25% / 50% / 75% / [x] 100%
+    ---
+    Open config file
+    View log
+    ---
+    Quit
+```
+!*/
+
+use crate::control::Request;
+use crate::engine::CornerAction;
+use std::path::{Path, PathBuf};
+
+/// Corner actions offered in the menu. Key chords and programs need the config file.
+pub const CORNER_ACTIONS: [(CornerAction, &str); 5] = [
+    (CornerAction::StartMenu, "Start menu"),
+    (CornerAction::DesktopSelector, "Desktop selector"),
+    (CornerAction::ShowDesktop, "Show desktop"),
+    (CornerAction::Lock, "Lock"),
+    (CornerAction::Nothing, "Nothing"),
+];
+pub const SENSITIVITY_PRESETS: [i32; 4] = [25, 50, 75, 100];
+
+// Item ids, corner actions and sensitivities are offset by their index and value
+const TOGGLE_ID: u32 = 1;
+const OPEN_CONFIG_ID: u32 = 2;
+const VIEW_LOG_ID: u32 = 3;
+const QUIT_ID: u32 = 4;
+const CORNER_ACTION_ID: u32 = 100;
+const SENSITIVITY_ID: u32 = 200;
+
+/// What the menu shows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MenuState {
+    pub enabled: bool,
+    pub corner_action: CornerAction,
+    pub sensitivity: i32,
+    pub config_path: Option<PathBuf>,
+    pub log_path: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuCommand {
+    Toggle,
+    CornerAction(CornerAction),
+    Sensitivity(i32),
+    OpenConfig,
+    ViewLog,
+    Quit,
+}
+
+impl MenuCommand {
+    /// A nonzero id for the item, as menus on Windows want.
+    pub fn id(&self) -> u32 {
+        match self {
+            MenuCommand::Toggle => TOGGLE_ID,
+            MenuCommand::OpenConfig => OPEN_CONFIG_ID,
+            MenuCommand::ViewLog => VIEW_LOG_ID,
+            MenuCommand::Quit => QUIT_ID,
+            MenuCommand::CornerAction(action) => {
+                let index = CORNER_ACTIONS
+                    .iter()
+                    .position(|(preset, _)| preset == action)
+                    .unwrap_or(0);
+                CORNER_ACTION_ID + index as u32
+            }
+            MenuCommand::Sensitivity(sensitivity) => SENSITIVITY_ID + *sensitivity as u32,
+        }
+    }
+    pub fn from_id(id: u32) -> Option<MenuCommand> {
+        match id {
+            TOGGLE_ID => Some(MenuCommand::Toggle),
+            OPEN_CONFIG_ID => Some(MenuCommand::OpenConfig),
+            VIEW_LOG_ID => Some(MenuCommand::ViewLog),
+            QUIT_ID => Some(MenuCommand::Quit),
+            id if id >= SENSITIVITY_ID => SENSITIVITY_PRESETS
+                .iter()
+                .find(|&&sensitivity| SENSITIVITY_ID + sensitivity as u32 == id)
+                .map(|&sensitivity| MenuCommand::Sensitivity(sensitivity)),
+            id if id >= CORNER_ACTION_ID => CORNER_ACTIONS
+                .get((id - CORNER_ACTION_ID) as usize)
+                .map(|(action, _)| MenuCommand::CornerAction(action.clone())),
+            _ => None,
+        }
+    }
+    /// What choosing the item does, None if it can't do anything right now.
+    pub fn action(&self, state: &MenuState) -> Option<MenuAction> {
+        match self {
+            MenuCommand::Toggle => Some(MenuAction::Request(Request::Toggle)),
+            MenuCommand::Quit => Some(MenuAction::Request(Request::Quit)),
+            MenuCommand::CornerAction(action) => Some(MenuAction::SetCornerAction(action.clone())),
+            MenuCommand::Sensitivity(sensitivity) => Some(MenuAction::SetSensitivity(*sensitivity)),
+            MenuCommand::OpenConfig => state.config_path.as_deref().map(open_file),
+            MenuCommand::ViewLog => state.log_path.as_deref().map(open_file),
+        }
+    }
+}
+
+/// What a menu item does to the running instance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    /// Handled like the same request from `win-gnome toggle` or `win-gnome quit`.
+    Request(Request),
+    /// Until the config file is reloaded.
+    SetCornerAction(CornerAction),
+    SetSensitivity(i32),
+    Run(CornerAction),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MenuItem {
+    pub label: String,
+    pub command: MenuCommand,
+    pub checked: bool,
+    /// Grayed out items can't be chosen.
+    pub grayed: bool,
+}
+
+impl MenuItem {
+    fn new(label: &str, command: MenuCommand) -> MenuItem {
+        MenuItem {
+            label: label.to_string(),
+            command,
+            checked: false,
+            grayed: false,
+        }
+    }
+    fn checked(mut self, checked: bool) -> MenuItem {
+        self.checked = checked;
+        self
+    }
+    fn grayed(mut self, grayed: bool) -> MenuItem {
+        self.grayed = grayed;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuEntry {
+    Item(MenuItem),
+    Submenu(String, Vec<MenuEntry>),
+    Separator,
+}
+
+impl MenuEntry {
+    /// The items in this entry and its submenus.
+    pub fn items(&self) -> Vec<&MenuItem> {
+        match self {
+            MenuEntry::Item(item) => vec![item],
+            MenuEntry::Submenu(_, entries) => entries.iter().flat_map(MenuEntry::items).collect(),
+            MenuEntry::Separator => vec![],
+        }
+    }
+}
+
+pub fn build(state: &MenuState) -> Vec<MenuEntry> {
+    let corner_actions = CORNER_ACTIONS
+        .iter()
+        .map(|(action, label)| {
+            MenuEntry::Item(
+                MenuItem::new(label, MenuCommand::CornerAction(action.clone()))
+                    .checked(*action == state.corner_action),
+            )
+        })
+        .collect();
+    let sensitivities = SENSITIVITY_PRESETS
+        .iter()
+        .map(|&sensitivity| {
+            MenuEntry::Item(
+                MenuItem::new(
+                    &format!("{}%", sensitivity),
+                    MenuCommand::Sensitivity(sensitivity),
+                )
+                .checked(sensitivity == state.sensitivity),
+            )
+        })
+        .collect();
+    vec![
+        MenuEntry::Item(MenuItem::new("Enabled", MenuCommand::Toggle).checked(state.enabled)),
+        MenuEntry::Submenu("Corner action".to_string(), corner_actions),
+        MenuEntry::Submenu("Sensitivity".to_string(), sensitivities),
+        MenuEntry::Separator,
+        MenuEntry::Item(
+            MenuItem::new("Open config file", MenuCommand::OpenConfig)
+                .grayed(state.config_path.is_none()),
+        ),
+        MenuEntry::Item(
+            MenuItem::new("View log", MenuCommand::ViewLog).grayed(state.log_path.is_none()),
+        ),
+        MenuEntry::Separator,
+        MenuEntry::Item(MenuItem::new("Quit", MenuCommand::Quit)),
+    ]
+}
+
+/// Hover text for the icon.
+pub fn tooltip(enabled: bool) -> &'static str {
+    if enabled {
+        "WinGnome"
+    } else {
+        "WinGnome (disabled)"
+    }
+}
+
+/// Opens `path` in a text editor.
+pub fn open_file(path: &Path) -> MenuAction {
+    let program = if cfg!(windows) {
+        "notepad.exe"
+    } else {
+        "xdg-open"
+    };
+    MenuAction::Run(CornerAction::Launch {
+        program: program.to_string(),
+        args: vec![path.display().to_string()],
+    })
+}
@@ -0,0 +1,136 @@
+/*!
+Monitor layout.
+
+Windows puts the primary monitor's top left corner at `(0, 0)` and places the
+others around it, so a monitor to the left or above the primary one has
+negative coordinates. A [`Layout`] maps points on that virtual screen to the
+monitor they fall on and to that monitor's own coordinates, which is what
+[`TrayGeometry`](crate::geometry::TrayGeometry) works in.
+!*/
+
+use crate::dpi::Scale;
+pub use crate::dpi::DEFAULT_DPI;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Monitor {
+    /// `(top, bottom, left, right)` on the virtual screen.
+    pub pos: (i32, i32, i32, i32),
+    pub dpi: u32,
+    pub primary: bool,
+}
+
+impl Monitor {
+    pub fn new(top: i32, bottom: i32, left: i32, right: i32) -> Monitor {
+        Monitor {
+            pos: (top, bottom, left, right),
+            dpi: DEFAULT_DPI,
+            primary: false,
+        }
+    }
+    pub fn primary(mut self) -> Monitor {
+        self.primary = true;
+        self
+    }
+    pub fn dpi(mut self, dpi: u32) -> Monitor {
+        self.dpi = dpi;
+        self
+    }
+    pub fn scale(&self) -> Scale {
+        Scale::new(self.dpi)
+    }
+    pub fn dimensions(&self) -> (i32, i32) {
+        let (top, bottom, left, right) = self.pos;
+        (right - left, bottom - top)
+    }
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let (top, bottom, left, right) = self.pos;
+        x >= left && x < right && y >= top && y < bottom
+    }
+    /// `(x, y)` relative to this monitor's top left corner.
+    pub fn to_local(&self, x: i32, y: i32) -> (i32, i32) {
+        let (top, _, left, _) = self.pos;
+        (x - left, y - top)
+    }
+    /// A window rect relative to this monitor's top left corner.
+    pub fn to_local_pos(
+        &self,
+        (top, bottom, left, right): (i32, i32, i32, i32),
+    ) -> (i32, i32, i32, i32) {
+        let (monitor_top, _, monitor_left, _) = self.pos;
+        (
+            top - monitor_top,
+            bottom - monitor_top,
+            left - monitor_left,
+            right - monitor_left,
+        )
+    }
+    /// How far `(x, y)` is from this monitor, 0 when it is on it.
+    fn distance(&self, x: i32, y: i32) -> i64 {
+        let (top, bottom, left, right) = self.pos;
+        let dx = (left - x).max(x - (right - 1)).max(0) as i64;
+        let dy = (top - y).max(y - (bottom - 1)).max(0) as i64;
+        dx * dx + dy * dy
+    }
+    fn overlap(&self, (top, bottom, left, right): (i32, i32, i32, i32)) -> i64 {
+        let (monitor_top, monitor_bottom, monitor_left, monitor_right) = self.pos;
+        let width = (right.min(monitor_right) - left.max(monitor_left)).max(0) as i64;
+        let height = (bottom.min(monitor_bottom) - top.max(monitor_top)).max(0) as i64;
+        width * height
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Layout {
+    pub monitors: Vec<Monitor>,
+}
+
+impl Layout {
+    pub const fn new(monitors: Vec<Monitor>) -> Layout {
+        Layout { monitors }
+    }
+    /// One primary monitor of the given size.
+    pub fn single(width: i32, height: i32) -> Layout {
+        Layout::new(vec![Monitor::new(0, height, 0, width).primary()])
+    }
+    /// The rect around every monitor, as `(top, bottom, left, right)`.
+    pub fn bounds(&self) -> (i32, i32, i32, i32) {
+        let mut monitors = self.monitors.iter().map(|monitor| monitor.pos);
+        let first = monitors.next().unwrap_or((0, 0, 0, 0));
+        monitors.fold(first, |(t, b, l, r), (top, bottom, left, right)| {
+            (t.min(top), b.max(bottom), l.min(left), r.max(right))
+        })
+    }
+    pub fn primary(&self) -> Option<usize> {
+        self.monitors
+            .iter()
+            .position(|monitor| monitor.primary)
+            .or_else(|| Some(0).filter(|_| !self.monitors.is_empty()))
+    }
+    /// The monitor `(x, y)` is on, or the closest one if it is off screen.
+    pub fn monitor_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.monitors
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, monitor)| monitor.distance(x, y))
+            .map(|(index, _)| index)
+    }
+    /// The monitor a window rect mostly sits on.
+    pub fn monitor_for(&self, pos: (i32, i32, i32, i32)) -> Option<usize> {
+        let (top, bottom, left, right) = pos;
+        self.monitors
+            .iter()
+            .enumerate()
+            .filter(|(_, monitor)| monitor.overlap(pos) > 0)
+            .max_by_key(|(index, monitor)| (monitor.overlap(pos), std::cmp::Reverse(*index)))
+            .map(|(index, _)| index)
+            .or_else(|| self.monitor_at(left + (right - left) / 2, top + (bottom - top) / 2))
+    }
+    /// The monitor under `(x, y)` and the point in that monitor's coordinates.
+    pub fn to_local(&self, x: i32, y: i32) -> Option<(usize, i32, i32)> {
+        let index = self.monitor_at(x, y)?;
+        let (x, y) = self.monitors[index].to_local(x, y);
+        Some((index, x, y))
+    }
+}
@@ -0,0 +1,164 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Error};
+use std::os::windows::io::{AsRawHandle, FromRawHandle};
+use std::ptr::null_mut;
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use winapi::shared::winerror::{ERROR_FILE_NOT_FOUND, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::fileapi::FlushFileBuffers;
+use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+use winapi::um::namedpipeapi::{
+    ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, WaitNamedPipeW,
+};
+use winapi::um::processthreadsapi::{GetCurrentProcessId, ProcessIdToSessionId};
+use winapi::um::winbase::{
+    FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE,
+    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_WAIT,
+};
+use winapi::um::winuser::{PostMessageW, WM_APP, WM_CLOSE};
+
+use crate::window::{win32_string, Window};
+use log::error;
+use win_gnome::control::{self, Reply, Request};
+
+/// Posted to the window when a request is waiting in the channel.
+pub const WM_CONTROL: u32 = WM_APP + 1;
+
+/// How long a client waits on the message loop before it gives up on a reply.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a client waits for the instance to finish with the one before it, in milliseconds.
+const BUSY_TIMEOUT: u32 = 2000;
+
+/// A request and where to send its reply.
+pub type Pending = (Request, Sender<Reply>);
+
+// Answers requests on the pipe from a thread of its own, handing each one to the
+// message loop through `requests` so all state stays on the main thread
+pub fn start_server(window: &Window, requests: Sender<Pending>) {
+    let window = window.handle as usize;
+    thread::spawn(move || {
+        let mut pipe = match create() {
+            Ok(pipe) => pipe,
+            Err(error) => {
+                error!(
+                    "Could not create control pipe, remote commands won't work: {}",
+                    error
+                );
+                return;
+            }
+        };
+        loop {
+            if let Err(error) = connect(&pipe) {
+                error!("Control pipe failed, remote commands won't work: {}", error);
+                return;
+            }
+            let served = control::serve(&mut pipe, |request| {
+                let (reply_sender, reply) = channel();
+                if requests.send((request, reply_sender)).is_err() {
+                    return Reply::Error("Shutting down".to_string());
+                }
+                unsafe { PostMessageW(window as _, WM_CONTROL, 0, 0) };
+                // The loop may be stuck in a menu or message box, leaving the
+                // client hanging on the pipe would block the next one too
+                match reply.recv_timeout(REPLY_TIMEOUT) {
+                    Ok(reply) => reply,
+                    Err(RecvTimeoutError::Timeout) => {
+                        Reply::Error("win-gnome is busy, try again".to_string())
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        Reply::Error("Shutting down".to_string())
+                    }
+                }
+            });
+            disconnect(&pipe);
+            if let Ok(Request::Quit) = served {
+                // The reply is out, now it is safe to close
+                unsafe { PostMessageW(window as _, WM_CLOSE, 0, 0) };
+                return;
+            }
+        }
+    });
+}
+
+// The pipe of this Windows session, see `control::pipe_name`
+fn pipe_name() -> String {
+    let mut session = 0;
+    unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session) };
+    control::pipe_name(session)
+}
+
+// Fails if anything else already holds the name, so no other program can answer
+// in its place, and only takes clients from this machine
+fn create() -> Result<File, Error> {
+    unsafe {
+        let handle = CreateNamedPipeW(
+            win32_string(&pipe_name()).as_ptr(),
+            PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            1,
+            4096,
+            4096,
+            0,
+            null_mut(),
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            Err(Error::last_os_error())
+        } else {
+            Ok(File::from_raw_handle(handle as _))
+        }
+    }
+}
+
+// Waits for a client
+fn connect(pipe: &File) -> Result<(), Error> {
+    unsafe {
+        if ConnectNamedPipe(pipe.as_raw_handle() as _, null_mut()) == 0
+            && GetLastError() != ERROR_PIPE_CONNECTED
+        {
+            return Err(Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+// Lets the client read the reply, then frees the pipe for the next one
+fn disconnect(pipe: &File) {
+    unsafe {
+        FlushFileBuffers(pipe.as_raw_handle() as _);
+        DisconnectNamedPipe(pipe.as_raw_handle() as _);
+    }
+}
+
+/// Sends `request` to the running instance. Not running is a `NotFound` error.
+pub fn send(request: Request) -> io::Result<Reply> {
+    let name = pipe_name();
+    let mut waited = false;
+    let mut pipe = loop {
+        let error = match OpenOptions::new().read(true).write(true).open(&name) {
+            Ok(pipe) => break pipe,
+            Err(error) => error,
+        };
+        match error.raw_os_error().map(|code| code as u32) {
+            Some(ERROR_FILE_NOT_FOUND) => {
+                return Err(Error::new(
+                    io::ErrorKind::NotFound,
+                    "win-gnome isn't running",
+                ))
+            }
+            // Another client has the only instance, wait for it once
+            Some(ERROR_PIPE_BUSY) if !waited => {
+                waited = true;
+                unsafe { WaitNamedPipeW(win32_string(&name).as_ptr(), BUSY_TIMEOUT) };
+            }
+            Some(ERROR_PIPE_BUSY) => {
+                return Err(Error::new(error.kind(), "win-gnome is busy, try again"))
+            }
+            _ => return Err(error),
+        }
+    };
+    control::send(&mut pipe, request)
+}
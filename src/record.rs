@@ -0,0 +1,415 @@
+/*!
+Recording and replaying hook traffic.
+
+A recording is a JSON lines file. The first line snapshots the shell windows
+`Desktop` found along with the options in effect, every following line is one
+mouse move, foreground change or hotkey press, stamped with the milliseconds
+since recording started:
+
+```text
+{"time":0,"event":"start","sensitivity":100,"corner_action":"start_menu",...}
+{"time":16,"event":"move","x":2,"y":1079}
+{"time":412,"event":"foreground","window":1312,"windows":[...]}
+{"time":900,"event":"hotkey"}
+```
+
+[`replay`] rebuilds the shell as a [`FakeShell`] and feeds the recording back
+through [`HotCornerEngine`], so a misfire can be reproduced on any machine.
+
+A [`WindowTree`] is a snapshot of every window instead, written by
+`--dump-windows` for when `Desktop` can't find the shell at all.
+!*/
+
+use crate::corners::CornerMap;
+use crate::desktop::Desktop;
+use crate::engine::{Command, CornerAction, Event, HotCornerEngine, TimerKind, TrayAction};
+use crate::geometry::CornerSize;
+use crate::monitor::Monitor;
+use crate::region::Regions;
+use crate::rules::Rules;
+use crate::shell::{FakeShell, FakeWindow, ShellBackend, WindowId};
+use crate::timer::Timers;
+use crate::trigger::TriggerSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+/// A window as it looked when it was recorded.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Captured {
+    pub id: WindowId,
+    #[serde(flatten)]
+    pub window: FakeWindow,
+}
+
+impl Captured {
+    pub fn new(shell: &impl ShellBackend, id: WindowId) -> Captured {
+        Captured {
+            id,
+            window: FakeWindow::capture(shell, id),
+        }
+    }
+}
+
+/// Every window of a shell at one moment.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowTree {
+    #[serde(default)]
+    pub build: Option<u32>,
+    pub desktop: WindowId,
+    pub foreground: WindowId,
+    #[serde(default)]
+    pub monitors: Vec<Monitor>,
+    /// Parents come before their children.
+    pub windows: Vec<Captured>,
+}
+
+impl WindowTree {
+    /// Walks the top-level windows and all of their children.
+    pub fn capture(shell: &impl ShellBackend) -> WindowTree {
+        let desktop = shell.desktop_window();
+        let mut windows = vec![Captured::new(shell, desktop)];
+        let mut parents = vec![None];
+
+        while let Some(parent) = parents.pop() {
+            let mut window = shell.find_window_ex(parent, None, None, None);
+            while let Some(current) = window {
+                windows.push(Captured::new(shell, current));
+                parents.push(Some(current));
+                window = shell.find_window_ex(parent, Some(current), None, None);
+            }
+        }
+        WindowTree {
+            build: shell.os_build(),
+            desktop,
+            foreground: shell.foreground_window(),
+            monitors: shell.monitors(),
+            windows,
+        }
+    }
+    pub fn into_shell(self) -> io::Result<FakeShell> {
+        self.build().map(|(shell, _)| shell)
+    }
+    /// The shell along with the fake handle of each recorded one.
+    fn build(self) -> io::Result<(FakeShell, HashMap<WindowId, WindowId>)> {
+        let desktop = self.desktop;
+        let (top, bottom, left, right) = self
+            .windows
+            .iter()
+            .find(|captured| captured.id == desktop)
+            .map(|captured| captured.window.pos)
+            .ok_or_else(|| invalid("missing the desktop window"))?;
+        let mut shell = FakeShell::new(right - left, bottom - top);
+        shell.build = self.build;
+        if !self.monitors.is_empty() {
+            shell.monitors = self.monitors;
+        }
+        let mut ids = HashMap::new();
+        ids.insert(desktop, shell.desktop_window());
+        for captured in self
+            .windows
+            .into_iter()
+            .filter(|captured| captured.id != desktop)
+        {
+            upsert(&mut shell, &mut ids, captured);
+        }
+        if let Some(foreground) = ids.get(&self.foreground) {
+            shell.set_foreground(*foreground);
+        }
+        Ok((shell, ids))
+    }
+}
+
+/// Adds a recorded window, or updates it if it was seen before.
+fn upsert(shell: &mut FakeShell, ids: &mut HashMap<WindowId, WindowId>, captured: Captured) {
+    let mut window = captured.window;
+    window.parent = window.parent.and_then(|parent| ids.get(&parent).cloned());
+
+    match ids.get(&captured.id).and_then(|id| shell.window_mut(*id)) {
+        Some(existing) => *existing = window,
+        None => {
+            let id = shell.add(window);
+            ids.insert(captured.id, id);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Entry {
+    Start {
+        sensitivity: i32,
+        /// Older recordings only have `sensitivity`.
+        #[serde(default)]
+        corner_size: Option<CornerSize>,
+        corner_action: CornerAction,
+        #[serde(default)]
+        corners: CornerMap,
+        #[serde(default)]
+        regions: Regions,
+        tray_action: TrayAction,
+        #[serde(default)]
+        trigger: TriggerSettings,
+        #[serde(default)]
+        build: Option<u32>,
+        desktop: WindowId,
+        foreground: WindowId,
+        #[serde(default)]
+        monitors: Vec<Monitor>,
+        /// Parents come before their children.
+        windows: Vec<Captured>,
+    },
+    Move {
+        x: i32,
+        y: i32,
+    },
+    Foreground {
+        window: WindowId,
+        /// The new foreground window and the tray windows whose state it depends on.
+        windows: Vec<Captured>,
+    },
+    Hotkey,
+    TriggerCorner,
+    PeekTaskbar,
+    ShellRestarted,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub time: u64,
+    #[serde(flatten)]
+    pub entry: Entry,
+}
+
+pub struct Recorder<W: Write> {
+    out: W,
+    started: Instant,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(out: W) -> Recorder<W> {
+        Recorder {
+            out,
+            started: Instant::now(),
+        }
+    }
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, record)?;
+        self.out.write_all(b"\n")
+    }
+    fn write_now(&mut self, entry: Entry) -> io::Result<()> {
+        let time = self.started.elapsed().as_millis() as u64;
+        self.write(&Record { time, entry })
+    }
+    pub fn start<B: ShellBackend>(
+        &mut self,
+        desktop: &Desktop<B>,
+        engine: &HotCornerEngine,
+    ) -> io::Result<()> {
+        self.started = Instant::now();
+        let shell = &desktop.backend;
+        let top_desktop = shell.desktop_window();
+        let mut windows = vec![
+            top_desktop,
+            desktop.shell_parent,
+            desktop.shell_window,
+            desktop.tray.bar,
+            desktop.tray.start_button,
+            desktop.tray.start_menu,
+            desktop.tray.search,
+            desktop.tray.icon_overflow,
+        ];
+        for tray in &desktop.secondary_trays {
+            windows.push(tray.bar);
+            windows.push(tray.start_button);
+        }
+
+        self.write_now(Entry::Start {
+            sensitivity: desktop.corner_size.button_percent().unwrap_or(100),
+            corner_size: Some(desktop.corner_size),
+            corner_action: engine.corner_action.clone(),
+            corners: engine.corners.clone(),
+            regions: engine.regions.clone(),
+            tray_action: engine.tray_action,
+            trigger: engine.trigger.settings,
+            build: shell.os_build(),
+            desktop: top_desktop,
+            foreground: desktop.last_window,
+            monitors: desktop.layout.monitors.clone(),
+            windows: windows
+                .iter()
+                .filter(|window| !window.is_null())
+                .map(|window| Captured::new(shell, *window))
+                .collect(),
+        })
+    }
+    pub fn mouse_moved(&mut self, x: i32, y: i32) -> io::Result<()> {
+        self.write_now(Entry::Move { x, y })
+    }
+    /// Call once `Desktop` has checked the menus so the tray state is current.
+    pub fn foreground_changed<B: ShellBackend>(
+        &mut self,
+        desktop: &Desktop<B>,
+        window: WindowId,
+    ) -> io::Result<()> {
+        let shell = &desktop.backend;
+        let mut windows = vec![Captured::new(shell, window)];
+        for related in &[
+            desktop.tray.start_menu,
+            desktop.tray.search,
+            desktop.tray.icon_overflow,
+        ] {
+            if *related != window && !related.is_null() {
+                windows.push(Captured::new(shell, *related));
+            }
+        }
+        self.write_now(Entry::Foreground { window, windows })
+    }
+    pub fn hotkey(&mut self) -> io::Result<()> {
+        self.write_now(Entry::Hotkey)
+    }
+    pub fn trigger_corner(&mut self) -> io::Result<()> {
+        self.write_now(Entry::TriggerCorner)
+    }
+    pub fn peek_taskbar(&mut self) -> io::Result<()> {
+        self.write_now(Entry::PeekTaskbar)
+    }
+    pub fn shell_restarted(&mut self) -> io::Result<()> {
+        self.write_now(Entry::ShellRestarted)
+    }
+}
+
+/// A command the engine issued during a replay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Action {
+    pub time: u64,
+    pub event: Event,
+    pub command: Command,
+}
+
+struct Replay {
+    desktop: Desktop<FakeShell>,
+    engine: HotCornerEngine,
+    /// Recorded handles to their fake counterparts.
+    ids: HashMap<WindowId, WindowId>,
+    timers: Timers<TimerKind>,
+    actions: Vec<Action>,
+}
+
+impl Replay {
+    fn new(entry: Entry) -> io::Result<Replay> {
+        let (corner_size, mut engine, tree) = match entry {
+            Entry::Start {
+                sensitivity,
+                corner_size,
+                corner_action,
+                corners,
+                regions,
+                tray_action,
+                trigger,
+                build,
+                desktop,
+                foreground,
+                monitors,
+                windows,
+            } => {
+                let mut engine = HotCornerEngine::new(corner_action, tray_action);
+                engine.corners = corners;
+                engine.regions = regions;
+                engine.trigger.settings = trigger;
+                let tree = WindowTree {
+                    build,
+                    desktop,
+                    foreground,
+                    monitors,
+                    windows,
+                };
+                let corner_size = corner_size.unwrap_or(CornerSize::Button(sensitivity));
+                (corner_size, engine, tree)
+            }
+            _ => return Err(invalid("recording does not begin with a start entry")),
+        };
+        let (shell, ids) = tree.build()?;
+
+        let mut desktop =
+            Desktop::with_rules(shell, corner_size, Rules::builtin()).map_err(invalid)?;
+        for command in engine.start() {
+            desktop.execute(command);
+        }
+        Ok(Replay {
+            desktop,
+            engine,
+            ids,
+            timers: Timers::new(),
+            actions: vec![],
+        })
+    }
+    fn dispatch(&mut self, time: u64, event: Event) {
+        for command in self.engine.handle_at(time, event, &mut self.desktop) {
+            if let Command::StartTimer(kind, delay) = command {
+                self.timers.schedule(time, delay, kind);
+            }
+            self.desktop.execute(command.clone());
+            self.actions.push(Action {
+                time,
+                event,
+                command,
+            });
+        }
+    }
+    /// Fires every timer due by `time`, in order.
+    fn run_timers(&mut self, time: u64) {
+        while let Some((due, kind)) = self.timers.pop_due(time) {
+            self.dispatch(due, Event::TimerElapsed(kind));
+        }
+    }
+    fn play(&mut self, record: Record) {
+        self.run_timers(record.time);
+
+        match record.entry {
+            Entry::Start { .. } => {}
+            Entry::Move { x, y } => self.dispatch(record.time, Event::MouseMoved { x, y }),
+            Entry::Foreground { window, windows } => {
+                for captured in windows {
+                    upsert(&mut self.desktop.backend, &mut self.ids, captured);
+                }
+                let window = self.ids[&window];
+                self.desktop.backend.set_foreground(window);
+                let menu_closed = self.desktop.foreground_changed(window);
+                self.dispatch(record.time, Event::ForegroundChanged { menu_closed });
+            }
+            Entry::Hotkey => self.dispatch(record.time, Event::Hotkey),
+            Entry::TriggerCorner => self.dispatch(record.time, Event::TriggerCorner),
+            Entry::PeekTaskbar => self.dispatch(record.time, Event::PeekTaskbar),
+            // The recorded windows are all there is, so the fake shell has nothing to restart
+            Entry::ShellRestarted => self.dispatch(record.time, Event::ShellRestarted),
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Plays a recording against a simulated shell and returns every command issued.
+pub fn replay(input: impl BufRead) -> io::Result<Vec<Action>> {
+    let mut replay: Option<Replay> = None;
+
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(&line)?;
+
+        match replay.as_mut() {
+            Some(replay) => replay.play(record),
+            None => replay = Some(Replay::new(record.entry)?),
+        }
+    }
+
+    let mut replay = replay.ok_or_else(|| invalid("recording is empty"))?;
+    replay.run_timers(u64::MAX);
+    Ok(replay.actions)
+}
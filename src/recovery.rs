@@ -0,0 +1,186 @@
+/*!
+Getting the taskbar back however win-gnome stops.
+
+Closing the window shows the taskbar again, but a panic (release builds
+abort), Ctrl+C, logging off or being killed skip that. The platform code calls
+[`restore`] from a panic hook, the console control handler and
+`WM_ENDSESSION`. For what can't be caught, [`DirtyMarker`] keeps a file around
+while the taskbar is hidden, and the next run shows the taskbar if it finds
+one.
+
+X11 docks lose their strut while hidden, so on X11 the marker also lists each
+[`HiddenDock`] with the strut it had, see [`ewmh`](crate::ewmh).
+!*/
+
+use crate::autohide::AutoHide;
+use crate::engine::Command;
+use crate::rules::{Context, Role, Rules};
+use crate::shell::{DockBackend, ShellBackend, Strut, WindowId};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The primary taskbar and the ones on other monitors as `rules` find them, without
+/// needing a `Desktop`. The primary one comes first.
+pub fn find_taskbars(shell: &impl ShellBackend, rules: &Rules) -> Vec<WindowId> {
+    let context = Context::default();
+    let mut bars = rules.find_all(shell, Role::Taskbar, &context);
+    for bar in rules.find_all(shell, Role::SecondaryTaskbar, &context) {
+        if !bars.contains(&bar) {
+            bars.push(bar);
+        }
+    }
+    bars
+}
+
+/// Shows every one of `taskbars`, returns how many there are.
+pub fn show_taskbars(shell: &mut impl ShellBackend, taskbars: &[WindowId]) -> usize {
+    for bar in taskbars {
+        shell.set_visible(*bar, true);
+    }
+    taskbars.len()
+}
+
+/// A dock win-gnome hides and the strut to give it back.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HiddenDock {
+    pub window: WindowId,
+    pub strut: Option<Strut>,
+}
+
+/// Maps `docks` again and gives them their struts back, returns how many are still around.
+pub fn show_docks(shell: &mut impl DockBackend, docks: &[HiddenDock]) -> usize {
+    let mut shown = 0;
+    for dock in docks {
+        if !shell.is_window(dock.window) {
+            continue;
+        }
+        shell.set_visible(dock.window, true);
+        if let Some(strut) = dock.strut {
+            shell.set_strut(dock.window, Some(strut));
+        }
+        shown += 1;
+    }
+    shown
+}
+
+/// Shows `taskbars`, puts auto-hide back on the first and clears the marker, ignoring
+/// whatever state the rest of the program is in.
+pub fn restore(
+    shell: &mut impl ShellBackend,
+    taskbars: &[WindowId],
+    marker: Option<&Path>,
+    auto_hide: Option<&Path>,
+) {
+    show_taskbars(shell, taskbars);
+    if let Some(&taskbar) = taskbars.first() {
+        let _ = AutoHide::open(auto_hide.map(Path::to_path_buf)).apply(false, shell, taskbar);
+    }
+    let _ = DirtyMarker::open(marker.map(Path::to_path_buf)).clear();
+}
+
+/// A file that exists exactly while the taskbar is hidden.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirtyMarker {
+    pub path: Option<PathBuf>,
+    hidden: bool,
+}
+
+impl DirtyMarker {
+    pub const fn new() -> DirtyMarker {
+        DirtyMarker {
+            path: None,
+            hidden: false,
+        }
+    }
+    pub fn open(path: Option<PathBuf>) -> DirtyMarker {
+        let hidden = path.as_deref().is_some_and(Path::exists);
+        DirtyMarker { path, hidden }
+    }
+    /// Whether the taskbar was left hidden, by this run or one that didn't get to show it.
+    pub fn is_dirty(&self) -> bool {
+        self.hidden
+    }
+    /// Follows a command on its way to the desktop, writing the file only when that changes.
+    pub fn track(&mut self, command: &Command) -> io::Result<()> {
+        match command {
+            Command::HideTray if !self.hidden => self.set(),
+            Command::ShowTray if self.hidden => self.clear(),
+            _ => Ok(()),
+        }
+    }
+    fn set(&mut self) -> io::Result<()> {
+        self.write(&[])
+    }
+    /// Writes the marker listing `docks` before they are hidden, so a run that finds it
+    /// can give them back their struts.
+    pub fn hide_docks(&mut self, docks: &[HiddenDock]) -> io::Result<()> {
+        self.write(docks)
+    }
+    /// The docks listed by `hide_docks`, one per line after the process id.
+    pub fn hidden_docks(&self) -> Vec<HiddenDock> {
+        let text = self
+            .path
+            .as_deref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        text.lines()
+            .skip(1)
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+    fn write(&mut self, docks: &[HiddenDock]) -> io::Result<()> {
+        self.hidden = true;
+        match &self.path {
+            Some(path) => {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                let mut text = format!("{}\n", std::process::id());
+                for dock in docks {
+                    text += &serde_json::to_string(dock)?;
+                    text.push('\n');
+                }
+                fs::write(path, text)
+            }
+            None => Ok(()),
+        }
+    }
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.hidden = false;
+        match self.path.as_deref().map(fs::remove_file) {
+            Some(Err(ref error)) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            Some(result) => result,
+            None => Ok(()),
+        }
+    }
+    /// Shows `taskbars` if a previous run left them hidden, returns whether it had to.
+    pub fn recover(
+        &mut self,
+        shell: &mut impl ShellBackend,
+        taskbars: &[WindowId],
+    ) -> io::Result<bool> {
+        if !self.hidden {
+            return Ok(false);
+        }
+        show_taskbars(shell, taskbars);
+        self.clear()?;
+        Ok(true)
+    }
+    /// `recover` for the docks listed by `hide_docks`.
+    pub fn recover_docks(&mut self, shell: &mut impl DockBackend) -> io::Result<bool> {
+        if !self.hidden {
+            return Ok(false);
+        }
+        show_docks(shell, &self.hidden_docks());
+        self.clear()?;
+        Ok(true)
+    }
+}
+
+/// `taskbar-hidden` next to the config file.
+pub fn default_path() -> Option<PathBuf> {
+    crate::config::Config::default_path()
+        .and_then(|config| Some(config.parent()?.join("taskbar-hidden")))
+}
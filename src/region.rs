@@ -0,0 +1,161 @@
+/*!
+Corner shapes and dead zones.
+
+A hot corner is a rectangle by default, so sliding the cursor along the bottom
+of the screen towards the start button fires it as soon as it crosses the
+corner's far side. A [`Shape`] cuts the inside of the rectangle away, so only
+the cursor heading for the corner itself gets there:
+
+```toml
+[regions]
+# "rect", "triangle" or "radius" for every corner
+shape = "radius"
+# Or one corner at a time
+top_right = "triangle"
+
+# Rectangles where no corner or edge ever fires, in device-independent pixels
+# of the monitor. Negative x and y count from the right and bottom.
+[[regions.dead_zone]]
+x = -400
+y = -48
+width = 400
+height = 48
+```
+
+Edges are a row of pixels and have no shape, dead zones still apply to them.
+Everything here is plain arithmetic on points relative to a corner, the
+screen layout is left to [`TrayGeometry`].
+!*/
+
+use crate::geometry::{Corner, TrayGeometry};
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shape {
+    /// The whole corner.
+    Rect,
+    /// Below the diagonal between the corner's two inner ends.
+    Triangle,
+    /// A quarter ellipse around the corner, a quarter circle for a square corner.
+    Radius,
+}
+
+impl Shape {
+    /// Whether a point `(dx, dy)` from the corner falls in a `shape` corner `width`
+    /// by `height` pixels. Distances are counted inwards from the screen's edges,
+    /// points past the edges count as on them.
+    pub fn contains(self, (dx, dy): (i32, i32), (width, height): (i32, i32)) -> bool {
+        if width <= 0 || height <= 0 {
+            return false;
+        }
+        let (dx, dy) = (i64::from(dx.max(0)), i64::from(dy.max(0)));
+        let (width, height) = (i64::from(width), i64::from(height));
+        match self {
+            Shape::Rect => dx < width && dy < height,
+            Shape::Triangle => dx * height + dy * width < width * height,
+            Shape::Radius => {
+                dx * dx * height * height + dy * dy * width * width
+                    < width * width * height * height
+            }
+        }
+    }
+}
+
+/// A rectangle where corners never fire.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeadZone {
+    /// From the left of the monitor, or its right when negative.
+    pub x: i32,
+    /// From the top of the monitor, or its bottom when negative.
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl DeadZone {
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> DeadZone {
+        DeadZone {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.width > 0 && self.height > 0 {
+            Ok(())
+        } else {
+            Err("A dead zone needs a width and height")
+        }
+    }
+    /// Whether `(x, y)` on a `screen` sized monitor falls in the zone. Everything
+    /// is in the same pixels, points past the screen's edges count as on them.
+    pub fn contains(&self, (width, height): (i32, i32), x: i32, y: i32) -> bool {
+        let from = |offset: i32, side: i32| if offset < 0 { side + offset } else { offset };
+        let (left, top) = (from(self.x, width), from(self.y, height));
+        let (x, y) = (x.max(0).min(width - 1), y.max(0).min(height - 1));
+        x >= left && x < left + self.width && y >= top && y < top + self.height
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Regions {
+    /// Of every corner without one of its own.
+    pub shape: Shape,
+    pub top_left: Option<Shape>,
+    pub top_right: Option<Shape>,
+    pub bottom_left: Option<Shape>,
+    pub bottom_right: Option<Shape>,
+    #[serde(rename = "dead_zone")]
+    pub dead_zones: Vec<DeadZone>,
+}
+
+impl Regions {
+    /// Rectangular corners and no dead zones, how corners have always been.
+    pub const fn new() -> Regions {
+        Regions {
+            shape: Shape::Rect,
+            top_left: None,
+            top_right: None,
+            bottom_left: None,
+            bottom_right: None,
+            dead_zones: Vec::new(),
+        }
+    }
+    pub fn validate(&self) -> Result<(), &'static str> {
+        for zone in &self.dead_zones {
+            zone.validate()?;
+        }
+        Ok(())
+    }
+    /// The shape of `corner`, edges are always a `Rect`.
+    pub fn shape_of(&self, corner: Corner) -> Shape {
+        let shape = match corner {
+            Corner::TopLeft => self.top_left,
+            Corner::TopRight => self.top_right,
+            Corner::BottomLeft => self.bottom_left,
+            Corner::BottomRight => self.bottom_right,
+            _ => return Shape::Rect,
+        };
+        shape.unwrap_or(self.shape)
+    }
+    /// Whether `(x, y)`, in `geometry`'s coordinates, is in a dead zone.
+    pub fn is_dead(&self, geometry: &TrayGeometry, x: i32, y: i32) -> bool {
+        let scale = geometry.scale;
+        let screen = (geometry.parent_width, geometry.parent_height);
+        self.dead_zones.iter().any(|zone| {
+            let (left, top) = scale.size_to_physical((zone.x, zone.y));
+            let (width, height) = scale.size_to_physical((zone.width, zone.height));
+            DeadZone::new(left, top, width, height).contains(screen, x, y)
+        })
+    }
+}
+
+impl Default for Regions {
+    fn default() -> Regions {
+        Regions::new()
+    }
+}
@@ -0,0 +1,361 @@
+/*!
+Rules for finding Explorer's windows.
+
+Which class and title the taskbar, start menu or desktop icons go by changes
+with Windows feature updates, so instead of being spelled out in `Desktop`
+they are [`Rule`]s in `shared/rules.toml`, built into the program:
+
+```toml
+version = 1
+
+[[rule]]
+role = "start_menu"
+class = "Windows.UI.Core.CoreWindow"
+title = "Start"
+shell = "windows11"
+```
+
+A `rules.toml` next to the config file overrides them a [`Role`] at a time, so
+a broken rule can be fixed without waiting for a release. `version` is the
+format of the file, a file newer than this program understands is refused.
+
+[`Rules`] evaluates them against any [`ShellBackend`], so they can be checked
+against recorded window trees without Windows.
+!*/
+
+use crate::geometry::TrayGeometry;
+use crate::shell::{ShellBackend, WindowId};
+use crate::taskbar::ShellVersion;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The newest rules file format this program reads.
+pub const RULES_VERSION: u32 = 1;
+/// `shared/rules.toml`
+pub const DEFAULT_RULES: &str = include_str!("../shared/rules.toml");
+
+/// `(top, bottom, left, right)` a window has to be at, `None` for anywhere.
+pub type Offsets = (Option<i32>, Option<i32>, Option<i32>, Option<i32>);
+
+/// What a window found by a rule is to win-gnome.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Taskbar,
+    /// A taskbar on another monitor.
+    SecondaryTaskbar,
+    /// Part of the Windows 11 taskbar, used to tell the versions apart.
+    XamlIsland,
+    StartButton,
+    StartMenu,
+    Search,
+    IconOverflow,
+    /// The window the desktop icons live in, `WorkerW` or `Progman`.
+    DesktopHost,
+    /// The desktop icons, `SHELLDLL_DefView`.
+    DesktopView,
+}
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Taskbar => "taskbar",
+            Role::SecondaryTaskbar => "secondary_taskbar",
+            Role::XamlIsland => "xaml_island",
+            Role::StartButton => "start_button",
+            Role::StartMenu => "start_menu",
+            Role::Search => "search",
+            Role::IconOverflow => "icon_overflow",
+            Role::DesktopHost => "desktop_host",
+            Role::DesktopView => "desktop_view",
+        }
+    }
+}
+
+/// Where a window has to be.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Position {
+    /// As big as the desktop window.
+    FillsDesktop,
+    /// Where the start menu opens next to the start button, see
+    /// `TrayGeometry::get_menu_offsets`.
+    BesideStartButton,
+}
+
+/// What the rules are evaluated against besides the windows themselves.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Context {
+    /// None until the taskbar is found, only rules for every version apply until then.
+    pub version: Option<ShellVersion>,
+    /// Size of the desktop window.
+    pub desktop: (i32, i32),
+    /// None until the start button is found.
+    pub menu_offsets: Option<Offsets>,
+}
+
+impl Context {
+    pub const fn new(version: Option<ShellVersion>, desktop: (i32, i32)) -> Context {
+        Context {
+            version,
+            desktop,
+            menu_offsets: None,
+        }
+    }
+    /// Adds where the start menu opens for a tray measured as `geometry`.
+    pub fn with_tray(mut self, geometry: &TrayGeometry) -> Context {
+        self.menu_offsets = Some(TrayGeometry::get_menu_offsets(
+            geometry.start_width,
+            geometry.parent_width,
+            geometry.orientation,
+        ));
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub role: Role,
+    pub class: Option<String>,
+    pub title: Option<String>,
+    /// Role of the window this one is a child of, None for top-level windows.
+    pub parent: Option<Role>,
+    /// None for every version.
+    pub shell: Option<ShellVersion>,
+    pub visible: Option<bool>,
+    pub min_size: Option<(i32, i32)>,
+    pub position: Option<Position>,
+}
+
+impl Rule {
+    pub fn applies(&self, version: Option<ShellVersion>) -> bool {
+        self.shell.is_none() || self.shell == version
+    }
+    /// Whether `window` fits the rule, leaving its parent to the caller.
+    pub fn matches(&self, shell: &impl ShellBackend, window: WindowId, context: &Context) -> bool {
+        let field = |expected: &Option<String>, actual: Option<String>| {
+            expected
+                .as_deref()
+                .is_none_or(|expected| actual.as_deref().unwrap_or_default() == expected)
+        };
+        let (width, height) = shell.window_dimensions(window);
+        let (top, bottom, left, right) = shell.window_pos(window);
+        let at = |expected: Option<i32>, actual: i32| expected.is_none_or(|pos| pos == actual);
+
+        field(&self.class, shell.class_name(window))
+            && field(&self.title, shell.title(window))
+            && self
+                .visible
+                .is_none_or(|visible| shell.is_visible(window) == visible)
+            && self
+                .min_size
+                .is_none_or(|(min_width, min_height)| width >= min_width && height >= min_height)
+            && self.position.is_none_or(|position| match position {
+                Position::FillsDesktop => (width, height) == context.desktop,
+                Position::BesideStartButton => context.menu_offsets.is_some_and(|(t, b, l, r)| {
+                    at(t, top) && at(b, bottom) && at(l, left) && at(r, right)
+                }),
+            })
+    }
+    /// A rule without a class or title would match nearly every window.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.class.is_none() && self.title.is_none() {
+            Err(format!(
+                "A {} rule needs a class or title",
+                self.role.name()
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Rules in the order they are tried.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    pub version: u32,
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+impl Rules {
+    /// No rules at all, which find nothing.
+    pub const fn new() -> Rules {
+        Rules {
+            version: RULES_VERSION,
+            rules: Vec::new(),
+        }
+    }
+    pub fn builtin() -> Rules {
+        Rules::parse(DEFAULT_RULES).expect("built-in rules are invalid")
+    }
+    pub fn parse(text: &str) -> Result<Rules, String> {
+        let rules: Rules = toml::from_str(text).map_err(|error| error.to_string())?;
+        if rules.version == 0 || rules.version > RULES_VERSION {
+            return Err(format!(
+                "Rules version {} is not one this win-gnome understands, it reads up to {}",
+                rules.version, RULES_VERSION
+            ));
+        }
+        rules.validate()?;
+        Ok(rules)
+    }
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in &self.rules {
+            rule.validate()?;
+            if self.ancestors(rule.role).contains(&rule.role) {
+                return Err(format!(
+                    "The {} rules are their own parent",
+                    rule.role.name()
+                ));
+            }
+        }
+        Ok(())
+    }
+    /// Every role a window of `role` may be nested in.
+    fn ancestors(&self, role: Role) -> Vec<Role> {
+        let mut ancestors: Vec<Role> = vec![];
+        let mut next = vec![role];
+        while let Some(role) = next.pop() {
+            for parent in self.rules.iter().filter(|rule| rule.role == role) {
+                if let Some(parent) = parent.parent.filter(|parent| !ancestors.contains(parent)) {
+                    ancestors.push(parent);
+                    next.push(parent);
+                }
+            }
+        }
+        ancestors
+    }
+    /// These rules with every role `user` has rules for replaced by those.
+    pub fn overridden_by(mut self, user: Rules) -> Result<Rules, String> {
+        self.rules
+            .retain(|rule| !user.rules.iter().any(|other| other.role == rule.role));
+        self.rules.extend(user.rules);
+        self.validate()?;
+        Ok(self)
+    }
+    /// The built-in rules, overridden by the file at `path` if there is one.
+    pub fn with_user_file(path: Option<&Path>) -> Result<Rules, String> {
+        let rules = Rules::builtin();
+        match path.map(fs::read_to_string) {
+            Some(Ok(text)) => rules.overridden_by(Rules::parse(&text)?),
+            Some(Err(ref error)) if error.kind() == io::ErrorKind::NotFound => Ok(rules),
+            Some(Err(error)) => Err(error.to_string()),
+            None => Ok(rules),
+        }
+    }
+    pub fn for_role(
+        &self,
+        role: Role,
+        version: Option<ShellVersion>,
+    ) -> impl Iterator<Item = &Rule> {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.role == role && rule.applies(version))
+    }
+    /// The first window found by the first rule for `role` that finds one.
+    pub fn find(
+        &self,
+        shell: &impl ShellBackend,
+        role: Role,
+        context: &Context,
+    ) -> Option<WindowId> {
+        self.for_role(role, context.version)
+            .find_map(|rule| self.found_by(shell, rule, context).into_iter().next())
+    }
+    /// Every window found by the rules for `role`, in the order the rules found them.
+    pub fn find_all(
+        &self,
+        shell: &impl ShellBackend,
+        role: Role,
+        context: &Context,
+    ) -> Vec<WindowId> {
+        let mut windows = vec![];
+        for rule in self.for_role(role, context.version) {
+            for window in self.found_by(shell, rule, context) {
+                if !windows.contains(&window) {
+                    windows.push(window);
+                }
+            }
+        }
+        windows
+    }
+    /// The first child of `parent` found by a rule for `role`, whatever parent the rule names.
+    pub fn find_in(
+        &self,
+        shell: &impl ShellBackend,
+        role: Role,
+        parent: WindowId,
+        context: &Context,
+    ) -> Option<WindowId> {
+        self.for_role(role, context.version).find_map(|rule| {
+            children(shell, rule, Some(parent), context)
+                .into_iter()
+                .next()
+        })
+    }
+    /// Whether a rule for `role` matches `window` and, if the rule names one, its parent.
+    pub fn is(
+        &self,
+        shell: &impl ShellBackend,
+        window: WindowId,
+        role: Role,
+        context: &Context,
+    ) -> bool {
+        !window.is_null()
+            && self.for_role(role, context.version).any(|rule| {
+                rule.matches(shell, window, context)
+                    && rule.parent.is_none_or(|role| {
+                        shell
+                            .parent(window)
+                            .is_some_and(|parent| self.is(shell, parent, role, context))
+                    })
+            })
+    }
+    fn found_by(&self, shell: &impl ShellBackend, rule: &Rule, context: &Context) -> Vec<WindowId> {
+        match rule.parent {
+            Some(role) => self
+                .find_all(shell, role, context)
+                .into_iter()
+                .flat_map(|parent| children(shell, rule, Some(parent), context))
+                .collect(),
+            None => children(shell, rule, None, context),
+        }
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules::new()
+    }
+}
+
+/// Children of `parent`, or top-level windows, that match `rule`.
+fn children(
+    shell: &impl ShellBackend,
+    rule: &Rule,
+    parent: Option<WindowId>,
+    context: &Context,
+) -> Vec<WindowId> {
+    let (class_name, title) = (rule.class.as_deref(), rule.title.as_deref());
+    let mut windows = vec![];
+    let mut window = shell.find_window_ex(parent, None, class_name, title);
+
+    while let Some(current) = window {
+        if rule.matches(shell, current, context) {
+            windows.push(current);
+        }
+        window = shell.find_window_ex(parent, Some(current), class_name, title);
+    }
+    windows
+}
+
+/// `rules.toml` next to the config file.
+pub fn default_path() -> Option<PathBuf> {
+    crate::config::Config::default_path()
+        .and_then(|config| Some(config.parent()?.join("rules.toml")))
+}
@@ -0,0 +1,488 @@
+/*!
+In-memory window tree for tests and replays.
+!*/
+
+use super::{DockBackend, ShellBackend, Strut, WindowId};
+use crate::autohide::AppBarState;
+use crate::fullscreen::NotificationState;
+use crate::monitor::{Layout, Monitor};
+use crate::taskbar::{ShellVersion, CORE_WINDOW};
+use serde::{Deserialize, Serialize};
+
+/// Top-level windows `restart_explorer` replaces, along with their children.
+const EXPLORER_CLASSES: [&str; 7] = [
+    "WorkerW",
+    "Progman",
+    "Shell_TrayWnd",
+    "Shell_SecondaryTrayWnd",
+    CORE_WINDOW,
+    "NotifyIconOverflowWindow",
+    "TopLevelWindowForOverflowXamlIsland",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FakeWindow {
+    pub class_name: String,
+    pub title: String,
+    /// Empty when the owning program is unknown.
+    pub process: String,
+    pub parent: Option<WindowId>,
+    /// `(top, bottom, left, right)`
+    pub pos: (i32, i32, i32, i32),
+    pub style: u32,
+    pub ex_style: u32,
+    pub visible: bool,
+    pub cloaked: bool,
+    /// A `_NET_WM_WINDOW_TYPE_DOCK` window.
+    pub dock: bool,
+    pub strut: Option<Strut>,
+    pub transient_for: Option<WindowId>,
+}
+
+impl FakeWindow {
+    /// Copies what a backend knows about a real window.
+    pub fn capture(shell: &impl ShellBackend, window: WindowId) -> FakeWindow {
+        FakeWindow {
+            class_name: shell.class_name(window).unwrap_or_default(),
+            title: shell.title(window).unwrap_or_default(),
+            process: shell.process_name(window).unwrap_or_default(),
+            parent: shell.parent(window),
+            pos: shell.window_pos(window),
+            style: shell.style(window),
+            ex_style: shell.ex_style(window),
+            visible: shell.is_visible(window),
+            cloaked: shell.is_cloaked(window),
+            ..FakeWindow::default()
+        }
+    }
+    pub fn new(class_name: &str, title: &str) -> FakeWindow {
+        FakeWindow {
+            class_name: class_name.to_string(),
+            title: title.to_string(),
+            visible: true,
+            ..FakeWindow::default()
+        }
+    }
+    pub fn parent(mut self, parent: WindowId) -> FakeWindow {
+        self.parent = Some(parent);
+        self
+    }
+    pub fn process(mut self, process: &str) -> FakeWindow {
+        self.process = process.to_string();
+        self
+    }
+    pub fn pos(mut self, top: i32, bottom: i32, left: i32, right: i32) -> FakeWindow {
+        self.pos = (top, bottom, left, right);
+        self
+    }
+    pub fn style(mut self, style: u32) -> FakeWindow {
+        self.style = style;
+        self
+    }
+    pub fn cloaked(mut self, cloaked: bool) -> FakeWindow {
+        self.cloaked = cloaked;
+        self
+    }
+    /// Makes this a dock reserving `strut`.
+    pub fn dock(mut self, strut: Option<Strut>) -> FakeWindow {
+        self.dock = true;
+        self.strut = strut;
+        self
+    }
+    pub fn transient_for(mut self, owner: WindowId) -> FakeWindow {
+        self.transient_for = Some(owner);
+        self
+    }
+}
+
+/// Scriptable stand-in for the shell.
+///
+/// Windows live in creation order, which doubles as their z-order for searches.
+/// Every key chord sent through the backend is kept in `sent_keys`, programs in `launched`.
+#[derive(Clone, Debug)]
+pub struct FakeShell {
+    windows: Vec<(WindowId, FakeWindow)>,
+    next_id: usize,
+    desktop: WindowId,
+    foreground: WindowId,
+    /// Starts out as a single primary monitor the size of the desktop.
+    pub monitors: Vec<Monitor>,
+    pub sent_keys: Vec<Vec<u8>>,
+    pub launched: Vec<(String, Vec<String>)>,
+    pub locked: bool,
+    pub notification_state: NotificationState,
+    /// Always on top without auto-hide, as Windows starts out.
+    pub app_bar: Option<AppBarState>,
+    /// Makes `set_app_bar_state` fail, reading still works.
+    pub app_bar_locked: bool,
+    /// Makes `set_visible` fail.
+    pub visibility_locked: bool,
+    /// None until a Windows layout is added.
+    pub build: Option<u32>,
+}
+
+impl FakeShell {
+    /// An empty shell with only a desktop window of the given size.
+    pub fn new(width: i32, height: i32) -> FakeShell {
+        let mut shell = FakeShell {
+            windows: vec![],
+            next_id: 1,
+            desktop: WindowId::NULL,
+            foreground: WindowId::NULL,
+            monitors: Layout::single(width, height).monitors,
+            sent_keys: vec![],
+            launched: vec![],
+            locked: false,
+            notification_state: NotificationState::AcceptsNotifications,
+            app_bar: Some(AppBarState::new(false, true)),
+            app_bar_locked: false,
+            visibility_locked: false,
+            build: None,
+        };
+        shell.desktop = shell.add(FakeWindow::new("#32769", "").pos(0, height, 0, width));
+        shell
+    }
+    /// A Windows 10 desktop with a bottom taskbar and a closed start menu.
+    pub fn windows10(width: i32, height: i32) -> FakeShell {
+        let mut shell = FakeShell::new(width, height);
+        shell.build = Some(19045);
+        shell.add_explorer(width, height);
+        shell
+    }
+    /// A Windows 11 desktop with a centered bottom taskbar, closed start menu and search.
+    pub fn windows11(width: i32, height: i32) -> FakeShell {
+        let mut shell = FakeShell::new(width, height);
+        shell.build = Some(22631);
+        shell.add_explorer(width, height);
+        shell
+    }
+    /// Explorer's windows: the desktop icons, taskbar, start menu and notification overflow.
+    fn add_explorer(&mut self, width: i32, height: i32) {
+        match self.build.map(ShellVersion::from_build) {
+            Some(ShellVersion::Windows11) => self.add_explorer11(width, height),
+            _ => self.add_explorer10(width, height),
+        }
+    }
+    fn add_explorer10(&mut self, width: i32, height: i32) {
+        let shell = self;
+        let desktop = shell.desktop;
+        let worker = shell.add(
+            FakeWindow::new("WorkerW", "")
+                .parent(desktop)
+                .pos(0, height, 0, width),
+        );
+        shell.add(
+            FakeWindow::new("SHELLDLL_DefView", "")
+                .parent(worker)
+                .pos(0, height, 0, width),
+        );
+        let bar = shell.add(FakeWindow::new("Shell_TrayWnd", "").parent(desktop).pos(
+            height - 40,
+            height,
+            0,
+            width,
+        ));
+        shell.add(
+            FakeWindow::new("Start", "Start")
+                .parent(bar)
+                .pos(height - 40, height, 0, 48),
+        );
+        shell.add(
+            FakeWindow::new("Windows.UI.Core.CoreWindow", "Cortana")
+                .parent(desktop)
+                .pos(height - 40 - 600, height - 40, 0, 640)
+                .cloaked(true),
+        );
+        let overflow = shell.add(FakeWindow::new("NotifyIconOverflowWindow", "").parent(desktop));
+        shell.window_mut(overflow).unwrap().visible = false;
+        shell.foreground = worker;
+    }
+    /// The desktop icons stay with `Progman`, the start button is a hidden leftover and the
+    /// start menu and search are centered above the taskbar.
+    fn add_explorer11(&mut self, width: i32, height: i32) {
+        let shell = self;
+        let desktop = shell.desktop;
+        let progman = shell.add(
+            FakeWindow::new("Progman", "Program Manager")
+                .parent(desktop)
+                .pos(0, height, 0, width),
+        );
+        shell.add(
+            FakeWindow::new("SHELLDLL_DefView", "")
+                .parent(progman)
+                .pos(0, height, 0, width),
+        );
+        let bar = shell.add(FakeWindow::new("Shell_TrayWnd", "").parent(desktop).pos(
+            height - 48,
+            height,
+            0,
+            width,
+        ));
+        let start = shell.add(FakeWindow::new("Start", "Start").parent(bar).pos(
+            height - 48,
+            height,
+            0,
+            0,
+        ));
+        shell.window_mut(start).unwrap().visible = false;
+        shell.add(
+            FakeWindow::new("Windows.UI.Composition.DesktopWindowContentBridge", "")
+                .parent(bar)
+                .pos(height - 48, height, 0, width),
+        );
+        let center = width / 2;
+        shell.add(
+            FakeWindow::new(CORE_WINDOW, "Start")
+                .parent(desktop)
+                .process("StartMenuExperienceHost.exe")
+                .pos(height - 48 - 734, height - 60, center - 321, center + 321)
+                .cloaked(true),
+        );
+        shell.add(
+            FakeWindow::new(CORE_WINDOW, "Search")
+                .parent(desktop)
+                .process("SearchHost.exe")
+                .pos(height - 48 - 734, height - 60, center - 321, center + 321)
+                .cloaked(true),
+        );
+        let overflow =
+            shell.add(FakeWindow::new("TopLevelWindowForOverflowXamlIsland", "").parent(desktop));
+        shell.window_mut(overflow).unwrap().visible = false;
+        shell.foreground = progman;
+    }
+    /// Closes Explorer's windows and opens them again with new handles, the way a crashed
+    /// or restarted Explorer comes back. Other programs' windows stay.
+    pub fn restart_explorer(&mut self) {
+        let explorer: Vec<WindowId> = self
+            .windows
+            .iter()
+            .filter(|(_, window)| {
+                window.parent.is_none_or(|parent| parent == self.desktop)
+                    && EXPLORER_CLASSES.contains(&&window.class_name[..])
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for window in explorer {
+            self.remove(window);
+        }
+        let (_, height, _, width) = self.window_pos(self.desktop);
+        self.add_explorer(width, height);
+        for monitor in self
+            .monitors
+            .clone()
+            .iter()
+            .filter(|monitor| !monitor.primary)
+        {
+            self.add_secondary_taskbar(monitor);
+        }
+    }
+    /// Adds a monitor with a secondary taskbar along its bottom edge.
+    pub fn add_monitor(&mut self, monitor: Monitor) -> WindowId {
+        self.monitors.push(monitor);
+        self.add_secondary_taskbar(&monitor)
+    }
+    fn add_secondary_taskbar(&mut self, monitor: &Monitor) -> WindowId {
+        let (_, bottom, left, right) = monitor.pos;
+        let bar = self.add(FakeWindow::new("Shell_SecondaryTrayWnd", "").pos(
+            bottom - 40,
+            bottom,
+            left,
+            right,
+        ));
+        if self.build.map(ShellVersion::from_build) == Some(ShellVersion::Windows11) {
+            return bar;
+        }
+        self.add(FakeWindow::new("Start", "Start").parent(bar).pos(
+            bottom - 40,
+            bottom,
+            left,
+            left + 48,
+        ));
+        bar
+    }
+    pub fn add(&mut self, window: FakeWindow) -> WindowId {
+        let id = WindowId(self.next_id);
+        self.next_id += 1;
+        self.windows.push((id, window));
+        id
+    }
+    /// Removes a window along with all of its children.
+    pub fn remove(&mut self, window: WindowId) {
+        let children: Vec<WindowId> = self
+            .windows
+            .iter()
+            .filter(|(_, child)| child.parent == Some(window))
+            .map(|(id, _)| *id)
+            .collect();
+        for child in children {
+            self.remove(child);
+        }
+        self.windows.retain(|(id, _)| *id != window);
+    }
+    pub fn window(&self, window: WindowId) -> Option<&FakeWindow> {
+        self.windows
+            .iter()
+            .find(|(id, _)| *id == window)
+            .map(|(_, window)| window)
+    }
+    pub fn window_mut(&mut self, window: WindowId) -> Option<&mut FakeWindow> {
+        self.windows
+            .iter_mut()
+            .find(|(id, _)| *id == window)
+            .map(|(_, window)| window)
+    }
+    pub fn set_foreground(&mut self, window: WindowId) {
+        self.foreground = window;
+    }
+    /// Iterates over every window in z-order.
+    pub fn windows(&self) -> impl Iterator<Item = &(WindowId, FakeWindow)> {
+        self.windows.iter()
+    }
+    fn effective_parent(&self, window: &FakeWindow) -> WindowId {
+        window.parent.unwrap_or(self.desktop)
+    }
+}
+
+impl ShellBackend for FakeShell {
+    fn desktop_window(&self) -> WindowId {
+        self.desktop
+    }
+    fn foreground_window(&self) -> WindowId {
+        self.foreground
+    }
+    fn find_window_ex(
+        &self,
+        parent: Option<WindowId>,
+        after: Option<WindowId>,
+        class_name: Option<&str>,
+        title: Option<&str>,
+    ) -> Option<WindowId> {
+        let parent = parent.unwrap_or(self.desktop);
+        let start = match after {
+            Some(after) => self.windows.iter().position(|(id, _)| *id == after)? + 1,
+            None => 0,
+        };
+
+        self.windows[start..]
+            .iter()
+            .filter(|(id, _)| *id != self.desktop)
+            .find(|(_, window)| {
+                self.effective_parent(window) == parent
+                    && class_name.is_none_or(|name| window.class_name == name)
+                    && title.is_none_or(|title| window.title == title)
+            })
+            .map(|(id, _)| *id)
+    }
+    fn is_window(&self, window: WindowId) -> bool {
+        self.window(window).is_some()
+    }
+    fn parent(&self, window: WindowId) -> Option<WindowId> {
+        self.window(window).and_then(|window| window.parent)
+    }
+    fn class_name(&self, window: WindowId) -> Option<String> {
+        self.window(window).map(|window| window.class_name.clone())
+    }
+    fn title(&self, window: WindowId) -> Option<String> {
+        self.window(window)
+            .map(|window| window.title.clone())
+            .filter(|title| !title.is_empty())
+    }
+    fn process_name(&self, window: WindowId) -> Option<String> {
+        self.window(window)
+            .map(|window| window.process.clone())
+            .filter(|process| !process.is_empty())
+    }
+    fn window_pos(&self, window: WindowId) -> (i32, i32, i32, i32) {
+        self.window(window)
+            .map_or((0, 0, 0, 0), |window| window.pos)
+    }
+    fn style(&self, window: WindowId) -> u32 {
+        self.window(window).map_or(0, |window| window.style)
+    }
+    fn ex_style(&self, window: WindowId) -> u32 {
+        self.window(window).map_or(0, |window| window.ex_style)
+    }
+    fn is_visible(&self, window: WindowId) -> bool {
+        self.window(window).is_some_and(|window| window.visible)
+    }
+    fn is_cloaked(&self, window: WindowId) -> bool {
+        self.window(window).is_some_and(|window| window.cloaked)
+    }
+    fn set_visible(&mut self, window: WindowId, visible: bool) -> bool {
+        if self.visibility_locked {
+            return false;
+        }
+        match self.window_mut(window) {
+            Some(window) => {
+                window.visible = visible;
+                true
+            }
+            None => false,
+        }
+    }
+    fn send_keys(&mut self, keys: &[u8]) {
+        self.sent_keys.push(keys.to_vec());
+    }
+    fn launch(&mut self, program: &str, args: &[String]) -> bool {
+        self.launched.push((program.to_string(), args.to_vec()));
+        true
+    }
+    fn lock(&mut self) -> bool {
+        self.locked = true;
+        true
+    }
+    fn flush(&self) {}
+    fn monitors(&self) -> Vec<Monitor> {
+        self.monitors.clone()
+    }
+    fn notification_state(&self) -> NotificationState {
+        self.notification_state
+    }
+    fn app_bar_state(&self, taskbar: WindowId) -> Option<AppBarState> {
+        self.app_bar.filter(|_| self.is_window(taskbar))
+    }
+    fn set_app_bar_state(&mut self, taskbar: WindowId, state: AppBarState) -> bool {
+        if self.app_bar_locked || !self.is_window(taskbar) {
+            return false;
+        }
+        match self.app_bar.as_mut() {
+            Some(app_bar) => {
+                *app_bar = state;
+                true
+            }
+            None => false,
+        }
+    }
+    fn os_build(&self) -> Option<u32> {
+        self.build
+    }
+}
+
+impl DockBackend for FakeShell {
+    fn docks(&self) -> Vec<WindowId> {
+        self.windows
+            .iter()
+            .filter(|(_, window)| window.dock)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+    fn is_dock(&self, window: WindowId) -> bool {
+        self.window(window).is_some_and(|window| window.dock)
+    }
+    fn transient_for(&self, window: WindowId) -> Option<WindowId> {
+        self.window(window).and_then(|window| window.transient_for)
+    }
+    fn strut(&self, window: WindowId) -> Option<Strut> {
+        self.window(window).and_then(|window| window.strut)
+    }
+    fn set_strut(&mut self, window: WindowId, strut: Option<Strut>) -> bool {
+        match self.window_mut(window) {
+            Some(window) => {
+                window.strut = strut;
+                true
+            }
+            None => false,
+        }
+    }
+}
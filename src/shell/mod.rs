@@ -0,0 +1,186 @@
+/*!
+Access to the shell's windows.
+
+[`ShellBackend`] is the handful of window manager calls `Desktop` and `Tray` are
+built on. The Win32 and X11 implementations talk to the real desktop,
+[`FakeShell`] keeps an in-memory window tree that tests can script.
+!*/
+
+use crate::autohide::AppBarState;
+use crate::fullscreen::NotificationState;
+use crate::monitor::Monitor;
+use serde::{Deserialize, Serialize};
+
+mod fake;
+pub use self::fake::*;
+#[cfg(windows)]
+pub mod win32;
+#[cfg(target_os = "linux")]
+pub mod x11;
+
+/// Opaque window handle. `WindowId::NULL` stands in for a missing window.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct WindowId(pub usize);
+
+impl WindowId {
+    pub const NULL: WindowId = WindowId(0);
+
+    pub fn is_null(self) -> bool {
+        self == WindowId::NULL
+    }
+}
+
+/// Virtual key codes used by the shell shortcuts.
+pub mod vk {
+    pub const BACK: u8 = 0x08;
+    pub const TAB: u8 = 0x09;
+    pub const RETURN: u8 = 0x0D;
+    pub const SHIFT: u8 = 0x10;
+    pub const CONTROL: u8 = 0x11;
+    pub const MENU: u8 = 0x12;
+    pub const ESCAPE: u8 = 0x1B;
+    pub const SPACE: u8 = 0x20;
+    pub const PRIOR: u8 = 0x21;
+    pub const NEXT: u8 = 0x22;
+    pub const END: u8 = 0x23;
+    pub const HOME: u8 = 0x24;
+    pub const LEFT: u8 = 0x25;
+    pub const UP: u8 = 0x26;
+    pub const RIGHT: u8 = 0x27;
+    pub const DOWN: u8 = 0x28;
+    pub const SNAPSHOT: u8 = 0x2C;
+    pub const INSERT: u8 = 0x2D;
+    pub const DELETE: u8 = 0x2E;
+    pub const LWIN: u8 = 0x5B;
+    pub const F1: u8 = 0x70;
+}
+
+/// `_NET_WM_STRUT_PARTIAL`: the space a dock reserves along the `left`, `right`, `top`
+/// and `bottom` edges, then the start and end of each of those edges.
+pub type Strut = [u32; 12];
+
+pub trait ShellBackend {
+    /// The root window everything else is parented to.
+    fn desktop_window(&self) -> WindowId;
+    fn foreground_window(&self) -> WindowId;
+    /// Finds the next child of `parent` after `after` that matches `class_name` and `title`.
+    ///
+    /// A `parent` of `None` searches top-level windows, `None` filters match anything.
+    fn find_window_ex(
+        &self,
+        parent: Option<WindowId>,
+        after: Option<WindowId>,
+        class_name: Option<&str>,
+        title: Option<&str>,
+    ) -> Option<WindowId>;
+    fn is_window(&self, window: WindowId) -> bool;
+    fn parent(&self, window: WindowId) -> Option<WindowId>;
+    fn class_name(&self, window: WindowId) -> Option<String>;
+    fn title(&self, window: WindowId) -> Option<String>;
+    /// File name of the program that owns the window, like `mstsc.exe`.
+    fn process_name(&self, window: WindowId) -> Option<String>;
+    /// Window rect as `(top, bottom, left, right)`.
+    fn window_pos(&self, window: WindowId) -> (i32, i32, i32, i32);
+    /// Win32 window style, see `fullscreen::style`. Zero where there's no such thing.
+    fn style(&self, window: WindowId) -> u32;
+    fn ex_style(&self, window: WindowId) -> u32;
+    fn is_visible(&self, window: WindowId) -> bool;
+    /// Whether DWM is keeping the window off screen even though it is "visible".
+    fn is_cloaked(&self, window: WindowId) -> bool;
+    /// Returns false when the window no longer exists.
+    fn set_visible(&mut self, window: WindowId, visible: bool) -> bool;
+    /// Presses `keys` in order then releases them in reverse.
+    fn send_keys(&mut self, keys: &[u8]);
+    /// Starts `program` without waiting for it, returns false if it couldn't be started.
+    fn launch(&mut self, program: &str, args: &[String]) -> bool;
+    fn lock(&mut self) -> bool;
+    /// Waits for the compositor's next frame.
+    fn flush(&self);
+    /// Every monitor attached to the desktop, in no particular order.
+    fn monitors(&self) -> Vec<Monitor>;
+    /// Whether the shell knows of a full screen program, a game or a presentation.
+    fn notification_state(&self) -> NotificationState;
+    /// The auto-hide and always on top settings of `taskbar`, None if it isn't one.
+    fn app_bar_state(&self, taskbar: WindowId) -> Option<AppBarState>;
+    fn set_app_bar_state(&mut self, taskbar: WindowId, state: AppBarState) -> bool;
+    /// The Windows build number, like 19045. None where there's no such thing.
+    fn os_build(&self) -> Option<u32>;
+
+    fn find_window(&self, class_name: Option<&str>, title: Option<&str>) -> Option<WindowId> {
+        self.find_window_ex(None, None, class_name, title)
+    }
+    fn find_child(&self, parent: WindowId, class_name: &str) -> Option<WindowId> {
+        self.find_window_ex(Some(parent), None, Some(class_name), None)
+    }
+    /// Every top-level window of `class_name`.
+    fn find_all(&self, class_name: &str) -> Vec<WindowId> {
+        let mut windows = vec![];
+        let mut window = self.find_window(Some(class_name), None);
+
+        while let Some(current) = window {
+            windows.push(current);
+            window = self.find_window_ex(None, Some(current), Some(class_name), None);
+        }
+        windows
+    }
+    fn window_dimensions(&self, window: WindowId) -> (i32, i32) {
+        let (top, bottom, left, right) = self.window_pos(window);
+        (right - left, bottom - top)
+    }
+    fn is_window_named(&self, window: WindowId, class_name: &str, title: &str) -> bool {
+        self.class_name(window).unwrap_or_default() == class_name
+            && self.title(window).unwrap_or_default() == title
+    }
+    fn find_by_dimensions(
+        &self,
+        parent: WindowId,
+        class_name: &str,
+        width: i32,
+        height: i32,
+    ) -> Option<WindowId> {
+        let mut window = self.find_window(Some(class_name), None);
+
+        while let Some(current) = window {
+            if self.window_dimensions(current) == (width, height) {
+                return Some(current);
+            }
+            window = self.find_window_ex(Some(parent), Some(current), Some(class_name), None);
+        }
+        None
+    }
+    fn find_by_position(
+        &self,
+        parent: WindowId,
+        class_name: &str,
+        (top, bottom, left, right): (Option<i32>, Option<i32>, Option<i32>, Option<i32>),
+    ) -> Option<WindowId> {
+        let mut window = self.find_window(Some(class_name), None);
+
+        while let Some(current) = window {
+            let (t, b, l, r) = self.window_pos(current);
+            let matches =
+                |expected: Option<i32>, actual: i32| expected.is_none_or(|pos| pos == actual);
+
+            if matches(top, t) && matches(bottom, b) && matches(left, l) && matches(right, r) {
+                return Some(current);
+            }
+            window = self.find_window_ex(Some(parent), Some(current), Some(class_name), None);
+        }
+        None
+    }
+}
+
+/// Panels on desktops without a Windows taskbar, as EWMH describes them.
+pub trait DockBackend: ShellBackend {
+    /// Windows of type `_NET_WM_WINDOW_TYPE_DOCK`, hidden or not.
+    fn docks(&self) -> Vec<WindowId>;
+    fn is_dock(&self, window: WindowId) -> bool;
+    /// The window `window` is a menu or dialog of, from `WM_TRANSIENT_FOR`.
+    fn transient_for(&self, window: WindowId) -> Option<WindowId>;
+    fn strut(&self, window: WindowId) -> Option<Strut>;
+    /// Sets the strut, or removes it with `None` so other windows can use the space.
+    fn set_strut(&mut self, window: WindowId, strut: Option<Strut>) -> bool;
+}
@@ -0,0 +1,358 @@
+/*!
+The real shell, through Win32 and DWM.
+!*/
+
+use super::{ShellBackend, WindowId};
+use crate::autohide::AppBarState;
+use crate::fullscreen::NotificationState;
+use crate::monitor::{Monitor, DEFAULT_DPI};
+use std::ffi::OsStr;
+use std::iter::once;
+use std::mem;
+use std::os::windows::ffi::OsStrExt;
+use std::process::Command;
+use std::ptr::{null, null_mut};
+use winapi::ctypes::c_void;
+use winapi::shared::minwindef::{BOOL, DWORD, LPARAM, TRUE, UINT};
+use winapi::shared::windef::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2;
+use winapi::shared::windef::{DPI_AWARENESS_CONTEXT, HDC, HMONITOR, HWND, LPRECT, RECT};
+use winapi::shared::winerror::{ERROR_SUCCESS, HRESULT, S_OK};
+use winapi::um::dwmapi::{DwmFlush, DwmGetWindowAttribute, DWMWA_CLOAKED};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryW};
+use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::shellapi::{
+    SHAppBarMessage, SHQueryUserNotificationState, ABM_GETSTATE, ABM_SETSTATE, APPBARDATA,
+};
+use winapi::um::shellscalingapi::{
+    MDT_EFFECTIVE_DPI, MONITOR_DPI_TYPE, PROCESS_DPI_AWARENESS, PROCESS_PER_MONITOR_DPI_AWARE,
+};
+use winapi::um::winbase::QueryFullProcessImageNameW;
+use winapi::um::wingdi::{GetDeviceCaps, LOGPIXELSX};
+use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+use winapi::um::winreg::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+use winapi::um::winuser::{
+    keybd_event, EnumDisplayMonitors, FindWindowExW, GetClassNameW, GetDC, GetDesktopWindow,
+    GetForegroundWindow, GetMonitorInfoW, GetParent, GetWindowLongW, GetWindowRect, GetWindowTextW,
+    GetWindowThreadProcessId, IsWindow, LockWorkStation, ReleaseDC, SetProcessDPIAware, ShowWindow,
+    GWL_EXSTYLE, GWL_STYLE, KEYEVENTF_KEYUP, MONITORINFO, MONITORINFOF_PRIMARY, SW_HIDE, SW_SHOW,
+    WS_VISIBLE,
+};
+
+pub fn win32_string(value: &str) -> Vec<u16> {
+    OsStr::new(value).encode_wide().chain(once(0)).collect()
+}
+
+pub fn hwnd(window: WindowId) -> HWND {
+    window.0 as HWND
+}
+
+pub fn window_id(hwnd: HWND) -> WindowId {
+    WindowId(hwnd as usize)
+}
+
+fn non_null(hwnd: HWND) -> Option<WindowId> {
+    if hwnd.is_null() {
+        None
+    } else {
+        Some(window_id(hwnd))
+    }
+}
+
+type SetProcessDpiAwarenessContextFn = unsafe extern "system" fn(DPI_AWARENESS_CONTEXT) -> BOOL;
+type SetProcessDpiAwarenessFn = unsafe extern "system" fn(PROCESS_DPI_AWARENESS) -> HRESULT;
+type GetDpiForMonitorFn =
+    unsafe extern "system" fn(HMONITOR, MONITOR_DPI_TYPE, *mut UINT, *mut UINT) -> HRESULT;
+
+// Looks `name` up in `library` at run time, for what older versions of Windows don't
+// have. Linking it would stop the whole program from starting there.
+unsafe fn proc_address<F>(library: &str, name: &str) -> Option<F> {
+    let module = LoadLibraryW(win32_string(library).as_ptr());
+    if module.is_null() {
+        return None;
+    }
+    let name: Vec<u8> = name.bytes().chain(once(0)).collect();
+    let address = GetProcAddress(module, name.as_ptr() as _);
+    if address.is_null() {
+        None
+    } else {
+        Some(mem::transmute_copy::<_, F>(&address))
+    }
+}
+
+/// Makes every coordinate Windows hands out physical pixels of the monitor it is on,
+/// see `dpi`. Call before creating any windows. Falls back to the Windows 8.1 per-monitor
+/// mode, then the system wide one from Vista, returns false if none could be set.
+pub fn set_dpi_awareness() -> bool {
+    unsafe {
+        let context: Option<SetProcessDpiAwarenessContextFn> =
+            proc_address("user32.dll", "SetProcessDpiAwarenessContext");
+        if let Some(set) = context {
+            if set(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) != 0 {
+                return true;
+            }
+        }
+        let awareness: Option<SetProcessDpiAwarenessFn> =
+            proc_address("shcore.dll", "SetProcessDpiAwareness");
+        if let Some(set) = awareness {
+            if set(PROCESS_PER_MONITOR_DPI_AWARE) == S_OK {
+                return true;
+            }
+        }
+        SetProcessDPIAware() != 0
+    }
+}
+
+/// The effective DPI of `monitor`. Without per-monitor DPI, before Windows 8.1, every
+/// monitor gets the system's.
+unsafe fn monitor_dpi(monitor: HMONITOR) -> u32 {
+    let get: Option<GetDpiForMonitorFn> = proc_address("shcore.dll", "GetDpiForMonitor");
+    if let Some(get) = get {
+        let (mut dpi, mut dpi_y) = (DEFAULT_DPI, DEFAULT_DPI);
+        if get(monitor, MDT_EFFECTIVE_DPI, &mut dpi, &mut dpi_y) == S_OK {
+            return dpi;
+        }
+    }
+    let screen = GetDC(null_mut());
+    if screen.is_null() {
+        return DEFAULT_DPI;
+    }
+    let dpi = GetDeviceCaps(screen, LOGPIXELSX);
+    ReleaseDC(null_mut(), screen);
+    if dpi > 0 {
+        dpi as u32
+    } else {
+        DEFAULT_DPI
+    }
+}
+
+fn app_bar_data(taskbar: WindowId) -> APPBARDATA {
+    let mut data: APPBARDATA = unsafe { mem::zeroed() };
+    data.cbSize = mem::size_of::<APPBARDATA>() as u32;
+    data.hWnd = hwnd(taskbar);
+    data
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct Win32Shell;
+
+impl Win32Shell {
+    fn read_string(read: impl Fn(*mut u16, i32) -> i32) -> Option<String> {
+        let mut buffer: Vec<u16> = vec![0; 255];
+        let char_count = read(buffer.as_mut_ptr(), 255) as usize;
+        if char_count != 0 {
+            String::from_utf16(&buffer[0..char_count]).ok()
+        } else {
+            None
+        }
+    }
+    unsafe extern "system" fn collect_monitor(
+        monitor: HMONITOR,
+        _: HDC,
+        _: LPRECT,
+        monitors: LPARAM,
+    ) -> BOOL {
+        let monitors = &mut *(monitors as *mut Vec<Monitor>);
+        let mut info: MONITORINFO = mem::zeroed();
+        info.cbSize = mem::size_of::<MONITORINFO>() as u32;
+
+        if GetMonitorInfoW(monitor, &mut info as *mut MONITORINFO) != 0 {
+            let dpi = monitor_dpi(monitor);
+            let rect = info.rcMonitor;
+            monitors.push(Monitor {
+                pos: (rect.top, rect.bottom, rect.left, rect.right),
+                dpi,
+                primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+            });
+        }
+        TRUE
+    }
+}
+
+impl ShellBackend for Win32Shell {
+    fn desktop_window(&self) -> WindowId {
+        window_id(unsafe { GetDesktopWindow() })
+    }
+    fn foreground_window(&self) -> WindowId {
+        window_id(unsafe { GetForegroundWindow() })
+    }
+    fn find_window_ex(
+        &self,
+        parent: Option<WindowId>,
+        after: Option<WindowId>,
+        class_name: Option<&str>,
+        title: Option<&str>,
+    ) -> Option<WindowId> {
+        // Keep the buffers alive for the duration of the call.
+        let class_name = class_name.map(win32_string);
+        let title = title.map(win32_string);
+        let hwnd = unsafe {
+            FindWindowExW(
+                parent.map_or(null_mut(), hwnd),
+                after.map_or(null_mut(), hwnd),
+                class_name.as_ref().map_or(null_mut(), |name| name.as_ptr()),
+                title.as_ref().map_or(null_mut(), |title| title.as_ptr()),
+            )
+        };
+        non_null(hwnd)
+    }
+    fn is_window(&self, window: WindowId) -> bool {
+        unsafe { IsWindow(hwnd(window)) != 0 }
+    }
+    fn parent(&self, window: WindowId) -> Option<WindowId> {
+        non_null(unsafe { GetParent(hwnd(window)) })
+    }
+    fn class_name(&self, window: WindowId) -> Option<String> {
+        Win32Shell::read_string(|buffer, size| unsafe { GetClassNameW(hwnd(window), buffer, size) })
+    }
+    fn title(&self, window: WindowId) -> Option<String> {
+        Win32Shell::read_string(|buffer, size| unsafe {
+            GetWindowTextW(hwnd(window), buffer, size)
+        })
+    }
+    fn process_name(&self, window: WindowId) -> Option<String> {
+        unsafe {
+            let mut process_id = 0;
+            GetWindowThreadProcessId(hwnd(window), &mut process_id);
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, process_id);
+            if process.is_null() {
+                return None;
+            }
+            let mut buffer: Vec<u16> = vec![0; 1024];
+            let mut size = buffer.len() as u32;
+            let read = QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut size);
+            CloseHandle(process);
+            if read == 0 {
+                return None;
+            }
+            let path = String::from_utf16(&buffer[..size as usize]).ok()?;
+            path.rsplit('\\').next().map(str::to_string)
+        }
+    }
+    fn window_pos(&self, window: WindowId) -> (i32, i32, i32, i32) {
+        let mut dimensions = RECT {
+            top: 0,
+            left: 0,
+            right: 0,
+            bottom: 0,
+        };
+        unsafe {
+            GetWindowRect(hwnd(window), &mut dimensions as *mut RECT);
+        }
+        (
+            dimensions.top,
+            dimensions.bottom,
+            dimensions.left,
+            dimensions.right,
+        )
+    }
+    fn style(&self, window: WindowId) -> u32 {
+        unsafe { GetWindowLongW(hwnd(window), GWL_STYLE) as u32 }
+    }
+    fn ex_style(&self, window: WindowId) -> u32 {
+        unsafe { GetWindowLongW(hwnd(window), GWL_EXSTYLE) as u32 }
+    }
+    fn is_visible(&self, window: WindowId) -> bool {
+        let styles = unsafe { GetWindowLongW(hwnd(window), GWL_STYLE) } as u32;
+        styles & WS_VISIBLE != 0
+    }
+    fn is_cloaked(&self, window: WindowId) -> bool {
+        let mut cloaked: u32 = 0;
+        unsafe {
+            DwmGetWindowAttribute(
+                hwnd(window),
+                DWMWA_CLOAKED,
+                &mut cloaked as *mut u32 as *mut c_void,
+                std::mem::size_of::<u32>() as u32,
+            );
+        }
+        cloaked != 0
+    }
+    fn set_visible(&mut self, window: WindowId, visible: bool) -> bool {
+        if !self.is_window(window) {
+            return false;
+        }
+        unsafe { ShowWindow(hwnd(window), if visible { SW_SHOW } else { SW_HIDE }) };
+        true
+    }
+    fn send_keys(&mut self, keys: &[u8]) {
+        for key in keys {
+            unsafe { keybd_event(*key, 0, 0, 0) };
+        }
+        for key in keys.iter().rev() {
+            unsafe { keybd_event(*key, 0, KEYEVENTF_KEYUP, 0) };
+        }
+    }
+    fn launch(&mut self, program: &str, args: &[String]) -> bool {
+        Command::new(program).args(args).spawn().is_ok()
+    }
+    fn lock(&mut self) -> bool {
+        unsafe { LockWorkStation() != 0 }
+    }
+    fn flush(&self) {
+        unsafe { DwmFlush() };
+    }
+    fn monitors(&self) -> Vec<Monitor> {
+        let mut monitors: Vec<Monitor> = vec![];
+        unsafe {
+            EnumDisplayMonitors(
+                null_mut(),
+                null(),
+                Some(Win32Shell::collect_monitor),
+                &mut monitors as *mut Vec<Monitor> as LPARAM,
+            );
+        }
+        monitors
+    }
+    fn notification_state(&self) -> NotificationState {
+        let mut state = 0;
+        if unsafe { SHQueryUserNotificationState(&mut state) } != S_OK {
+            return NotificationState::AcceptsNotifications;
+        }
+        NotificationState::from_quns(state)
+    }
+    fn app_bar_state(&self, taskbar: WindowId) -> Option<AppBarState> {
+        if !self.is_window(taskbar) {
+            return None;
+        }
+        let mut data = app_bar_data(taskbar);
+        let bits = unsafe { SHAppBarMessage(ABM_GETSTATE, &mut data) };
+        Some(AppBarState::from_bits(bits as u32))
+    }
+    fn set_app_bar_state(&mut self, taskbar: WindowId, state: AppBarState) -> bool {
+        if !self.is_window(taskbar) {
+            return false;
+        }
+        let mut data = app_bar_data(taskbar);
+        data.lParam = state.bits() as LPARAM;
+        unsafe { SHAppBarMessage(ABM_SETSTATE, &mut data) };
+        true
+    }
+    /// Read from the registry, `GetVersionEx` only admits to Windows 8 without a manifest.
+    fn os_build(&self) -> Option<u32> {
+        let key = win32_string(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion");
+        let value = win32_string("CurrentBuildNumber");
+        let mut buffer: Vec<u16> = vec![0; 32];
+        let mut size = (buffer.len() * mem::size_of::<u16>()) as DWORD;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                key.as_ptr(),
+                value.as_ptr(),
+                RRF_RT_REG_SZ,
+                null_mut(),
+                buffer.as_mut_ptr() as *mut c_void,
+                &mut size,
+            )
+        };
+        if status != ERROR_SUCCESS as i32 {
+            return None;
+        }
+        let length = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+        String::from_utf16(&buffer[..length])
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+}
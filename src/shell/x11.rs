@@ -0,0 +1,660 @@
+/*!
+X11 backend.
+
+Windows on X11 have a class (`WM_CLASS`) and a title just like they do on
+Windows, so [`X11Shell`] answers the same [`ShellBackend`] questions the Win32
+backend does. Pointer motion comes from XInput2 raw events so it keeps coming
+while another client has the pointer grabbed, foreground changes come from the
+EWMH `_NET_ACTIVE_WINDOW` property and keys are pressed through XTest. Panels
+are the windows of type `_NET_WM_WINDOW_TYPE_DOCK`.
+
+[`X11Events`] reads those events on a thread of its own.
+!*/
+
+use super::{vk, DockBackend, ShellBackend, Strut, WindowId};
+use crate::autohide::AppBarState;
+use crate::fullscreen::NotificationState;
+use crate::hotkey::{modifiers, HotkeyCommand, HotkeyMap, Unbound};
+use crate::monitor::Monitor;
+use crate::trigger::unclipped;
+use log::warn;
+use std::cell::Cell;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use x11rb::connection::Connection;
+use x11rb::errors::ReplyError;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xinput::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt as _, EventMask, GetPropertyReply,
+    GrabMode, MapState, ModMask, PropMode, Window, KEY_PRESS_EVENT, KEY_RELEASE_EVENT,
+};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::protocol::Event as XEvent;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{CURRENT_TIME, NONE};
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_ACTIVE_WINDOW,
+        _NET_CLIENT_LIST,
+        _NET_WM_NAME,
+        _NET_WM_PID,
+        _NET_WM_STRUT_PARTIAL,
+        _NET_WM_WINDOW_TYPE,
+        _NET_WM_WINDOW_TYPE_DOCK,
+        UTF8_STRING,
+    }
+}
+
+/// The X keysym for a virtual key code, if there is one.
+pub fn keysym(key: u8) -> Option<u32> {
+    let keysym = match key {
+        vk::BACK => 0xff08,
+        vk::TAB => 0xff09,
+        vk::RETURN => 0xff0d,
+        vk::SHIFT => 0xffe1,
+        vk::CONTROL => 0xffe3,
+        vk::MENU => 0xffe9,
+        vk::ESCAPE => 0xff1b,
+        vk::SPACE => 0x0020,
+        vk::PRIOR => 0xff55,
+        vk::NEXT => 0xff56,
+        vk::END => 0xff57,
+        vk::HOME => 0xff50,
+        vk::LEFT => 0xff51,
+        vk::UP => 0xff52,
+        vk::RIGHT => 0xff53,
+        vk::DOWN => 0xff54,
+        vk::SNAPSHOT => 0xff61,
+        vk::INSERT => 0xff63,
+        vk::DELETE => 0xffff,
+        vk::LWIN => 0xffeb,
+        b'0'..=b'9' => u32::from(key),
+        b'A'..=b'Z' => u32::from(key.to_ascii_lowercase()),
+        key if (vk::F1..vk::F1 + 24).contains(&key) => 0xffbe + u32::from(key - vk::F1),
+        _ => return None,
+    };
+    Some(keysym)
+}
+
+/// The X modifier mask for `modifiers` bits, Super standing in for Win.
+fn modifier_mask(bits: u32) -> u16 {
+    [
+        (modifiers::WIN, ModMask::M4),
+        (modifiers::CONTROL, ModMask::CONTROL),
+        (modifiers::ALT, ModMask::M1),
+        (modifiers::SHIFT, ModMask::SHIFT),
+    ]
+    .iter()
+    .filter(|(bit, _)| bits & bit != 0)
+    .fold(0, |mask, (_, modifier)| mask | u16::from(*modifier))
+}
+
+/// What the event thread hands to the main loop.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum X11Event {
+    MouseMoved {
+        x: i32,
+        y: i32,
+    },
+    ForegroundChanged(WindowId),
+    Hotkey(HotkeyCommand),
+    /// A top-level window was mapped, it may be a new dock or one of a dock's menus.
+    WindowMapped(WindowId),
+    WindowUnmapped(WindowId),
+    /// A top-level window went away or the window manager's client list changed.
+    WindowsChanged,
+}
+
+/// A grabbed key: keycode, modifier mask and what it is bound to.
+type Grab = (u8, u16, HotkeyCommand);
+
+/// Modifiers that don't tell hotkeys apart, Caps Lock and Num Lock.
+const LOCK_MASKS: [u16; 4] = [0, 0x02, 0x10, 0x12];
+
+pub struct X11Shell {
+    conn: Arc<RustConnection>,
+    root: Window,
+    atoms: Atoms,
+    min_keycode: u8,
+    keysyms_per_keycode: u8,
+    /// The keyboard mapping, `keysyms_per_keycode` entries per keycode from `min_keycode` on.
+    keysyms: Vec<u32>,
+    grabs: Arc<Mutex<Vec<Grab>>>,
+    /// Run for `CornerAction::Lock`.
+    pub lock_command: Vec<String>,
+}
+
+impl X11Shell {
+    /// Connects to `display`, or to `$DISPLAY` if it is `None`.
+    pub fn connect(display: Option<&str>) -> Result<X11Shell, String> {
+        let (conn, screen) = RustConnection::connect(display).map_err(|error| error.to_string())?;
+        let root = conn.setup().roots[screen].root;
+        let setup_error = |error: ReplyError| format!("Could not set up X11 connection: {}", error);
+
+        let atoms = Atoms::new(&conn)
+            .map_err(ReplyError::from)
+            .and_then(|cookie| cookie.reply())
+            .map_err(setup_error)?;
+        conn.xinput_xi_query_version(2, 0)
+            .map_err(ReplyError::from)
+            .and_then(|cookie| cookie.reply())
+            .map_err(|_| "The X server does not support XInput 2".to_string())?;
+
+        let (min_keycode, max_keycode) = (conn.setup().min_keycode, conn.setup().max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)
+            .map_err(ReplyError::from)
+            .and_then(|cookie| cookie.reply())
+            .map_err(setup_error)?;
+
+        let shell = X11Shell {
+            conn: Arc::new(conn),
+            root,
+            atoms,
+            min_keycode,
+            keysyms_per_keycode: mapping.keysyms_per_keycode,
+            keysyms: mapping.keysyms,
+            grabs: Arc::new(Mutex::new(Vec::new())),
+            lock_command: vec!["loginctl".to_string(), "lock-session".to_string()],
+        };
+        shell.listen().map_err(setup_error)?;
+        Ok(shell)
+    }
+    /// Asks for pointer motion and active window changes.
+    fn listen(&self) -> Result<(), ReplyError> {
+        let conn = &self.conn;
+        conn.xinput_xi_select_events(
+            self.root,
+            &[xinput::EventMask {
+                deviceid: xinput::Device::ALL_MASTER.into(),
+                mask: vec![xinput::XIEventMask::RAW_MOTION],
+            }],
+        )?
+        .check()?;
+        conn.change_window_attributes(
+            self.root,
+            &ChangeWindowAttributesAux::new().event_mask(
+                EventMask::PROPERTY_CHANGE
+                    | EventMask::STRUCTURE_NOTIFY
+                    | EventMask::SUBSTRUCTURE_NOTIFY,
+            ),
+        )?
+        .check()?;
+
+        Ok(())
+    }
+    /// Grabs every key combination in `hotkeys` instead of the ones grabbed before.
+    ///
+    /// Returns the combinations another client already grabbed or this keyboard lacks.
+    pub fn grab_hotkeys(&self, hotkeys: &HotkeyMap) -> Unbound {
+        let conn = &self.conn;
+        let mut grabs = self
+            .grabs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for (keycode, mask, _) in grabs.drain(..) {
+            for locks in &LOCK_MASKS {
+                let _ = conn.ungrab_key(keycode, self.root, ModMask::from(mask | locks));
+            }
+        }
+
+        let mut unbound = Unbound::new();
+        for (hotkey, command) in &hotkeys.bindings {
+            let keycode = match keysym(hotkey.key).and_then(|keysym| self.keycode(keysym)) {
+                Some(keycode) => keycode,
+                None => {
+                    unbound.unknown.push((*hotkey, *command));
+                    continue;
+                }
+            };
+            let mask = modifier_mask(hotkey.modifiers);
+            // Grabbed once for every combination of lock keys
+            let grabbed = LOCK_MASKS.iter().all(|locks| {
+                conn.grab_key(
+                    true,
+                    self.root,
+                    ModMask::from(mask | locks),
+                    keycode,
+                    GrabMode::ASYNC,
+                    GrabMode::ASYNC,
+                )
+                .is_ok_and(|cookie| cookie.check().is_ok())
+            });
+            if grabbed {
+                grabs.push((keycode, mask, *command));
+            } else {
+                for locks in &LOCK_MASKS {
+                    let _ = conn.ungrab_key(keycode, self.root, ModMask::from(mask | locks));
+                }
+                unbound.taken.push((*hotkey, *command));
+            }
+        }
+        let _ = conn.flush();
+        unbound
+    }
+    /// A handle for reading events, meant to be moved to another thread.
+    pub fn events(&self) -> X11Events {
+        let (top, bottom, left, right) = self.window_pos(self.desktop_window());
+        X11Events {
+            conn: Arc::clone(&self.conn),
+            root: self.root,
+            size: Cell::new((right - left, bottom - top)),
+            last: Cell::new((0, 0)),
+            active_window: self.atoms._NET_ACTIVE_WINDOW,
+            client_list: self.atoms._NET_CLIENT_LIST,
+            grabs: Arc::clone(&self.grabs),
+        }
+    }
+    fn keycode(&self, keysym: u32) -> Option<u8> {
+        let per_keycode = usize::from(self.keysyms_per_keycode.max(1));
+        self.keysyms
+            .chunks(per_keycode)
+            .position(|keysyms| keysyms.contains(&keysym))
+            .map(|index| self.min_keycode + index as u8)
+    }
+    fn property(&self, window: WindowId, property: Atom, type_: Atom) -> Option<GetPropertyReply> {
+        self.conn
+            .get_property(false, window.0 as Window, property, type_, 0, u32::MAX / 4)
+            .ok()?
+            .reply()
+            .ok()
+            .filter(|reply| reply.type_ != NONE)
+    }
+    fn children(&self, parent: WindowId) -> Vec<WindowId> {
+        self.conn
+            .query_tree(parent.0 as Window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map_or(vec![], |tree| {
+                tree.children
+                    .into_iter()
+                    .map(|window| WindowId(window as usize))
+                    .collect()
+            })
+    }
+}
+
+/// Reads events from the connection of the [`X11Shell`] it came from.
+pub struct X11Events {
+    conn: Arc<RustConnection>,
+    root: Window,
+    /// Of the root window, which the pointer is clipped to.
+    size: Cell<(i32, i32)>,
+    /// Where the pointer was after the previous motion.
+    last: Cell<(i32, i32)>,
+    active_window: Atom,
+    client_list: Atom,
+    grabs: Arc<Mutex<Vec<Grab>>>,
+}
+
+impl X11Events {
+    /// Blocks until something the hot corner cares about happens.
+    pub fn next(&self) -> Result<X11Event, ReplyError> {
+        loop {
+            match self.conn.wait_for_event()? {
+                // Raw events carry no position, ask where the pointer ended up
+                XEvent::XinputRawMotion(event) => {
+                    let pointer = self.conn.query_pointer(self.root)?.reply()?;
+                    let (x, y) = (i32::from(pointer.root_x), i32::from(pointer.root_y));
+                    let (last_x, last_y) = self.last.replace((x, y));
+                    let (width, height) = self.size.get();
+                    let (dx, dy) = motion(&event);
+                    let past = (
+                        unclipped(last_x, dx, x, width),
+                        unclipped(last_y, dy, y, height),
+                    );
+                    // Tablets report where the pen is, not how far it moved
+                    let (x, y) = if past != (x, y) && self.is_relative(event.sourceid)? {
+                        past
+                    } else {
+                        (x, y)
+                    };
+                    return Ok(X11Event::MouseMoved { x, y });
+                }
+                XEvent::ConfigureNotify(event) if event.window == self.root => {
+                    self.size
+                        .set((i32::from(event.width), i32::from(event.height)));
+                }
+                XEvent::MapNotify(event) if event.event == self.root => {
+                    return Ok(X11Event::WindowMapped(WindowId(event.window as usize)));
+                }
+                XEvent::UnmapNotify(event) if event.event == self.root => {
+                    return Ok(X11Event::WindowUnmapped(WindowId(event.window as usize)));
+                }
+                XEvent::DestroyNotify(event) if event.event == self.root => {
+                    return Ok(X11Event::WindowsChanged);
+                }
+                XEvent::PropertyNotify(event) if event.atom == self.client_list => {
+                    return Ok(X11Event::WindowsChanged);
+                }
+                XEvent::PropertyNotify(event) if event.atom == self.active_window => {
+                    let reply = self
+                        .conn
+                        .get_property(false, self.root, self.active_window, AtomEnum::WINDOW, 0, 1)?
+                        .reply()?;
+                    let window = reply
+                        .value32()
+                        .and_then(|mut value| value.next())
+                        .unwrap_or(NONE);
+                    return Ok(X11Event::ForegroundChanged(WindowId(window as usize)));
+                }
+                XEvent::KeyPress(event) => {
+                    let mask = u16::from(event.state) & modifier_mask(!0);
+                    let grabs = self
+                        .grabs
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                    if let Some((_, _, command)) = grabs
+                        .iter()
+                        .find(|(keycode, grabbed, _)| *keycode == event.detail && *grabbed == mask)
+                    {
+                        return Ok(X11Event::Hotkey(*command));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl X11Events {
+    /// Whether `device` moves the pointer by how far it moved rather than to where it is.
+    fn is_relative(&self, device: xinput::DeviceId) -> Result<bool, ReplyError> {
+        let reply = self.conn.xinput_xi_query_device(device)?.reply()?;
+        Ok(reply
+            .infos
+            .iter()
+            .flat_map(|info| &info.classes)
+            .any(|class| match &class.data {
+                xinput::DeviceClassData::Valuator(valuator) => {
+                    valuator.number == 0 && valuator.mode == xinput::ValuatorMode::RELATIVE
+                }
+                _ => false,
+            }))
+    }
+}
+
+/// How far a raw motion moved the pointer across and down, after acceleration.
+fn motion(event: &xinput::RawMotionEvent) -> (i32, i32) {
+    let mut delta = (0, 0);
+    let mut values = event.axisvalues.iter();
+    let bits = event
+        .valuator_mask
+        .iter()
+        .enumerate()
+        .flat_map(|(word, mask)| (0..32).map(move |bit| (word * 32 + bit, mask & (1 << bit) != 0)));
+    for (axis, set) in bits {
+        if !set {
+            continue;
+        }
+        let value = match values.next() {
+            Some(value) => f64::from(value.integral) + f64::from(value.frac) / 4_294_967_296.0,
+            None => break,
+        };
+        match axis {
+            0 => delta.0 = value.round() as i32,
+            1 => delta.1 = value.round() as i32,
+            _ => break,
+        }
+    }
+    delta
+}
+
+impl ShellBackend for X11Shell {
+    fn desktop_window(&self) -> WindowId {
+        WindowId(self.root as usize)
+    }
+    fn foreground_window(&self) -> WindowId {
+        let root = self.desktop_window();
+        self.property(root, self.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW.into())
+            .and_then(|reply| reply.value32()?.next())
+            .map_or(WindowId::NULL, |window| WindowId(window as usize))
+    }
+    fn find_window_ex(
+        &self,
+        parent: Option<WindowId>,
+        after: Option<WindowId>,
+        class_name: Option<&str>,
+        title: Option<&str>,
+    ) -> Option<WindowId> {
+        let children = self.children(parent.unwrap_or_else(|| self.desktop_window()));
+        let start = after
+            .and_then(|after| children.iter().position(|window| *window == after))
+            .map_or(0, |index| index + 1);
+
+        children[start.min(children.len())..]
+            .iter()
+            .find(|window| {
+                class_name.is_none_or(|class_name| {
+                    self.class_name(**window).as_deref() == Some(class_name)
+                }) && title.is_none_or(|title| self.title(**window).as_deref() == Some(title))
+            })
+            .cloned()
+    }
+    fn is_window(&self, window: WindowId) -> bool {
+        !window.is_null()
+            && self
+                .conn
+                .get_window_attributes(window.0 as Window)
+                .is_ok_and(|cookie| cookie.reply().is_ok())
+    }
+    fn parent(&self, window: WindowId) -> Option<WindowId> {
+        let tree = self
+            .conn
+            .query_tree(window.0 as Window)
+            .ok()?
+            .reply()
+            .ok()?;
+        Some(WindowId(tree.parent as usize)).filter(|parent| !parent.is_null())
+    }
+    /// The class half of `WM_CLASS`.
+    fn class_name(&self, window: WindowId) -> Option<String> {
+        let reply = self.property(window, AtomEnum::WM_CLASS.into(), AtomEnum::STRING.into())?;
+        let mut parts = reply.value.split(|byte| *byte == 0);
+        parts.next();
+        parts
+            .next()
+            .map(|class| String::from_utf8_lossy(class).into_owned())
+    }
+    fn title(&self, window: WindowId) -> Option<String> {
+        self.property(window, self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING)
+            .or_else(|| self.property(window, AtomEnum::WM_NAME.into(), AtomEnum::STRING.into()))
+            .map(|reply| String::from_utf8_lossy(&reply.value).into_owned())
+    }
+    fn process_name(&self, window: WindowId) -> Option<String> {
+        let reply = self.property(window, self.atoms._NET_WM_PID, AtomEnum::CARDINAL.into())?;
+        let pid = reply.value32()?.next()?;
+        let name = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+        Some(name.trim_end().to_string())
+    }
+    fn window_pos(&self, window: WindowId) -> (i32, i32, i32, i32) {
+        let conn = &self.conn;
+        let pos = || -> Result<(i32, i32, i32, i32), ReplyError> {
+            let geometry = conn.get_geometry(window.0 as Window)?.reply()?;
+            let origin = conn
+                .translate_coordinates(window.0 as Window, self.root, 0, 0)?
+                .reply()?;
+            let (x, y) = (i32::from(origin.dst_x), i32::from(origin.dst_y));
+            Ok((
+                y,
+                y + i32::from(geometry.height),
+                x,
+                x + i32::from(geometry.width),
+            ))
+        };
+        pos().unwrap_or_default()
+    }
+    fn style(&self, _window: WindowId) -> u32 {
+        0
+    }
+    fn ex_style(&self, _window: WindowId) -> u32 {
+        0
+    }
+    fn is_visible(&self, window: WindowId) -> bool {
+        self.conn
+            .get_window_attributes(window.0 as Window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .is_some_and(|attributes| attributes.map_state == MapState::VIEWABLE)
+    }
+    /// X11 has no cloaking, unmapped windows are just invisible.
+    fn is_cloaked(&self, _window: WindowId) -> bool {
+        false
+    }
+    fn set_visible(&mut self, window: WindowId, visible: bool) -> bool {
+        if !self.is_window(window) {
+            return false;
+        }
+        let window = window.0 as Window;
+        let sent = if visible {
+            self.conn.map_window(window).is_ok()
+        } else {
+            self.conn.unmap_window(window).is_ok()
+        };
+        sent && self.conn.flush().is_ok()
+    }
+    fn send_keys(&mut self, keys: &[u8]) {
+        let keycodes: Vec<u8> = keys
+            .iter()
+            .filter_map(|key| keysym(*key).and_then(|keysym| self.keycode(keysym)))
+            .collect();
+        if keycodes.len() != keys.len() {
+            warn!("Some keys have no keycode on this keyboard, not sending them");
+            return;
+        }
+        let fake = |type_, keycode| {
+            self.conn
+                .xtest_fake_input(type_, keycode, CURRENT_TIME, NONE, 0, 0, 0)
+        };
+        for keycode in &keycodes {
+            let _ = fake(KEY_PRESS_EVENT, *keycode);
+        }
+        for keycode in keycodes.iter().rev() {
+            let _ = fake(KEY_RELEASE_EVENT, *keycode);
+        }
+        let _ = self.conn.flush();
+    }
+    fn launch(&mut self, program: &str, args: &[String]) -> bool {
+        Command::new(program).args(args).spawn().is_ok()
+    }
+    fn lock(&mut self) -> bool {
+        match self.lock_command.split_first() {
+            Some((program, args)) => Command::new(program).args(args).spawn().is_ok(),
+            None => false,
+        }
+    }
+    /// Waits for the X server to handle everything sent so far.
+    fn flush(&self) {
+        let _ = self.conn.get_input_focus().map(|cookie| cookie.reply());
+    }
+    fn monitors(&self) -> Vec<Monitor> {
+        let reply = self
+            .conn
+            .randr_get_monitors(self.root, true)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok());
+
+        reply.map_or(vec![], |reply| {
+            reply
+                .monitors
+                .iter()
+                .map(|info| {
+                    let (x, y) = (i32::from(info.x), i32::from(info.y));
+                    let monitor =
+                        Monitor::new(y, y + i32::from(info.height), x, x + i32::from(info.width));
+                    if info.primary {
+                        monitor.primary()
+                    } else {
+                        monitor
+                    }
+                })
+                .collect()
+        })
+    }
+    fn notification_state(&self) -> NotificationState {
+        NotificationState::AcceptsNotifications
+    }
+    /// Panels are hidden through their struts instead.
+    fn app_bar_state(&self, _taskbar: WindowId) -> Option<AppBarState> {
+        None
+    }
+    fn set_app_bar_state(&mut self, _taskbar: WindowId, _state: AppBarState) -> bool {
+        false
+    }
+    fn os_build(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl DockBackend for X11Shell {
+    fn docks(&self) -> Vec<WindowId> {
+        let root = self.desktop_window();
+        // Most docks aren't managed, so they only show up as children of the root
+        let mut windows = self.children(root);
+        if let Some(clients) =
+            self.property(root, self.atoms._NET_CLIENT_LIST, AtomEnum::WINDOW.into())
+        {
+            let clients = clients.value32().into_iter().flatten();
+            windows.extend(clients.map(|window| WindowId(window as usize)));
+        }
+        windows.sort();
+        windows.dedup();
+
+        windows
+            .into_iter()
+            .filter(|window| self.is_dock(*window))
+            .collect()
+    }
+    fn is_dock(&self, window: WindowId) -> bool {
+        self.property(
+            window,
+            self.atoms._NET_WM_WINDOW_TYPE,
+            AtomEnum::ATOM.into(),
+        )
+        .and_then(|reply| {
+            reply
+                .value32()
+                .map(|mut types| types.any(|atom| atom == self.atoms._NET_WM_WINDOW_TYPE_DOCK))
+        })
+        .unwrap_or(false)
+    }
+    fn transient_for(&self, window: WindowId) -> Option<WindowId> {
+        let reply = self.property(
+            window,
+            AtomEnum::WM_TRANSIENT_FOR.into(),
+            AtomEnum::WINDOW.into(),
+        )?;
+        let owner = reply.value32()?.next()?;
+        Some(WindowId(owner as usize)).filter(|owner| !owner.is_null())
+    }
+    fn strut(&self, window: WindowId) -> Option<Strut> {
+        let reply = self.property(
+            window,
+            self.atoms._NET_WM_STRUT_PARTIAL,
+            AtomEnum::CARDINAL.into(),
+        )?;
+        let values: Vec<u32> = reply.value32()?.collect();
+        let mut strut = [0; 12];
+        strut.copy_from_slice(values.get(..12)?);
+        Some(strut)
+    }
+    fn set_strut(&mut self, window: WindowId, strut: Option<Strut>) -> bool {
+        let (conn, property) = (&self.conn, self.atoms._NET_WM_STRUT_PARTIAL);
+        let sent = match strut {
+            Some(strut) => conn
+                .change_property32(
+                    PropMode::REPLACE,
+                    window.0 as Window,
+                    property,
+                    AtomEnum::CARDINAL,
+                    &strut,
+                )
+                .is_ok(),
+            None => conn.delete_property(window.0 as Window, property).is_ok(),
+        };
+        sent && conn.flush().is_ok()
+    }
+}
@@ -0,0 +1,78 @@
+/*!
+Finding the start button, start menu and search on each version of Windows.
+
+Windows 10 parents a `Start` button to `Shell_TrayWnd` and opens the start
+menu next to it in a `Windows.UI.Core.CoreWindow`. Windows 11 draws its whole
+taskbar in XAML, centers the start button by default and opens the start menu
+and search in `CoreWindow`s titled `Start` and `Search`, nowhere near the
+button.
+
+[`ShellVersion`] picks which of the two layouts to look for, from the build
+number when the shell can tell it and from the taskbar's children when it
+can't. The windows themselves are described by `rules`. `tests/fixtures` holds
+window trees dumped with `--dump-windows` on each build, trimmed to Explorer's
+windows.
+!*/
+
+use crate::rules::{Context, Role, Rules};
+use crate::shell::{ShellBackend, WindowId};
+use serde::{Deserialize, Serialize};
+
+/// Hosts the start menu, search and the other shell flyouts.
+pub const CORE_WINDOW: &str = "Windows.UI.Core.CoreWindow";
+/// The first Windows 11 build.
+pub const WINDOWS11_BUILD: u32 = 22000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellVersion {
+    Windows10,
+    Windows11,
+}
+
+impl ShellVersion {
+    pub fn from_build(build: u32) -> ShellVersion {
+        if build >= WINDOWS11_BUILD {
+            ShellVersion::Windows11
+        } else {
+            ShellVersion::Windows10
+        }
+    }
+    /// The version of the shell `bar` belongs to. Without a build number, only the
+    /// Windows 11 taskbar hosts a XAML island.
+    pub fn detect(shell: &impl ShellBackend, rules: &Rules, bar: WindowId) -> ShellVersion {
+        let context = Context::default();
+        match shell.os_build() {
+            Some(build) => ShellVersion::from_build(build),
+            None if rules
+                .find_in(shell, Role::XamlIsland, bar, &context)
+                .is_some() =>
+            {
+                ShellVersion::Windows11
+            }
+            None => ShellVersion::Windows10,
+        }
+    }
+}
+
+/// The start button and its size. Windows 11 has no window for the button, or a hidden
+/// one, so it stands in a square as thick as the taskbar with a null handle.
+pub fn find_start_button(
+    shell: &impl ShellBackend,
+    rules: &Rules,
+    bar: WindowId,
+    version: ShellVersion,
+) -> Option<(WindowId, (i32, i32))> {
+    let context = Context::new(Some(version), (0, 0));
+    let button = rules
+        .find_in(shell, Role::StartButton, bar, &context)
+        .map(|button| (button, shell.window_dimensions(button)));
+    match version {
+        ShellVersion::Windows10 => button,
+        ShellVersion::Windows11 => button.or_else(|| {
+            let (width, height) = shell.window_dimensions(bar);
+            let thickness = width.min(height);
+            Some((WindowId::NULL, (thickness, thickness)))
+        }),
+    }
+}
@@ -0,0 +1,113 @@
+/*!
+Timers on a single thread.
+
+[`Timers`] is a queue of deadlines that the message loop drains whenever its
+one window timer goes off, so every timer runs on the thread the hooks run
+on. Time comes from a [`Clock`], which tests and replays can drive by hand
+with a [`ManualClock`].
+!*/
+
+use std::cell::Cell;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Milliseconds since some fixed point.
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+/// Counts from the first time it is read.
+pub struct SystemClock {
+    started: OnceLock<Instant>,
+}
+
+impl SystemClock {
+    pub const fn new() -> SystemClock {
+        SystemClock {
+            started: OnceLock::new(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        self.started.get_or_init(Instant::now).elapsed().as_millis() as u64
+    }
+}
+
+/// Only moves when told to.
+#[derive(Default)]
+pub struct ManualClock {
+    now: Cell<u64>,
+}
+
+impl ManualClock {
+    pub fn new(now: u64) -> ManualClock {
+        ManualClock {
+            now: Cell::new(now),
+        }
+    }
+    pub fn set(&self, now: u64) {
+        self.now.set(now);
+    }
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration.as_millis() as u64);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+pub struct Timers<T> {
+    /// Deadlines in the order they were scheduled.
+    queue: Vec<(u64, T)>,
+}
+
+impl<T> Timers<T> {
+    pub const fn new() -> Timers<T> {
+        Timers { queue: Vec::new() }
+    }
+    pub fn schedule(&mut self, now: u64, delay: Duration, timer: T) {
+        self.queue.push((now + delay.as_millis() as u64, timer));
+    }
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+    /// The earliest deadline.
+    pub fn next_due(&self) -> Option<u64> {
+        self.queue.iter().map(|(due, _)| *due).min()
+    }
+    /// How long until the earliest deadline, zero if it has passed.
+    pub fn delay_until_next(&self, now: u64) -> Option<Duration> {
+        self.next_due()
+            .map(|due| Duration::from_millis(due.saturating_sub(now)))
+    }
+    /// Removes the earliest timer that is due by `now`. Timers due at the same
+    /// time come out in the order they were scheduled.
+    pub fn pop_due(&mut self, now: u64) -> Option<(u64, T)> {
+        let due = self.next_due().filter(|due| *due <= now)?;
+        let index = self.queue.iter().position(|(at, _)| *at == due)?;
+        Some(self.queue.remove(index))
+    }
+}
+
+impl<T> Default for Timers<T> {
+    fn default() -> Timers<T> {
+        Timers::new()
+    }
+}
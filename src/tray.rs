@@ -0,0 +1,138 @@
+use std::mem;
+use std::ptr::null_mut;
+
+use winapi::shared::minwindef::UINT;
+use winapi::shared::windef::{HMENU, POINT};
+use winapi::um::shellapi::{
+    Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIIF_WARNING, NIM_ADD, NIM_DELETE,
+    NIM_MODIFY, NOTIFYICONDATAW,
+};
+use winapi::um::winuser::{
+    AppendMenuW, CreatePopupMenu, DestroyMenu, GetCursorPos, LoadIconW, PostMessageW,
+    SetForegroundWindow, TrackPopupMenu, IDI_APPLICATION, IDI_WARNING, MF_CHECKED, MF_GRAYED,
+    MF_POPUP, MF_SEPARATOR, MF_STRING, TPM_NONOTIFY, TPM_RETURNCMD, TPM_RIGHTBUTTON, WM_APP,
+    WM_NULL,
+};
+
+use crate::window::{win32_string, Window};
+use win_gnome::menu::{self, MenuCommand, MenuEntry};
+
+/// What the shell sends to the window when the icon is clicked, `lParam` is the mouse message.
+pub const WM_TRAY_NOTIFY: u32 = WM_APP + 2;
+
+const ICON_ID: UINT = 1;
+
+fn icon_data(window: &Window, enabled: bool) -> NOTIFYICONDATAW {
+    unsafe {
+        let mut data: NOTIFYICONDATAW = mem::zeroed();
+        data.cbSize = mem::size_of::<NOTIFYICONDATAW>() as u32;
+        data.hWnd = window.handle;
+        data.uID = ICON_ID;
+        data.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+        data.uCallbackMessage = WM_TRAY_NOTIFY;
+        data.hIcon = LoadIconW(
+            null_mut(),
+            if enabled {
+                IDI_APPLICATION
+            } else {
+                IDI_WARNING
+            },
+        );
+        copy_text(&mut data.szTip, menu::tooltip(enabled));
+        data
+    }
+}
+
+// Cut short to fit, the last character is always the terminator
+fn copy_text(buffer: &mut [u16], text: &str) {
+    let text = win32_string(text);
+    let length = text.len().min(buffer.len() - 1);
+    buffer[..length].copy_from_slice(&text[..length]);
+}
+
+pub fn add(window: &Window, enabled: bool) -> bool {
+    unsafe { Shell_NotifyIconW(NIM_ADD, &mut icon_data(window, enabled)) != 0 }
+}
+
+/// Switches the icon and its tooltip to show whether the hot corner is enabled.
+pub fn set_enabled(window: &Window, enabled: bool) -> bool {
+    unsafe { Shell_NotifyIconW(NIM_MODIFY, &mut icon_data(window, enabled)) != 0 }
+}
+
+/// Pops a warning balloon up from the icon, false if there is no icon to show it from.
+pub fn notify(window: &Window, text: &str) -> bool {
+    unsafe {
+        let mut data: NOTIFYICONDATAW = mem::zeroed();
+        data.cbSize = mem::size_of::<NOTIFYICONDATAW>() as u32;
+        data.hWnd = window.handle;
+        data.uID = ICON_ID;
+        data.uFlags = NIF_INFO;
+        data.dwInfoFlags = NIIF_WARNING;
+        copy_text(&mut data.szInfoTitle, "WinGnome");
+        copy_text(&mut data.szInfo, text);
+        Shell_NotifyIconW(NIM_MODIFY, &mut data) != 0
+    }
+}
+
+pub fn remove(window: &Window) -> bool {
+    unsafe { Shell_NotifyIconW(NIM_DELETE, &mut icon_data(window, true)) != 0 }
+}
+
+fn create_menu(entries: &[MenuEntry]) -> HMENU {
+    unsafe {
+        let handle = CreatePopupMenu();
+        for entry in entries {
+            match entry {
+                MenuEntry::Item(item) => {
+                    let mut flags = MF_STRING;
+                    if item.checked {
+                        flags |= MF_CHECKED;
+                    }
+                    if item.grayed {
+                        flags |= MF_GRAYED;
+                    }
+                    let label = win32_string(&item.label);
+                    AppendMenuW(handle, flags, item.command.id() as usize, label.as_ptr());
+                }
+                MenuEntry::Submenu(label, entries) => {
+                    let label = win32_string(label);
+                    // Destroyed along with the menu it is in
+                    let submenu = create_menu(entries);
+                    AppendMenuW(
+                        handle,
+                        MF_STRING | MF_POPUP,
+                        submenu as usize,
+                        label.as_ptr(),
+                    );
+                }
+                MenuEntry::Separator => {
+                    AppendMenuW(handle, MF_SEPARATOR, 0, null_mut());
+                }
+            }
+        }
+        handle
+    }
+}
+
+/// Shows the menu at the cursor and waits for an item to be chosen.
+pub fn show_menu(window: &Window, entries: &[MenuEntry]) -> Option<MenuCommand> {
+    unsafe {
+        let handle = create_menu(entries);
+        let mut cursor = POINT { x: 0, y: 0 };
+        GetCursorPos(&mut cursor);
+        // Without the foreground the menu won't close when clicking elsewhere
+        SetForegroundWindow(window.handle);
+        let id = TrackPopupMenu(
+            handle,
+            TPM_RETURNCMD | TPM_NONOTIFY | TPM_RIGHTBUTTON,
+            cursor.x,
+            cursor.y,
+            0,
+            window.handle,
+            null_mut(),
+        );
+        PostMessageW(window.handle, WM_NULL, 0, 0);
+        DestroyMenu(handle);
+        MenuCommand::from_id(id as u32)
+    }
+}
@@ -0,0 +1,194 @@
+/*!
+Dwell time and pressure.
+
+Flinging the mouse at the start button lands it in the hot corner for a moment
+on the way, so a corner can ask for more than just a point inside it:
+
+```toml
+[trigger]
+# Milliseconds the cursor has to rest in the corner
+dwell = 150
+# Pixels the cursor has to be pushed past the edge of the screen, like GNOME's pressure barrier
+pressure = 100
+# Cursor speed in pixels per millisecond above which a sample doesn't count
+max_speed = 2.0
+```
+
+Pixels here are device-independent, so the same settings feel the same on a
+monitor at 150% scaling; see [`dpi`](crate::dpi).
+
+Either a dwell or the pressure is enough to fire. With both left at 0 the
+corner fires as soon as the cursor lands in it, as long as it isn't moving
+faster than `max_speed`.
+
+[`TriggerFilter`] only sees timestamps it is given, so traces can be replayed
+through it exactly.
+!*/
+
+use crate::dpi::Scale;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TriggerSettings {
+    /// Milliseconds, 0 turns dwelling off.
+    pub dwell: u64,
+    /// Device-independent pixels past the screen edge, 0 turns pressure off.
+    pub pressure: i32,
+    /// Device-independent pixels per millisecond, 0 allows any speed.
+    pub max_speed: f64,
+}
+
+impl TriggerSettings {
+    pub const fn default() -> TriggerSettings {
+        TriggerSettings {
+            dwell: 0,
+            pressure: 0,
+            max_speed: 0.0,
+        }
+    }
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.pressure < 0 {
+            Err("Pressure can't be negative")
+        } else if self.max_speed < 0.0 || self.max_speed.is_nan() {
+            Err("Maximum speed can't be negative")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for TriggerSettings {
+    fn default() -> TriggerSettings {
+        TriggerSettings::default()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// Nothing to do yet.
+    Idle,
+    /// Fire the corner.
+    Fire,
+    /// Call `tick` after the duration to see if the cursor dwelled long enough.
+    Wait(Duration),
+}
+
+pub struct TriggerFilter {
+    pub settings: TriggerSettings,
+    /// Of the monitor the samples are on, they come in its physical pixels.
+    pub scale: Scale,
+    /// When the cursor entered the corner.
+    entered: Option<u64>,
+    pressure: i32,
+    waiting: bool,
+    /// Time and position of the previous sample.
+    last: Option<(u64, i32, i32)>,
+    speed: f64,
+}
+
+impl TriggerFilter {
+    pub const fn new(settings: TriggerSettings) -> TriggerFilter {
+        TriggerFilter {
+            settings,
+            scale: Scale::default(),
+            entered: None,
+            pressure: 0,
+            waiting: false,
+            last: None,
+            speed: 0.0,
+        }
+    }
+    /// Forgets the cursor was ever in a corner.
+    pub fn reset(&mut self) {
+        self.entered = None;
+        self.pressure = 0;
+        self.waiting = false;
+    }
+    /// Feeds one mouse sample taken at `time` milliseconds.
+    ///
+    /// `overshoot` is how far past the edge of the screen the sample is, which the
+    /// low level hook sees before the cursor is clipped.
+    pub fn sample(
+        &mut self,
+        time: u64,
+        x: i32,
+        y: i32,
+        in_corner: bool,
+        overshoot: i32,
+    ) -> Decision {
+        if let Some((last_time, last_x, last_y)) = self.last {
+            if time > last_time {
+                let distance = f64::from(x - last_x).hypot(f64::from(y - last_y));
+                self.speed = distance / (time - last_time) as f64;
+            }
+        }
+        self.last = Some((time, x, y));
+
+        if !in_corner {
+            self.reset();
+            return Decision::Idle;
+        }
+        if self.entered.is_none() {
+            self.entered = Some(time);
+        }
+        if self.too_fast(self.speed) {
+            return self.wait(time);
+        }
+        self.pressure += overshoot.max(0);
+        self.decide(time)
+    }
+    /// Checks back after a `Decision::Wait`, the cursor hasn't moved since the last sample.
+    pub fn tick(&mut self, time: u64, in_corner: bool) -> Decision {
+        self.waiting = false;
+        if !in_corner || self.entered.is_none() {
+            return Decision::Idle;
+        }
+        self.speed = 0.0;
+        self.decide(time)
+    }
+    fn too_fast(&self, speed: f64) -> bool {
+        self.settings.max_speed > 0.0 && speed > self.settings.max_speed * self.scale.factor()
+    }
+    fn decide(&mut self, time: u64) -> Decision {
+        let settings = self.settings;
+        let dwelled = self
+            .entered
+            .is_some_and(|entered| time.saturating_sub(entered) >= settings.dwell);
+
+        if settings.dwell == 0 && settings.pressure == 0
+            || settings.dwell > 0 && dwelled
+            || settings.pressure > 0 && self.pressure >= self.scale.to_physical(settings.pressure)
+        {
+            self.reset();
+            Decision::Fire
+        } else {
+            self.wait(time)
+        }
+    }
+    /// Asks for a tick once the dwell is up, unless one is already on its way.
+    fn wait(&mut self, time: u64) -> Decision {
+        let dwell_end = self.entered.unwrap_or(time) + self.settings.dwell;
+        if self.settings.dwell == 0 || self.waiting || dwell_end <= time {
+            return Decision::Idle;
+        }
+        self.waiting = true;
+        Decision::Wait(Duration::from_millis(dwell_end - time))
+    }
+}
+
+/// Where a cursor would be on one axis if the screen's `size` didn't stop it.
+///
+/// Backends that only see the cursor after it is clipped, like X11, know it moved
+/// by `delta` from `last` and ended up at `position`. Whatever `delta` wanted past
+/// the screen's edge is the overshoot the low level hook on Windows gets for free.
+pub fn unclipped(last: i32, delta: i32, position: i32, size: i32) -> i32 {
+    if position <= 0 && delta < 0 {
+        position.min(last + delta)
+    } else if position >= size - 1 && delta > 0 {
+        position.max(last + delta)
+    } else {
+        position
+    }
+}
@@ -1,11 +1,9 @@
 
-use std::ffi::OsStr;
 use std::io::Error;
-use std::iter::once;
 use std::mem;
-use std::os::windows::ffi::OsStrExt;
 use std::ptr::null_mut;
 
+use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
 use winapi::shared::windef::HWND;
 use winapi::shared::winerror::ERROR_ALREADY_EXISTS;
 use winapi::um::errhandlingapi::GetLastError;
@@ -13,20 +11,116 @@ use winapi::um::synchapi::CreateMutexW;
 use winapi::um::libloaderapi::GetModuleHandleW;
 use winapi::um::winuser::{
     CreateWindowExW, DefWindowProcW, DispatchMessageW,TranslateMessage,GetMessageW,RegisterClassW,
-    CW_USEDEFAULT, WM_HOTKEY, WM_CLOSE, WNDCLASSW, MSG
+    ChangeWindowMessageFilterEx, KillTimer, PostQuitMessage, RegisterHotKey, RegisterWindowMessageW, SetTimer, UnregisterHotKey, CW_USEDEFAULT, WM_DISPLAYCHANGE, WM_DPICHANGED, WM_ENDSESSION, WM_HOTKEY, WM_CLOSE, WM_QUERYENDSESSION, WM_TIMER, WNDCLASSW, MSG
 };
 
-#[cfg(windows)]
-pub fn win32_string(value: &str) -> Vec<u16> {
-    OsStr::new(value).encode_wide().chain(once(0)).collect()
-}
+pub use win_gnome::shell::win32::win32_string;
+use crate::pipe::WM_CONTROL;
+use crate::tray::{self, WM_TRAY_NOTIFY};
+use win_gnome::menu::{MenuCommand, MenuEntry};
 
 // Window struct
 #[cfg(windows)]
+#[derive(Copy, Clone)]
 pub struct Window {
     pub handle: HWND,
 }
 
+/// What the window does with its messages while `run` is pumping them.
+///
+/// They are handled in the window procedure, so they still arrive while a menu,
+/// message box or window drag runs a modal loop of its own.
+#[cfg(windows)]
+pub trait Handler {
+    /// `WM_HOTKEY` for the hotkey registered with `id`.
+    fn hotkey(&mut self, id: usize);
+    /// `WM_TIMER` for the timer started with `id`.
+    fn timer(&mut self, id: usize);
+    /// A request is waiting on the control pipe.
+    fn control(&mut self);
+    /// The notification area icon got mouse message `event`, returns the menu to open.
+    fn tray(&mut self, event: u32) -> Option<Vec<MenuEntry>>;
+    /// `command` was picked from the menu `tray` asked for. The menu runs a message loop
+    /// of its own, so anything may have happened in the meantime, closing included.
+    fn menu(&mut self, command: MenuCommand);
+    /// Explorer started over with a new taskbar.
+    fn shell_restarted(&mut self);
+    /// Monitors were added, moved or rescaled.
+    fn display_changed(&mut self);
+    /// The window is closing, the message loop ends once this returns.
+    fn close(&mut self);
+}
+
+/// Lets a message through to an elevated window, missing from winapi.
+#[cfg(windows)]
+const MSGFLT_ALLOW: u32 = 1;
+/// What Explorer broadcasts once it has created the taskbar, registered on window creation.
+#[cfg(windows)]
+static mut TASKBAR_CREATED: UINT = 0;
+/// Set by `run` for as long as it pumps messages.
+#[cfg(windows)]
+static mut HANDLER: Option<*mut dyn Handler> = None;
+#[cfg(windows)]
+static mut ON_QUERY_END_SESSION: Option<fn()> = None;
+#[cfg(windows)]
+static mut ON_END_SESSION: Option<fn()> = None;
+
+#[cfg(windows)]
+// Called from the window procedure when Windows asks whether the session may end, then
+// once it does. Nothing runs after the second, the message loop never gets another turn
+pub fn on_end_session(query: fn(), end: fn()) {
+    unsafe {
+        ON_QUERY_END_SESSION = Some(query);
+        ON_END_SESSION = Some(end);
+    }
+}
+
+// Posted messages reach this through DispatchMessageW, from `run` or whichever modal
+// loop is running, sent ones come straight here
+#[cfg(windows)]
+unsafe extern "system" fn window_proc(handle: HWND, message: UINT, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    // Borrowed for one call at a time, a modal loop inside one must not find it taken
+    if let Some(handler) = HANDLER {
+        if message == WM_HOTKEY {
+            (*handler).hotkey(w_param);
+            return 0;
+        } else if message == WM_TIMER {
+            (*handler).timer(w_param);
+            return 0;
+        } else if message == WM_CONTROL {
+            (*handler).control();
+            return 0;
+        } else if message == WM_TRAY_NOTIFY {
+            let entries = (*handler).tray(l_param as u32);
+            let chosen = entries.and_then(|entries| tray::show_menu(&Window { handle }, &entries));
+            if let (Some(command), Some(handler)) = (chosen, HANDLER) {
+                (*handler).menu(command);
+            }
+            return 0;
+        } else if message == TASKBAR_CREATED && message != 0 {
+            (*handler).shell_restarted();
+            return 0;
+        } else if message == WM_DISPLAYCHANGE || message == WM_DPICHANGED {
+            (*handler).display_changed();
+        } else if message == WM_CLOSE {
+            (*handler).close();
+            PostQuitMessage(0);
+            return 0;
+        }
+    }
+    if message == WM_QUERYENDSESSION {
+        if let Some(query) = ON_QUERY_END_SESSION {
+            query();
+        }
+    } else if message == WM_ENDSESSION && w_param != 0 {
+        if let Some(end) = ON_END_SESSION {
+            end();
+        }
+        return 0;
+    }
+    DefWindowProcW(handle, message, w_param, l_param)
+}
+
 // Create window function
 #[cfg(windows)]
 pub fn create_hidden_window(identifier: &str) -> Result<Window, Error> {
@@ -40,7 +134,7 @@ pub fn create_hidden_window(identifier: &str) -> Result<Window, Error> {
         // Create "class" for window, using WNDCLASSW struct (different from Window our struct)
         let wnd_class = WNDCLASSW {
             style: 0, // Style
-            lpfnWndProc: Some(DefWindowProcW), // The callbackfunction for any window event that can occur in our window!!! Here you could react to events like WM_SIZE or WM_QUIT.
+            lpfnWndProc: Some(window_proc), // The callbackfunction for any window event that can occur in our window!!! Here you could react to events like WM_SIZE or WM_QUIT.
             hInstance: hinstance, // The instance handle for our application which we can retrieve by calling GetModuleHandleW.
             lpszClassName: name.as_ptr(), // Our class name which needs to be a UTF-16 string (defined earlier before unsafe). as_ptr() (Rust's own function) returns a raw pointer to the slice's buffer
             cbClsExtra: 0,
@@ -74,33 +168,49 @@ pub fn create_hidden_window(identifier: &str) -> Result<Window, Error> {
         if handle.is_null() {
             Err(Error::last_os_error())
         } else {
+            // Explorer isn't elevated, without this its broadcast never reaches an elevated win-gnome
+            TASKBAR_CREATED = RegisterWindowMessageW(win32_string("TaskbarCreated").as_ptr());
+            ChangeWindowMessageFilterEx(handle, TASKBAR_CREATED, MSGFLT_ALLOW, null_mut());
             Ok(Window { handle })
         }
     }
 }
 
 #[cfg(windows)]
-// Create message handling function with which to link to hook window to Windows messaging system
+// Pumps messages to the window procedure, which hands them to `handler`, until the window closes
 // More info: https://msdn.microsoft.com/en-us/library/windows/desktop/ms644927(v=vs.85).aspx
-pub fn handle_message(window: &Window, on_hot_key: impl Fn() -> bool, on_close: impl Fn() -> bool) -> bool {
+pub fn run(handler: &'static mut dyn Handler) {
     unsafe {
-        let mut message: MSG = mem::uninitialized();
-
-        // Get message from message queue with GetMessageW
-        if GetMessageW(&mut message as *mut MSG, window.handle, 0, 0) > 0 {
-            TranslateMessage(&message as *const MSG); // Translate message into something meaningful with TranslateMessage
-            DispatchMessageW(&message as *const MSG); // Dispatch message with DispatchMessageW
-            if message.message == WM_HOTKEY {
-                return on_hot_key();
-            } else if message.message == WM_CLOSE {
-                return on_close();
-            }
-            return true;
-        } else {
-            return false;
+        HANDLER = Some(handler);
+        let mut message: MSG = mem::zeroed();
+        // Without a window filter so WM_QUIT comes through
+        while GetMessageW(&mut message, null_mut(), 0, 0) > 0 {
+            TranslateMessage(&message);
+            DispatchMessageW(&message);
         }
+        HANDLER = None;
     }
 }
+#[cfg(windows)]
+// Posts WM_HOTKEY with `id` when the combination is pressed, false if another program has it
+pub fn register_hotkey(window: &Window, id: usize, modifiers: u32, key: u8) -> bool {
+    // MOD_NOREPEAT, holding the keys down doesn't fire again
+    unsafe { RegisterHotKey(window.handle, id as i32, modifiers | 0x4000, key as u32) != 0 }
+}
+#[cfg(windows)]
+pub fn unregister_hotkey(window: &Window, id: usize) -> bool {
+    unsafe { UnregisterHotKey(window.handle, id as i32) != 0 }
+}
+#[cfg(windows)]
+// Posts WM_TIMER with `id` to the window every `interval` milliseconds
+pub fn set_timer(window: &Window, id: usize, interval: u32) -> bool {
+    unsafe { SetTimer(window.handle, id, interval, None) != 0 }
+}
+#[cfg(windows)]
+// Stops the timer started with `set_timer`
+pub fn kill_timer(window: &Window, id: usize) -> bool {
+    unsafe { KillTimer(window.handle, id) != 0 }
+}
 pub fn previous_instance(identifier: &str) -> bool {
     unsafe {
         CreateMutexW(null_mut(), 1, win32_string(identifier).as_ptr());
@@ -0,0 +1,110 @@
+use win_gnome::action::{Chord, CornerAction};
+use win_gnome::config::Config;
+use win_gnome::shell::{vk, FakeShell};
+
+#[test]
+fn parses_chords() {
+    assert_eq!(
+        Chord::parse("Win+Shift+S").unwrap().keys,
+        vec![vk::LWIN, vk::SHIFT, b'S']
+    );
+    assert_eq!(
+        Chord::parse(" ctrl + alt + delete ").unwrap().keys,
+        vec![vk::CONTROL, vk::MENU, vk::DELETE]
+    );
+    assert_eq!(Chord::parse("Win+d").unwrap().keys, vec![vk::LWIN, b'D']);
+    assert_eq!(Chord::parse("F1").unwrap().keys, vec![vk::F1]);
+    assert_eq!(
+        Chord::parse("Alt+F24").unwrap().keys,
+        vec![vk::MENU, vk::F1 + 23]
+    );
+    assert_eq!(Chord::parse("Win+1").unwrap().keys, vec![vk::LWIN, b'1']);
+}
+
+#[test]
+fn rejects_bad_chords() {
+    assert!(Chord::parse("").is_err());
+    assert!(Chord::parse("Win+").is_err());
+    assert!(Chord::parse("Win+Hyper").is_err());
+    assert!(Chord::parse("F25").is_err());
+    assert!(Chord::parse("F0").is_err());
+    assert!(Chord::parse("Win+Win").is_err());
+    assert_eq!(
+        Chord::parse("Ctrl+Foo").unwrap_err(),
+        "Unknown key 'Foo' in 'Ctrl+Foo'"
+    );
+}
+
+#[test]
+fn chords_print_with_canonical_names() {
+    let chord = Chord::parse("control+escape+pagedown+f5+q").unwrap();
+    assert_eq!(chord.to_string(), "Ctrl+Esc+PageDown+F5+Q");
+    assert_eq!(Chord::parse(&chord.to_string()).unwrap(), chord);
+}
+
+#[test]
+fn actions_from_config() {
+    let action = |text: &str| {
+        Config::parse(&format!("corner_action = {}", text)).map(|config| config.corner_action)
+    };
+
+    assert_eq!(action("\"show_desktop\""), Ok(CornerAction::ShowDesktop));
+    assert_eq!(action("\"lock\""), Ok(CornerAction::Lock));
+    assert_eq!(action("\"nothing\""), Ok(CornerAction::Nothing));
+    assert_eq!(
+        action("{ keys = \"Win+A\" }"),
+        Ok(CornerAction::Keys(Chord::new(&[vk::LWIN, b'A'])))
+    );
+    assert_eq!(
+        action("{ launch = { program = \"wt.exe\", args = [\"-d\", \"C:\\\\\"] } }"),
+        Ok(CornerAction::Launch {
+            program: "wt.exe".to_string(),
+            args: vec!["-d".to_string(), "C:\\".to_string()],
+        })
+    );
+    assert_eq!(
+        action("{ launch = { program = \"calc.exe\" } }"),
+        Ok(CornerAction::Launch {
+            program: "calc.exe".to_string(),
+            args: vec![],
+        })
+    );
+    assert!(action("{ keys = \"Win+Nope\" }").is_err());
+    assert!(action("\"explode\"").is_err());
+}
+
+#[test]
+fn actions_go_through_the_backend() {
+    let mut shell = FakeShell::new(1920, 1080);
+    CornerAction::StartMenu.perform(&mut shell);
+    CornerAction::DesktopSelector.perform(&mut shell);
+    CornerAction::ShowDesktop.perform(&mut shell);
+    CornerAction::Keys(Chord::parse("Win+Shift+S").unwrap()).perform(&mut shell);
+    CornerAction::Nothing.perform(&mut shell);
+
+    assert_eq!(
+        shell.sent_keys,
+        vec![
+            vec![vk::LWIN],
+            vec![vk::LWIN, vk::TAB],
+            vec![vk::LWIN, b'D'],
+            vec![vk::LWIN, vk::SHIFT, b'S'],
+        ]
+    );
+    assert!(shell.launched.is_empty());
+    assert!(!shell.locked);
+
+    CornerAction::Launch {
+        program: "notepad.exe".to_string(),
+        args: vec!["todo.txt".to_string()],
+    }
+    .perform(&mut shell);
+    CornerAction::Lock.perform(&mut shell);
+
+    assert_eq!(
+        shell.launched,
+        vec![("notepad.exe".to_string(), vec!["todo.txt".to_string()])]
+    );
+    assert!(shell.locked);
+    assert_eq!(shell.sent_keys.len(), 4);
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use common::temp_dir;
+use win_gnome::autohide::{self, AppBarState, AutoHide};
+use win_gnome::config::Config;
+use win_gnome::shell::{FakeShell, ShellBackend, WindowId};
+
+const SHOWN: AppBarState = AppBarState::new(false, true);
+const HIDDEN: AppBarState = AppBarState::new(true, true);
+
+#[test]
+fn state_bits_round_trip() {
+    assert_eq!(AppBarState::from_bits(3), HIDDEN);
+    assert_eq!(AppBarState::from_bits(2), SHOWN);
+    assert_eq!(
+        HIDDEN.bits(),
+        autohide::ABS_AUTOHIDE | autohide::ABS_ALWAYSONTOP
+    );
+    assert_eq!(AppBarState::new(false, false).bits(), 0);
+}
+
+#[test]
+fn plans_holding_and_restoring() {
+    // Turned on once, and back off once no longer held
+    let mut auto_hide = AutoHide::new();
+    assert_eq!(auto_hide.plan(true, SHOWN), Some(HIDDEN));
+    assert_eq!(auto_hide.original, Some(SHOWN));
+    assert_eq!(auto_hide.plan(true, HIDDEN), None);
+    assert_eq!(auto_hide.plan(false, HIDDEN), Some(SHOWN));
+    assert_eq!(auto_hide.original, None);
+    assert_eq!(auto_hide.plan(false, SHOWN), None);
+
+    // Already on, so nothing to turn off afterwards
+    let mut auto_hide = AutoHide::new();
+    assert_eq!(auto_hide.plan(true, HIDDEN), None);
+    assert_eq!(auto_hide.plan(false, HIDDEN), None);
+
+    // Only auto-hide is put back, always on top stays as it is now
+    let mut auto_hide = AutoHide::new();
+    auto_hide.plan(true, SHOWN);
+    assert_eq!(
+        auto_hide.plan(false, AppBarState::new(true, false)),
+        Some(AppBarState::new(false, false))
+    );
+
+    // Never held, never touched
+    assert_eq!(AutoHide::new().plan(false, SHOWN), None);
+}
+
+#[test]
+fn follows_config() {
+    // A Windows setting is only changed when asked for
+    assert!(!Config::default().manages_auto_hide());
+    assert!(Config::parse("manage_auto_hide = true")
+        .unwrap()
+        .manages_auto_hide());
+    assert!(!Config::parse("manage_auto_hide = true\nhide_tray = false")
+        .unwrap()
+        .manages_auto_hide());
+}
+
+#[test]
+fn restores_after_crash() {
+    let path = temp_dir("autohide").join("auto-hide.toml");
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let taskbar = shell.find_window(Some("Shell_TrayWnd"), None).unwrap();
+
+    let mut auto_hide = AutoHide::open(Some(path.clone()));
+    auto_hide.apply(true, &mut shell, taskbar).unwrap();
+    assert_eq!(shell.app_bar, Some(HIDDEN));
+    assert_eq!(autohide::load(&path), Some(SHOWN));
+
+    // The next run finds the saved setting and doesn't mistake auto-hide for the user's choice
+    let mut next = AutoHide::open(Some(path.clone()));
+    assert_eq!(next.original, Some(SHOWN));
+    next.apply(true, &mut shell, taskbar).unwrap();
+    assert_eq!(shell.app_bar, Some(HIDDEN));
+    next.apply(false, &mut shell, taskbar).unwrap();
+    assert_eq!(shell.app_bar, Some(SHOWN));
+    assert!(!path.exists());
+
+    // Only a taskbar has the setting
+    assert!(AutoHide::new()
+        .apply(true, &mut shell, WindowId::default())
+        .is_err());
+    shell.app_bar = None;
+    assert!(AutoHide::new().apply(true, &mut shell, taskbar).is_err());
+}
+
+#[test]
+fn keeps_the_saved_setting_until_it_is_back() {
+    let path = temp_dir("autohide-locked").join("auto-hide.toml");
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let taskbar = shell.find_window(Some("Shell_TrayWnd"), None).unwrap();
+    let mut auto_hide = AutoHide::open(Some(path.clone()));
+    auto_hide.apply(true, &mut shell, taskbar).unwrap();
+
+    shell.app_bar_locked = true;
+    assert!(auto_hide.apply(false, &mut shell, taskbar).is_err());
+    assert_eq!(shell.app_bar, Some(HIDDEN));
+    assert_eq!(autohide::load(&path), Some(SHOWN));
+    assert_eq!(auto_hide.original, Some(SHOWN));
+
+    // Tried again once the taskbar takes it
+    shell.app_bar_locked = false;
+    auto_hide.apply(false, &mut shell, taskbar).unwrap();
+    assert_eq!(shell.app_bar, Some(SHOWN));
+    assert!(!path.exists());
+}
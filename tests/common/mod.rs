@@ -0,0 +1,10 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// An empty directory for `name`, unique to this test binary.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("win-gnome-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::temp_dir;
+use std::fs;
+use win_gnome::config::{Config, ConfigWatcher, Overrides};
+use win_gnome::engine::{Command, CornerAction, HotCornerEngine, TrayAction};
+use win_gnome::geometry::{CornerSize, TrayGeometry, TrayOrientation};
+
+#[test]
+fn missing_keys_keep_defaults() {
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    assert_eq!(
+        Config::parse("corner_action = \"desktop_selector\"\nsensitivity = 40").unwrap(),
+        Config {
+            corner_action: CornerAction::DesktopSelector,
+            sensitivity: 40,
+            ..Config::default()
+        }
+    );
+}
+
+#[test]
+fn rejects_bad_files() {
+    assert!(Config::parse("sensitivty = 40").is_err());
+    assert!(Config::parse("corner_action = \"launcher\"").is_err());
+    assert!(Config::parse("hide_tray = \"yes\"").is_err());
+    assert!(Config::parse("sensitivity = 0").is_err());
+    assert!(Config::parse("sensitivity = 101").is_err());
+}
+
+#[test]
+fn command_line_wins() {
+    let config = Config::parse("hide_tray = false\nsensitivity = 40").unwrap();
+    let overrides = Overrides {
+        corner_action: Some(CornerAction::DesktopSelector),
+        hide_tray: None,
+        sensitivity: Some(80),
+    };
+    let merged = config.merge(&overrides);
+
+    assert_eq!(merged.corner_action, CornerAction::DesktopSelector);
+    assert_eq!(merged.tray_action(), TrayAction::NoHide);
+    assert_eq!(merged.sensitivity, 80);
+}
+
+#[test]
+fn corner_size_wins_over_sensitivity() {
+    assert_eq!(Config::default().corner_size(), CornerSize::Button(100));
+    assert_eq!(
+        Config::parse("sensitivity = 40").unwrap().corner_size(),
+        CornerSize::Button(40)
+    );
+    let config = Config::parse("sensitivity = 40\ncorner_size = \"2% screen\"").unwrap();
+    assert_eq!(config.corner_size(), CornerSize::Screen(2));
+    assert!(Config::parse("corner_size = \"600%\"").is_err());
+    assert!(Config::parse("corner_size = 32").is_err());
+
+    // --sensitivity on the command line still wins over the file
+    let overrides = Overrides {
+        sensitivity: Some(80),
+        ..Overrides::default()
+    };
+    assert_eq!(
+        config.merge(&overrides).corner_size(),
+        CornerSize::Button(80)
+    );
+}
+
+#[test]
+fn missing_file_is_default() {
+    let dir = temp_dir("missing");
+    assert_eq!(
+        Config::load(&dir.join("config.toml")).unwrap(),
+        Config::default()
+    );
+    assert!(Config::load(&dir).is_err());
+}
+
+#[test]
+fn watcher_notices_writes() {
+    let path = temp_dir("watch").join("config.toml");
+    let mut watcher = ConfigWatcher::new(path.clone());
+    assert!(!watcher.changed());
+
+    fs::write(&path, "sensitivity = 50").unwrap();
+    assert!(watcher.changed());
+    assert!(!watcher.changed());
+    assert_eq!(Config::load(&path).unwrap().sensitivity, 50);
+
+    fs::write(&path, "sensitivity = 100").unwrap();
+    assert!(watcher.changed());
+
+    fs::remove_file(&path).unwrap();
+    assert!(watcher.changed());
+}
+
+#[test]
+fn reload_switches_tray_action() {
+    let mut engine = HotCornerEngine::default();
+    assert_eq!(engine.set_tray_action(TrayAction::Hide), vec![]);
+    assert_eq!(
+        engine.set_tray_action(TrayAction::NoHide),
+        vec![Command::ShowTray]
+    );
+    assert_eq!(
+        engine.set_tray_action(TrayAction::Hide),
+        vec![Command::HideTray]
+    );
+
+    engine.enabled = false;
+    assert_eq!(
+        engine.set_tray_action(TrayAction::NoHide),
+        vec![Command::ShowTray]
+    );
+    assert_eq!(engine.set_tray_action(TrayAction::Hide), vec![]);
+}
+
+#[test]
+fn reload_resizes_hot_corner() {
+    let geometry = TrayGeometry::new(TrayOrientation::Bottom, (1920, 1080), (48, 40), 100);
+    let smaller = geometry.with_sensitivity(50);
+
+    assert_eq!((smaller.hot_width, smaller.hot_height), (24, 20));
+    assert_eq!(smaller.with_sensitivity(100), geometry);
+}
@@ -0,0 +1,135 @@
+use std::io::{self, Cursor};
+use win_gnome::control::{self, read_message, write_message, Reply, Request};
+
+#[test]
+fn requests_by_name() {
+    for request in Request::ALL.iter() {
+        assert_eq!(Request::parse(request.name()), Some(*request));
+    }
+    assert_eq!(Request::parse("--toggle"), None);
+    assert_eq!(Request::parse("Toggle"), None);
+}
+
+#[test]
+fn every_session_has_its_own_pipe() {
+    assert_eq!(control::pipe_name(1), r"\\.\pipe\win_gnome-1");
+    assert_ne!(control::pipe_name(1), control::pipe_name(2));
+}
+
+#[test]
+fn messages_are_json_lines() {
+    let mut buffer = vec![];
+    write_message(&mut buffer, &Request::Toggle).unwrap();
+    write_message(&mut buffer, &Reply::Error("No config file".to_string())).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer.clone()).unwrap(),
+        "\"toggle\"\n{\"error\":\"No config file\"}\n"
+    );
+
+    let mut reader = Cursor::new(buffer);
+    assert_eq!(
+        read_message::<Request>(&mut reader).unwrap(),
+        Request::Toggle
+    );
+    assert_eq!(
+        read_message::<Reply>(&mut reader).unwrap(),
+        Reply::Error("No config file".to_string())
+    );
+    let error = read_message::<Reply>(&mut reader).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn replies_print_for_people() {
+    let status = Reply::Status {
+        enabled: false,
+        hide_tray: true,
+    };
+    assert_eq!(status.to_string(), "disabled, taskbar hidden");
+    assert_eq!(Reply::Ok.to_string(), "ok");
+}
+
+/// A stream that reads what was written to it beforehand, and keeps what is written now.
+struct Canned {
+    input: Cursor<Vec<u8>>,
+    output: Vec<u8>,
+}
+
+impl io::Read for Canned {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.input.read(buffer)
+    }
+}
+
+impl io::Write for Canned {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.output.write(buffer)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn bad_requests_get_an_error_reply() {
+    let mut stream = Canned {
+        input: Cursor::new(b"\"dance\"\n".to_vec()),
+        output: vec![],
+    };
+    let result = control::serve(&mut stream, |_| panic!("Handled a bad request"));
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    let reply: Reply = read_message(&mut Cursor::new(stream.output)).unwrap();
+    assert!(matches!(reply, Reply::Error(_)));
+}
+
+#[cfg(unix)]
+#[test]
+fn loopback_round_trip() {
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+
+    let (mut client, mut server) = UnixStream::pair().unwrap();
+    let server = thread::spawn(move || {
+        let mut enabled = true;
+        let mut served = vec![];
+        loop {
+            let request = control::serve(&mut server, |request| match request {
+                Request::Toggle => {
+                    enabled = !enabled;
+                    Reply::Status {
+                        enabled,
+                        hide_tray: true,
+                    }
+                }
+                Request::Reload => Reply::Error("No config file to reload".to_string()),
+                _ => Reply::Ok,
+            })
+            .unwrap();
+            served.push(request);
+            if request == Request::Quit {
+                return served;
+            }
+        }
+    });
+
+    assert_eq!(
+        control::send(&mut client, Request::Toggle).unwrap(),
+        Reply::Status {
+            enabled: false,
+            hide_tray: true
+        }
+    );
+    assert_eq!(
+        control::send(&mut client, Request::Reload).unwrap(),
+        Reply::Error("No config file to reload".to_string())
+    );
+    assert_eq!(
+        control::send(&mut client, Request::Quit).unwrap(),
+        Reply::Ok
+    );
+    assert_eq!(
+        server.join().unwrap(),
+        vec![Request::Toggle, Request::Reload, Request::Quit]
+    );
+}
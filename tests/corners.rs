@@ -0,0 +1,188 @@
+use win_gnome::config::Config;
+use win_gnome::corners::{CornerMap, CornerSettings};
+use win_gnome::engine::{Command, CornerAction, Event, HotCornerEngine, ShellState, TimerKind};
+use win_gnome::geometry::{Corner, CornerSize, TrayGeometry, TrayOrientation};
+
+const SELECTOR: CornerSettings = CornerSettings {
+    action: CornerAction::DesktopSelector,
+    sensitivity: 100,
+    size: None,
+    enabled: true,
+};
+
+fn geometry(orientation: TrayOrientation) -> TrayGeometry {
+    TrayGeometry::new(orientation, (1920, 1080), (48, 40), 100)
+}
+
+fn hit(corners: &CornerMap, geometry: &TrayGeometry, x: i32, y: i32) -> Option<Corner> {
+    corners
+        .hit(geometry, &CornerAction::StartMenu, x, y)
+        .map(|(corner, _)| corner)
+}
+
+#[test]
+fn start_corner_follows_the_tray() {
+    assert_eq!(
+        geometry(TrayOrientation::Bottom).start_corner(),
+        Corner::BottomLeft
+    );
+    assert_eq!(
+        geometry(TrayOrientation::Top).start_corner(),
+        Corner::TopLeft
+    );
+    assert_eq!(
+        geometry(TrayOrientation::Left).start_corner(),
+        Corner::TopLeft
+    );
+    assert_eq!(
+        geometry(TrayOrientation::Right).start_corner(),
+        Corner::TopRight
+    );
+}
+
+#[test]
+fn every_corner_is_sized_like_the_start_button() {
+    let geometry = geometry(TrayOrientation::Bottom);
+    let cases = [
+        (Corner::TopLeft, (47, 39), (48, 39)),
+        (Corner::TopRight, (1873, 0), (1872, 0)),
+        (Corner::BottomLeft, (0, 1041), (0, 1040)),
+        (Corner::BottomRight, (1919, 1079), (1919, 1040)),
+    ];
+    for &(corner, inside, outside) in &cases {
+        assert!(
+            geometry.is_in_corner(corner, 100, inside.0, inside.1),
+            "{:?}",
+            corner
+        );
+        assert!(
+            !geometry.is_in_corner(corner, 100, outside.0, outside.1),
+            "{:?}",
+            corner
+        );
+    }
+    assert!(!geometry.is_in_corner(Corner::TopLeft, 50, 30, 10));
+}
+
+#[test]
+fn edges_cover_the_middle_of_a_side() {
+    let geometry = geometry(TrayOrientation::Bottom);
+
+    assert!(geometry.is_in_corner(Corner::TopEdge, 100, 0, 0));
+    assert!(geometry.is_in_corner(Corner::TopEdge, 100, 1919, -3));
+    assert!(!geometry.is_in_corner(Corner::TopEdge, 100, 900, 1));
+    assert!(geometry.is_in_corner(Corner::RightEdge, 100, 1919, 500));
+    assert!(geometry.is_in_corner(Corner::LeftEdge, 50, 0, 270));
+    assert!(!geometry.is_in_corner(Corner::LeftEdge, 50, 0, 269));
+    assert!(geometry.is_in_corner(Corner::BottomEdge, 50, 1439, 1079));
+    assert!(!geometry.is_in_corner(Corner::BottomEdge, 50, 1440, 1079));
+}
+
+#[test]
+fn empty_map_only_has_the_start_corner() {
+    let corners = CornerMap::new();
+    let geometry = geometry(TrayOrientation::Right);
+
+    assert_eq!(hit(&corners, &geometry, 1919, 0), Some(Corner::TopRight));
+    assert_eq!(hit(&corners, &geometry, 0, 0), None);
+    assert_eq!(hit(&corners, &geometry, 1919, 1079), None);
+}
+
+#[test]
+fn map_adds_and_replaces_corners() {
+    let geometry = geometry(TrayOrientation::Bottom);
+    let mut corners = CornerMap::new();
+    corners.set(Corner::TopRight, SELECTOR);
+    corners.set(Corner::TopEdge, CornerSettings::default());
+    corners.set(
+        Corner::BottomLeft,
+        CornerSettings {
+            enabled: false,
+            ..CornerSettings::default()
+        },
+    );
+
+    assert_eq!(
+        corners.hit(&geometry, &CornerAction::StartMenu, 1919, 0),
+        Some((Corner::TopRight, &CornerAction::DesktopSelector))
+    );
+    // The corner wins over the edge it touches.
+    assert_eq!(hit(&corners, &geometry, 1900, 0), Some(Corner::TopRight));
+    assert_eq!(hit(&corners, &geometry, 900, 0), Some(Corner::TopEdge));
+    assert_eq!(hit(&corners, &geometry, 0, 1079), None);
+}
+
+#[test]
+fn corners_from_config() {
+    let config = Config::parse(
+        "[corners.top_right]\naction = \"desktop_selector\"\n\n[corners.bottom_left]\nenabled = false",
+    )
+    .unwrap();
+
+    assert_eq!(config.corners.get(Corner::TopRight), Some(&SELECTOR));
+    assert!(!config.corners.get(Corner::BottomLeft).unwrap().enabled);
+    assert_eq!(config.corners.get(Corner::TopLeft), None);
+
+    assert!(Config::parse("[corners.middle]\nenabled = true").is_err());
+    assert!(Config::parse("[corners.top_left]\nsensitivity = 0").is_err());
+}
+
+#[test]
+fn corners_sized_from_config() {
+    let config = Config::parse("[corners.bottom_right]\nsize = \"64px\"").unwrap();
+    let settings = config.corners.get(Corner::BottomRight).unwrap();
+    assert_eq!(settings.size, Some(CornerSize::Pixels(64)));
+
+    // Past the 48x40 start button
+    let geometry = geometry(TrayOrientation::Bottom);
+    assert_eq!(
+        hit(&config.corners, &geometry, 1860, 1020),
+        Some(Corner::BottomRight)
+    );
+    assert_eq!(hit(&config.corners, &geometry, 1855, 1079), None);
+
+    assert!(Config::parse("[corners.top_left]\nsize = \"0px\"").is_err());
+}
+
+struct Shell(TrayGeometry);
+
+impl ShellState for Shell {
+    fn geometry(&self) -> &TrayGeometry {
+        &self.0
+    }
+    fn full_screen_program(&mut self) -> bool {
+        false
+    }
+    fn is_tray_open(&self) -> bool {
+        false
+    }
+    fn start_menu_focused(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn corner_rearms_after_leaving_it() {
+    let mut shell = Shell(geometry(TrayOrientation::Bottom));
+    let mut engine = HotCornerEngine::default();
+    engine.corners.set(Corner::TopRight, SELECTOR);
+    let corner = Event::MouseMoved { x: 1919, y: 0 };
+
+    assert!(engine
+        .handle(corner, &mut shell)
+        .contains(&Command::Run(CornerAction::DesktopSelector)));
+    // Resting in the corner doesn't fire it again once the delay is over.
+    assert_eq!(
+        engine.handle(Event::TimerElapsed(TimerKind::Debounce), &mut shell),
+        vec![]
+    );
+    assert_eq!(engine.handle(corner, &mut shell), vec![]);
+
+    assert_eq!(
+        engine.handle(Event::MouseMoved { x: 900, y: 500 }, &mut shell),
+        vec![Command::HideTray]
+    );
+    assert!(engine
+        .handle(corner, &mut shell)
+        .contains(&Command::Run(CornerAction::DesktopSelector)));
+}
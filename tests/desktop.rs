@@ -0,0 +1,300 @@
+use win_gnome::desktop::Desktop;
+use win_gnome::engine::{Command, Event, HotCornerEngine, ShellState};
+use win_gnome::geometry::TrayOrientation;
+use win_gnome::monitor::Monitor;
+use win_gnome::shell::{vk, FakeShell, FakeWindow, ShellBackend};
+
+fn find(shell: &FakeShell, class_name: &str) -> win_gnome::shell::WindowId {
+    shell
+        .windows()
+        .find(|(_, window)| window.class_name == class_name)
+        .map(|(id, _)| *id)
+        .unwrap()
+}
+
+#[test]
+fn finds_windows10_shell() {
+    let shell = FakeShell::windows10(1920, 1080);
+    let bar = find(&shell, "Shell_TrayWnd");
+    let desktop = Desktop::new(shell, 50).unwrap();
+
+    assert_eq!((desktop.width, desktop.height), (1920, 1080));
+    assert_eq!(desktop.tray.bar, bar);
+    assert_eq!(desktop.tray.geometry.orientation, TrayOrientation::Bottom);
+    assert_eq!(
+        (
+            desktop.tray.geometry.hot_width,
+            desktop.tray.geometry.hot_height
+        ),
+        (24, 20)
+    );
+    assert_eq!(
+        desktop.backend.class_name(desktop.shell_window).unwrap(),
+        "SHELLDLL_DefView"
+    );
+}
+
+#[test]
+fn start_menu_not_found() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let menu = find(&shell, "Windows.UI.Core.CoreWindow");
+    shell.remove(menu);
+
+    assert_eq!(
+        Desktop::new(shell, 100).err(),
+        Some("Unable to find start menu")
+    );
+}
+
+#[test]
+fn start_menu_found_by_position() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let menu = find(&shell, "Windows.UI.Core.CoreWindow");
+    shell.window_mut(menu).unwrap().title = "Start".to_string();
+
+    // Not next to the start button, so it can't be the menu.
+    let desktop = Desktop::new(shell.clone(), 100);
+    assert_eq!(desktop.err(), Some("Unable to find start menu"));
+
+    shell.window_mut(menu).unwrap().pos = (440, 1040, 48, 688);
+    let desktop = Desktop::new(shell, 100).unwrap();
+    assert_eq!(desktop.tray.start_menu, menu);
+}
+
+#[test]
+fn progman_fallback() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let worker = find(&shell, "WorkerW");
+    shell.remove(worker);
+
+    assert_eq!(
+        Desktop::new(shell.clone(), 100).err(),
+        Some("Could not find shell window")
+    );
+
+    let desktop_window = shell.desktop_window();
+    let progman = shell.add(FakeWindow::new("Progman", "Program Manager").parent(desktop_window));
+    shell.add(FakeWindow::new("SHELLDLL_DefView", "").parent(progman));
+
+    assert_eq!(Desktop::new(shell, 100).unwrap().shell_parent, progman);
+}
+
+#[test]
+fn start_menu_focus_and_close() {
+    let shell = FakeShell::windows10(1920, 1080);
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+    let menu = desktop.tray.start_menu;
+    let bar = desktop.tray.bar;
+
+    desktop.hide_tray();
+    assert!(!desktop.backend.is_visible(bar));
+
+    desktop.backend.window_mut(menu).unwrap().cloaked = false;
+    assert!(!desktop.foreground_changed(menu));
+    assert!(desktop.start_menu_focused());
+    assert!(desktop.backend.is_visible(bar));
+
+    // Focus moved elsewhere but the menu is still up.
+    let shell_window = desktop.shell_window;
+    assert!(!desktop.foreground_changed(shell_window));
+
+    desktop.backend.window_mut(menu).unwrap().cloaked = true;
+    assert!(desktop.foreground_changed(shell_window));
+    assert!(!desktop.tray.startmenu_showing);
+}
+
+#[test]
+fn menu_check_waits_for_the_compositor() {
+    let shell = FakeShell::windows10(1920, 1080);
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+    let menu = desktop.tray.start_menu;
+    let shell_window = desktop.shell_window;
+
+    // Nothing open, nothing to check.
+    assert!(!desktop.set_foreground(shell_window));
+
+    desktop.backend.window_mut(menu).unwrap().cloaked = false;
+    assert!(!desktop.set_foreground(menu));
+    assert!(desktop.set_foreground(shell_window));
+    // DWM hasn't cloaked the menu yet.
+    assert!(!desktop.check_menus());
+    assert!(desktop.tray.startmenu_showing);
+
+    desktop.backend.window_mut(menu).unwrap().cloaked = true;
+    assert!(desktop.check_menus());
+    assert!(!desktop.check_menus());
+}
+
+#[test]
+fn full_screen_program() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let desktop_window = shell.desktop_window();
+    let game = shell.add(
+        FakeWindow::new("Game", "Game")
+            .parent(desktop_window)
+            .pos(0, 1080, 0, 1920),
+    );
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+
+    assert!(!ShellState::full_screen_program(&mut desktop));
+    desktop.foreground_changed(game);
+    assert!(ShellState::full_screen_program(&mut desktop));
+}
+
+#[test]
+fn shell_restart_is_picked_up() {
+    let shell = FakeShell::windows10(1920, 1080);
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+    assert!(!desktop.shell_changed());
+
+    let old_bar = desktop.tray.bar;
+    desktop.backend.remove(old_bar);
+    let worker = find(&desktop.backend, "WorkerW");
+    desktop.backend.remove(worker);
+    assert!(!desktop.hide_tray());
+
+    // Explorer comes back with new handles.
+    let shell = &mut desktop.backend;
+    let desktop_window = shell.desktop_window();
+    let worker = shell.add(
+        FakeWindow::new("WorkerW", "")
+            .parent(desktop_window)
+            .pos(0, 1080, 0, 1920),
+    );
+    shell.add(FakeWindow::new("SHELLDLL_DefView", "").parent(worker));
+    let bar = shell.add(
+        FakeWindow::new("Shell_TrayWnd", "")
+            .parent(desktop_window)
+            .pos(1040, 1080, 0, 1920),
+    );
+    shell.add(
+        FakeWindow::new("Start", "Start")
+            .parent(bar)
+            .pos(1040, 1080, 0, 48),
+    );
+
+    assert!(desktop.shell_changed());
+    assert_eq!(desktop.tray.bar, bar);
+    assert!(desktop.hide_tray());
+}
+
+#[test]
+fn taskbar_created_rebuilds_and_hides_again() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    shell.add_monitor(Monitor::new(0, 1080, 1920, 3840));
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+    let mut engine = HotCornerEngine::default();
+    for command in engine.start() {
+        desktop.execute(command);
+    }
+    let (old_bar, old_secondary) = (desktop.tray.bar, desktop.secondary_trays[0].bar);
+
+    desktop.backend.restart_explorer();
+    let bar = find(&desktop.backend, "Shell_TrayWnd");
+    let secondary = find(&desktop.backend, "Shell_SecondaryTrayWnd");
+    assert!(!desktop.backend.is_window(old_bar));
+    assert!(desktop.backend.is_visible(bar) && desktop.backend.is_visible(secondary));
+
+    desktop.shell_restarted().unwrap();
+    assert_eq!(desktop.tray.bar, bar);
+    assert_ne!(desktop.secondary_trays[0].bar, old_secondary);
+    let commands = engine.handle(Event::ShellRestarted, &mut desktop);
+    assert_eq!(commands, vec![Command::HideTray]);
+    for command in commands {
+        desktop.execute(command);
+    }
+    assert!(!desktop.backend.is_visible(bar));
+    assert!(!desktop.backend.is_visible(secondary));
+
+    // Disabled, the new taskbar is left showing
+    engine.handle(Event::Hotkey, &mut desktop);
+    desktop.backend.restart_explorer();
+    desktop.shell_restarted().unwrap();
+    assert_eq!(engine.handle(Event::ShellRestarted, &mut desktop), vec![]);
+}
+
+#[test]
+fn shortcuts_send_keys() {
+    let shell = FakeShell::windows10(1920, 1080);
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+
+    desktop.open_start_menu();
+    desktop.open_desktop_selector();
+    assert_eq!(
+        desktop.backend.sent_keys,
+        vec![vec![vk::LWIN], vec![vk::LWIN, vk::TAB]]
+    );
+}
+
+#[test]
+fn secondary_monitors_have_their_own_corners() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let left = shell.add_monitor(Monitor::new(-360, 1080, -2560, 0));
+    let top = shell.add_monitor(Monitor::new(-1080, 0, 0, 1920));
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+
+    assert_eq!(desktop.layout.monitors.len(), 3);
+    assert_eq!(
+        desktop
+            .secondary_trays
+            .iter()
+            .map(|tray| tray.bar)
+            .collect::<Vec<_>>(),
+        vec![left, top]
+    );
+    assert_eq!(desktop.screens[1].parent_height, 1440);
+
+    let (geometry, x, y) = desktop.locate(-2559, 1079);
+    assert_eq!((x, y), (1, 1439));
+    assert!(geometry.is_hot_corner(x, y));
+    let (geometry, x, y) = desktop.locate(1, -1);
+    assert!(geometry.is_hot_corner(x, y));
+    let (geometry, x, y) = desktop.locate(-1, 1079);
+    assert!(!geometry.is_hot_corner(x, y));
+
+    assert!(desktop.hide_tray());
+    for bar in &[desktop.tray.bar, left, top] {
+        assert!(!desktop.backend.is_visible(*bar));
+    }
+    desktop.show_tray();
+    for bar in &[desktop.tray.bar, left, top] {
+        assert!(desktop.backend.is_visible(*bar));
+    }
+}
+
+#[test]
+fn monitors_without_a_taskbar_get_bottom_corners() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    shell.monitors.push(Monitor::new(0, 1200, 1920, 3840));
+    let desktop = Desktop::new(shell, 50).unwrap();
+
+    assert!(desktop.secondary_trays.is_empty());
+    assert_eq!(desktop.screens[1].orientation, TrayOrientation::Bottom);
+    assert_eq!(
+        (desktop.screens[1].hot_width, desktop.screens[1].hot_height),
+        (24, 20)
+    );
+    let (geometry, x, y) = desktop.locate(1920, 1199);
+    assert!(geometry.is_hot_corner(x, y));
+}
+
+#[test]
+fn corners_keep_their_size_across_scales() {
+    // A 150% laptop screen with a 100% monitor next to it
+    let mut shell = FakeShell::windows10(2880, 1620);
+    shell.monitors[0].dpi = 144;
+    shell.monitors.push(Monitor::new(0, 1080, 2880, 4800));
+    let desktop = Desktop::new(shell, 100).unwrap();
+
+    assert_eq!(desktop.tray.geometry.scale.percent(), 150);
+    assert_eq!(desktop.screens[0].scale.percent(), 150);
+    assert_eq!(desktop.screens[1].scale.percent(), 100);
+    assert_eq!(
+        (desktop.screens[1].hot_width, desktop.screens[1].hot_height),
+        (32, 27)
+    );
+    let (geometry, x, y) = desktop.locate(2880 + 31, 1079 - 25);
+    assert!(geometry.is_hot_corner(x, y));
+    let (geometry, x, y) = desktop.locate(2880 + 40, 1079);
+    assert!(!geometry.is_hot_corner(x, y));
+}
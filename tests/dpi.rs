@@ -0,0 +1,52 @@
+use win_gnome::dpi::{mul_div, Scale, DEFAULT_DPI};
+use win_gnome::monitor::Monitor;
+
+#[test]
+fn mul_div_rounds_to_nearest() {
+    assert_eq!(mul_div(40, 96, 144), 27);
+    assert_eq!(mul_div(10, 3, 4), 8);
+    assert_eq!(mul_div(-10, 3, 4), -8);
+    assert_eq!(mul_div(5, 1, 2), 3);
+    assert_eq!(mul_div(-5, 1, 2), -3);
+    assert_eq!(mul_div(7, 1, 0), 7);
+}
+
+#[test]
+fn converts_at_common_scales() {
+    let table = [
+        (96, 100, 48, 48),
+        (120, 125, 48, 60),
+        (144, 150, 48, 72),
+        (168, 175, 48, 84),
+        (192, 200, 48, 96),
+    ];
+    for &(dpi, percent, dips, pixels) in &table {
+        let scale = Scale::new(dpi);
+        assert_eq!(scale.percent(), percent, "{} dpi", dpi);
+        assert_eq!(scale.to_physical(dips), pixels, "{} dpi", dpi);
+        assert_eq!(scale.to_dips(pixels), dips, "{} dpi", dpi);
+        // Round trips stay put for every size up to a screen
+        for size in 0..4000 {
+            assert_eq!(
+                scale.to_dips(scale.to_physical(size)),
+                size,
+                "{} at {} dpi",
+                size,
+                dpi
+            );
+        }
+    }
+    assert_eq!(Scale::new(144).factor(), 1.5);
+    assert_eq!(Scale::new(0), Scale::default());
+    assert_eq!(Scale::default().dpi, DEFAULT_DPI);
+}
+
+#[test]
+fn rescales_between_monitors() {
+    let laptop = Monitor::new(0, 1620, 0, 2880).dpi(144).scale();
+    let external = Monitor::new(0, 1080, 2880, 4800).scale();
+    assert_eq!(external.rescale_size((48, 40), laptop), (32, 27));
+    assert_eq!(laptop.rescale_size((32, 27), external), (48, 41));
+    assert_eq!(external.size_to_dips((48, 40)), (48, 40));
+    assert_eq!(laptop.size_to_physical((48, 40)), (72, 60));
+}
@@ -0,0 +1,212 @@
+use win_gnome::engine::{
+    Command, CornerAction, Event, HotCornerEngine, ShellState, TimerKind, TrayAction, CORNER_DELAY,
+};
+use win_gnome::geometry::{TrayGeometry, TrayOrientation};
+
+struct Shell {
+    geometry: TrayGeometry,
+    full_screen: bool,
+    tray_open: bool,
+    menu_focused: bool,
+}
+
+impl Shell {
+    fn new() -> Shell {
+        Shell {
+            geometry: TrayGeometry::new(TrayOrientation::Bottom, (1920, 1080), (48, 40), 100),
+            full_screen: false,
+            tray_open: false,
+            menu_focused: false,
+        }
+    }
+}
+
+impl ShellState for Shell {
+    fn geometry(&self) -> &TrayGeometry {
+        &self.geometry
+    }
+    fn full_screen_program(&mut self) -> bool {
+        self.full_screen
+    }
+    fn is_tray_open(&self) -> bool {
+        self.tray_open
+    }
+    fn start_menu_focused(&self) -> bool {
+        self.menu_focused
+    }
+}
+
+const CORNER: Event = Event::MouseMoved { x: 1, y: 1079 };
+const MIDDLE: Event = Event::MouseMoved { x: 900, y: 500 };
+
+#[test]
+fn start_hides_tray() {
+    let mut engine = HotCornerEngine::default();
+    assert_eq!(engine.start(), vec![Command::HideTray]);
+
+    let mut engine = HotCornerEngine::new(CornerAction::StartMenu, TrayAction::NoHide);
+    assert_eq!(engine.start(), vec![]);
+}
+
+#[test]
+fn corner_opens_start_menu() {
+    let mut shell = Shell::new();
+    let mut engine = HotCornerEngine::default();
+
+    assert_eq!(engine.handle(MIDDLE, &mut shell), vec![]);
+    assert_eq!(
+        engine.handle(CORNER, &mut shell),
+        vec![
+            Command::ShowTray,
+            Command::Run(CornerAction::StartMenu),
+            Command::StartTimer(TimerKind::Debounce, CORNER_DELAY)
+        ]
+    );
+    assert!(!engine.hot_active);
+}
+
+#[test]
+fn corner_opens_selector_without_touching_tray() {
+    let mut shell = Shell::new();
+    let mut engine = HotCornerEngine::new(CornerAction::DesktopSelector, TrayAction::NoHide);
+
+    assert_eq!(
+        engine.handle(CORNER, &mut shell),
+        vec![
+            Command::Run(CornerAction::DesktopSelector),
+            Command::StartTimer(TimerKind::Debounce, CORNER_DELAY)
+        ]
+    );
+}
+
+#[test]
+fn full_screen_program_blocks_corner() {
+    let mut shell = Shell::new();
+    shell.full_screen = true;
+    let mut engine = HotCornerEngine::default();
+
+    assert_eq!(engine.handle(CORNER, &mut shell), vec![]);
+    assert!(engine.hot_active);
+}
+
+#[test]
+fn delay_swallows_moves_until_timer() {
+    let mut shell = Shell::new();
+    let mut engine = HotCornerEngine::default();
+    engine.handle(CORNER, &mut shell);
+
+    // Leaving the tray while delayed does nothing, the timer replays the last position.
+    assert_eq!(engine.handle(MIDDLE, &mut shell), vec![]);
+    assert_eq!(engine.last_position(), (900, 500));
+    assert_eq!(
+        engine.handle(Event::TimerElapsed(TimerKind::Debounce), &mut shell),
+        vec![Command::HideTray]
+    );
+    assert!(engine.hot_active);
+}
+
+#[test]
+fn leaving_keeps_tray_while_menu_or_tray_in_use() {
+    let mut shell = Shell::new();
+    let mut engine = HotCornerEngine::default();
+    engine.handle(CORNER, &mut shell);
+    engine.handle(Event::TimerElapsed(TimerKind::Debounce), &mut shell);
+
+    shell.menu_focused = true;
+    assert_eq!(engine.handle(MIDDLE, &mut shell), vec![]);
+
+    shell.menu_focused = false;
+    shell.tray_open = true;
+    assert_eq!(engine.handle(MIDDLE, &mut shell), vec![]);
+    assert!(!engine.hot_active);
+
+    shell.tray_open = false;
+    assert_eq!(engine.handle(MIDDLE, &mut shell), vec![Command::HideTray]);
+}
+
+#[test]
+fn closing_menu_outside_tray_forces_hide() {
+    let mut shell = Shell::new();
+    shell.tray_open = true;
+    let mut engine = HotCornerEngine::default();
+    engine.handle(CORNER, &mut shell);
+    engine.handle(Event::TimerElapsed(TimerKind::Debounce), &mut shell);
+
+    let closed = Event::ForegroundChanged { menu_closed: true };
+    // Still over the tray: leave it alone.
+    engine.handle(Event::MouseMoved { x: 600, y: 1070 }, &mut shell);
+    assert_eq!(engine.handle(closed, &mut shell), vec![]);
+
+    engine.handle(MIDDLE, &mut shell);
+    assert_eq!(engine.handle(closed, &mut shell), vec![Command::HideTray]);
+    assert!(engine.hot_active);
+}
+
+#[test]
+fn hotkey_toggles_and_disables_hooks() {
+    let mut shell = Shell::new();
+    let mut engine = HotCornerEngine::default();
+
+    assert_eq!(
+        engine.handle(Event::Hotkey, &mut shell),
+        vec![Command::ShowTray]
+    );
+    assert!(!engine.enabled);
+    assert_eq!(engine.handle(CORNER, &mut shell), vec![]);
+    assert_eq!(
+        engine.handle(Event::ForegroundChanged { menu_closed: true }, &mut shell),
+        vec![]
+    );
+
+    assert_eq!(
+        engine.handle(Event::Hotkey, &mut shell),
+        vec![Command::HideTray]
+    );
+    assert!(engine.enabled && engine.hot_active);
+}
+
+#[test]
+fn close_restores_tray() {
+    let mut shell = Shell::new();
+    let mut engine = HotCornerEngine::new(CornerAction::StartMenu, TrayAction::NoHide);
+
+    assert_eq!(
+        engine.handle(Event::Close, &mut shell),
+        vec![Command::ShowTray]
+    );
+    assert_eq!(engine.handle(CORNER, &mut shell), vec![]);
+}
+
+#[test]
+fn hotkeys_trigger_corner_and_peek_taskbar() {
+    let mut shell = Shell::new();
+    let mut engine = HotCornerEngine::default();
+    engine.handle(MIDDLE, &mut shell);
+
+    assert_eq!(
+        engine.handle(Event::TriggerCorner, &mut shell),
+        vec![
+            Command::ShowTray,
+            Command::Run(CornerAction::StartMenu),
+            Command::StartTimer(TimerKind::Debounce, CORNER_DELAY)
+        ]
+    );
+
+    let mut engine = HotCornerEngine::default();
+    engine.handle(MIDDLE, &mut shell);
+    assert_eq!(
+        engine.handle(Event::PeekTaskbar, &mut shell),
+        vec![Command::ShowTray]
+    );
+    // Stays up when the mouse moves around
+    assert_eq!(engine.handle(MIDDLE, &mut shell), vec![]);
+    assert_eq!(
+        engine.handle(Event::PeekTaskbar, &mut shell),
+        vec![Command::HideTray]
+    );
+
+    let mut engine = HotCornerEngine::new(CornerAction::StartMenu, TrayAction::NoHide);
+    assert_eq!(engine.handle(Event::PeekTaskbar, &mut shell), vec![]);
+    engine.handle(Event::Hotkey, &mut shell);
+    assert_eq!(engine.handle(Event::TriggerCorner, &mut shell), vec![]);
+}
@@ -0,0 +1,215 @@
+use win_gnome::engine::{Command, CornerAction, Event, HotCornerEngine, ShellState, TimerKind};
+use win_gnome::ewmh::EwmhDesktop;
+use win_gnome::geometry::Corner;
+use win_gnome::monitor::Monitor;
+use win_gnome::shell::{vk, DockBackend, FakeShell, FakeWindow, ShellBackend};
+
+#[test]
+fn every_monitor_gets_corners() {
+    let mut shell = FakeShell::new(1920, 1080);
+    shell.monitors = vec![
+        Monitor::new(0, 1080, 0, 1920).primary(),
+        Monitor::new(0, 1440, 1920, 4480),
+    ];
+    let desktop = EwmhDesktop::new(shell, 100);
+
+    assert_eq!(desktop.screens.len(), 2);
+    let (geometry, x, y) = desktop.locate(1920, 1439);
+    assert_eq!((x, y), (0, 1439));
+    assert!(geometry.is_hot_corner(x, y));
+    assert!(desktop.geometry().is_hot_corner(0, 1079));
+    assert!(!desktop.geometry().is_hot_corner(48, 1079));
+}
+
+#[test]
+fn no_monitors_uses_the_root_window() {
+    let mut shell = FakeShell::new(1280, 800);
+    shell.monitors.clear();
+    let desktop = EwmhDesktop::new(shell, 50);
+
+    assert_eq!(desktop.layout.bounds(), (0, 800, 0, 1280));
+    assert!(desktop.geometry().is_hot_corner(0, 799));
+    assert!(!desktop.geometry().is_hot_corner(24, 799));
+}
+
+#[test]
+fn full_screen_on_any_monitor() {
+    let mut shell = FakeShell::new(1920, 1080);
+    shell.monitors = vec![
+        Monitor::new(0, 1080, 0, 1920).primary(),
+        Monitor::new(0, 1080, 1920, 3840),
+    ];
+    let video = shell.add(FakeWindow::new("mpv", "video").pos(0, 1080, 1920, 3840));
+    let editor = shell.add(FakeWindow::new("Code", "editor").pos(0, 1050, 0, 1920));
+    let mut desktop = EwmhDesktop::new(shell, 100);
+
+    desktop.foreground_changed(video);
+    assert!(desktop.full_screen_program());
+    desktop.foreground_changed(editor);
+    assert!(!desktop.full_screen_program());
+}
+
+#[test]
+fn corner_presses_super() {
+    let mut desktop = EwmhDesktop::new(FakeShell::new(1920, 1080), 100);
+    let mut engine = HotCornerEngine::default();
+
+    let commands = engine.handle(Event::MouseMoved { x: 0, y: 1079 }, &mut desktop);
+    assert!(commands.contains(&Command::Run(CornerAction::StartMenu)));
+    for command in commands {
+        desktop.execute(command);
+    }
+    assert_eq!(desktop.backend.sent_keys, vec![vec![vk::LWIN]]);
+}
+
+const BOTTOM_STRUT: [u32; 12] = [0, 0, 0, 30, 0, 0, 0, 0, 0, 0, 0, 1919];
+
+#[test]
+fn docks_hide_and_give_back_their_space() {
+    let mut shell = FakeShell::new(1920, 1080);
+    let panel = shell.add(
+        FakeWindow::new("Polybar", "polybar-main")
+            .pos(1050, 1080, 0, 1920)
+            .dock(Some(BOTTOM_STRUT)),
+    );
+    let mut desktop = EwmhDesktop::new(shell, 100);
+    assert_eq!(desktop.docks.len(), 1);
+
+    desktop.execute(Command::HideTray);
+    assert!(!desktop.backend.is_visible(panel));
+    assert_eq!(desktop.backend.strut(panel), None);
+    // Hidden docks are still known after a refresh.
+    desktop.refresh();
+    assert_eq!(desktop.docks.len(), 1);
+
+    desktop.execute(Command::ShowTray);
+    assert!(desktop.backend.is_visible(panel));
+    assert_eq!(desktop.backend.strut(panel), Some(BOTTOM_STRUT));
+}
+
+#[test]
+fn dock_that_stays_up_keeps_its_space() {
+    let mut shell = FakeShell::new(1920, 1080);
+    let panel = shell.add(
+        FakeWindow::new("Polybar", "polybar-main")
+            .pos(1050, 1080, 0, 1920)
+            .dock(Some(BOTTOM_STRUT)),
+    );
+    let mut desktop = EwmhDesktop::new(shell, 100);
+
+    desktop.backend.visibility_locked = true;
+    desktop.execute(Command::HideTray);
+    assert!(desktop.backend.is_visible(panel));
+    assert_eq!(desktop.backend.strut(panel), Some(BOTTOM_STRUT));
+    assert!(desktop.docks[0].showing);
+
+    // Hiding it once it can be unmapped still gives the strut back after
+    desktop.backend.visibility_locked = false;
+    desktop.execute(Command::HideTray);
+    assert_eq!(desktop.backend.strut(panel), None);
+    desktop.execute(Command::ShowTray);
+    assert!(desktop.backend.is_visible(panel));
+    assert_eq!(desktop.backend.strut(panel), Some(BOTTOM_STRUT));
+}
+
+#[test]
+fn corners_follow_the_dock() {
+    let mut shell = FakeShell::new(1920, 1080);
+    shell.add(
+        FakeWindow::new("Tint2", "tint2")
+            .pos(0, 60, 0, 1920)
+            .dock(None),
+    );
+    let desktop = EwmhDesktop::new(shell, 100);
+
+    assert_eq!(desktop.geometry().start_corner(), Corner::TopLeft);
+    // The corner is as deep as the dock.
+    assert!(desktop.geometry().is_hot_corner(47, 59));
+    assert!(!desktop.geometry().is_hot_corner(48, 59));
+    assert!(desktop.geometry().is_tray_region(900, 59));
+}
+
+#[test]
+fn corner_reveals_the_dock() {
+    let mut shell = FakeShell::new(1920, 1080);
+    let panel = shell.add(
+        FakeWindow::new("Xfce4-panel", "panel")
+            .pos(1040, 1080, 0, 1920)
+            .dock(None),
+    );
+    let mut desktop = EwmhDesktop::new(shell, 100);
+    let mut engine = HotCornerEngine::default();
+    for command in engine.start() {
+        desktop.execute(command);
+    }
+    let mut run = |event, desktop: &mut EwmhDesktop<FakeShell>| {
+        for command in engine.handle(event, desktop) {
+            desktop.execute(command);
+        }
+    };
+
+    assert!(!desktop.backend.is_visible(panel));
+
+    run(Event::MouseMoved { x: 0, y: 1079 }, &mut desktop);
+    assert!(desktop.backend.is_visible(panel));
+    run(Event::TimerElapsed(TimerKind::Debounce), &mut desktop);
+    // Still over the dock.
+    run(Event::MouseMoved { x: 900, y: 1060 }, &mut desktop);
+    assert!(desktop.backend.is_visible(panel));
+    run(Event::MouseMoved { x: 900, y: 500 }, &mut desktop);
+    assert!(!desktop.backend.is_visible(panel));
+}
+
+#[test]
+fn docks_started_later_are_found() {
+    let mut desktop = EwmhDesktop::new(FakeShell::new(1920, 1080), 100);
+    desktop.execute(Command::HideTray);
+    let panel = desktop.backend.add(
+        FakeWindow::new("Polybar", "polybar-main")
+            .pos(1050, 1080, 0, 1920)
+            .dock(Some(BOTTOM_STRUT)),
+    );
+    let window = desktop.backend.add(FakeWindow::new("XTerm", "xterm"));
+    assert!(!desktop.window_mapped(window));
+    assert!(desktop.window_mapped(panel));
+
+    desktop.refresh();
+    assert!(desktop.hidden);
+    desktop.execute(Command::HideTray);
+    assert!(!desktop.backend.is_visible(panel));
+    // Mapping it again is no news
+    assert!(!desktop.window_mapped(panel));
+
+    // Closed docks are forgotten, hidden or not
+    desktop.backend.remove(panel);
+    desktop.refresh();
+    assert!(desktop.docks.is_empty());
+}
+
+#[test]
+fn tray_stays_open_over_a_dock_or_its_menus() {
+    let mut shell = FakeShell::new(1920, 1080);
+    let panel = shell.add(
+        FakeWindow::new("Xfce4-panel", "panel")
+            .pos(0, 40, 0, 1920)
+            .dock(None),
+    );
+    let mut desktop = EwmhDesktop::new(shell, 100);
+    desktop.pointer_moved(900, 20);
+    assert!(desktop.is_tray_open());
+    desktop.pointer_moved(900, 500);
+    assert!(!desktop.is_tray_open());
+
+    let menu = desktop
+        .backend
+        .add(FakeWindow::new("Xfce4-panel", "menu").transient_for(panel));
+    assert!(!desktop.window_mapped(menu));
+    assert!(desktop.is_tray_open());
+    desktop.window_unmapped(menu);
+    assert!(!desktop.is_tray_open());
+
+    // A hidden dock isn't in the way
+    desktop.execute(Command::HideTray);
+    desktop.pointer_moved(900, 20);
+    assert!(!desktop.is_tray_open());
+}
@@ -0,0 +1,108 @@
+use win_gnome::config::Config;
+use win_gnome::desktop::Desktop;
+use win_gnome::engine::{Command, CornerAction, Event, HotCornerEngine, ShellState};
+use win_gnome::exclude::{matches_pattern, ExcludeList, ExcludeRule, WindowInfo};
+use win_gnome::shell::{FakeShell, FakeWindow};
+
+fn window(process: &str, class: &str, title: &str) -> WindowInfo {
+    WindowInfo {
+        process: Some(process.to_string()),
+        class: Some(class.to_string()),
+        title: Some(title.to_string()),
+    }
+}
+
+fn exclusions(text: &str) -> ExcludeList {
+    Config::parse(text).unwrap().exclude
+}
+
+#[test]
+fn patterns_ignore_case_and_expand_wildcards() {
+    assert!(matches_pattern("mstsc.exe", "MSTSC.EXE"));
+    assert!(matches_pattern("*", ""));
+    assert!(matches_pattern("*Layout*", "Drawing1 - Layout1 - AutoCAD"));
+    assert!(matches_pattern("vm?are*", "VMware Workstation"));
+    assert!(matches_pattern("a*b*c", "aXbYbZc"));
+    assert!(!matches_pattern("a*b*c", "aXbYbZ"));
+    assert!(!matches_pattern("mstsc", "mstsc.exe"));
+    assert!(!matches_pattern("?", ""));
+}
+
+#[test]
+fn every_field_of_a_rule_has_to_match() {
+    let list = exclusions(
+        r#"
+        [[exclude]]
+        process = "mstsc.exe"
+
+        [[exclude]]
+        process = "acad.exe"
+        title = "*Layout*"
+        "#,
+    );
+    assert_eq!(list.rules.len(), 2);
+
+    let remote = window("mstsc.exe", "TscShellContainerClass", "Work PC");
+    assert_eq!(list.matches(&remote), Some(&list.rules[0]));
+    assert!(list
+        .matches(&window("acad.exe", "AfxFrame", "Drawing1 - Layout1"))
+        .is_some());
+    assert!(list
+        .matches(&window("acad.exe", "AfxFrame", "Drawing1 - Model"))
+        .is_none());
+    // Unknown fields don't match a pattern
+    let unknown = WindowInfo {
+        title: Some("Drawing1 - Layout1".to_string()),
+        ..WindowInfo::default()
+    };
+    assert!(list.matches(&unknown).is_none());
+}
+
+#[test]
+fn empty_rules_are_rejected() {
+    assert!(Config::parse("[[exclude]]").is_err());
+    assert!(Config::parse("[[exclude]]\nprogram = \"mstsc.exe\"").is_err());
+    assert!(ExcludeRule::default().validate().is_err());
+    assert!(Config::parse("").unwrap().exclude.is_empty());
+}
+
+#[test]
+fn excluded_windows_keep_the_corner_and_taskbar_alone() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let remote = shell.add(
+        FakeWindow::new(
+            "TscShellContainerClass",
+            "Work PC - Remote Desktop Connection",
+        )
+        .process("mstsc.exe")
+        .pos(0, 1040, 0, 1920),
+    );
+    let notepad = shell.add(FakeWindow::new("Notepad", "notes.txt").process("notepad.exe"));
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+    desktop.set_exclusions(exclusions(
+        "[[exclude]]\nclass = \"TscShellContainerClass\"",
+    ));
+    let mut engine = HotCornerEngine::default();
+    engine.start();
+
+    desktop.foreground_changed(remote);
+    assert!(desktop.excluded());
+    assert!(engine
+        .handle(Event::MouseMoved { x: 1, y: 1079 }, &mut desktop)
+        .is_empty());
+    assert!(engine
+        .handle(Event::ForegroundChanged { menu_closed: true }, &mut desktop)
+        .is_empty());
+
+    desktop.foreground_changed(notepad);
+    assert!(!desktop.excluded());
+    assert_eq!(
+        engine.handle(Event::MouseMoved { x: 1, y: 1079 }, &mut desktop)[..2],
+        [Command::ShowTray, Command::Run(CornerAction::StartMenu)]
+    );
+
+    // The list applies to the window that's already in front
+    desktop.foreground_changed(remote);
+    desktop.set_exclusions(ExcludeList::new());
+    assert!(!desktop.excluded());
+}
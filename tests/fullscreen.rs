@@ -0,0 +1,155 @@
+use win_gnome::desktop::Desktop;
+use win_gnome::engine::ShellState;
+use win_gnome::fullscreen::style::{WS_CAPTION, WS_CHILD, WS_MAXIMIZE, WS_THICKFRAME};
+use win_gnome::fullscreen::{classify, NotificationState, Verdict, WindowFacts};
+use win_gnome::monitor::Monitor;
+use win_gnome::shell::{FakeShell, FakeWindow};
+
+const PRIMARY: (i32, i32, i32, i32) = (0, 1080, 0, 1920);
+const SECONDARY: (i32, i32, i32, i32) = (0, 1440, 1920, 4480);
+/// What Windows gives a maximized window: its border hangs past the monitor edges.
+const MAXIMIZED: (i32, i32, i32, i32) = (-8, 1088, -8, 1928);
+
+fn window(pos: (i32, i32, i32, i32), monitor: (i32, i32, i32, i32), style: u32) -> WindowFacts {
+    WindowFacts {
+        pos,
+        monitor: Some(monitor),
+        style,
+        visible: true,
+        cloaked: false,
+    }
+}
+
+#[test]
+fn fixtures() {
+    use NotificationState::*;
+    let framed = WS_CAPTION | WS_THICKFRAME;
+    let fixtures = [
+        (
+            "borderless game",
+            window(PRIMARY, PRIMARY, 0),
+            AcceptsNotifications,
+            Verdict::CoversMonitor,
+        ),
+        (
+            "borderless video on the second monitor",
+            window(SECONDARY, SECONDARY, 0),
+            AcceptsNotifications,
+            Verdict::CoversMonitor,
+        ),
+        (
+            "a pixel short of the edge",
+            window((0, 1079, 1, 1920), PRIMARY, 0),
+            AcceptsNotifications,
+            Verdict::CoversMonitor,
+        ),
+        (
+            "browser in F11 mode",
+            window(MAXIMIZED, PRIMARY, WS_MAXIMIZE),
+            AcceptsNotifications,
+            Verdict::CoversMonitor,
+        ),
+        (
+            "maximized editor with the taskbar hidden",
+            window(MAXIMIZED, PRIMARY, framed | WS_MAXIMIZE),
+            AcceptsNotifications,
+            Verdict::Maximized,
+        ),
+        (
+            "maximized editor above a taskbar",
+            window((-8, 1048, -8, 1928), PRIMARY, framed | WS_MAXIMIZE),
+            AcceptsNotifications,
+            Verdict::Maximized,
+        ),
+        (
+            "window half a screen wide",
+            window((0, 1080, 0, 960), PRIMARY, framed),
+            AcceptsNotifications,
+            Verdict::Windowed,
+        ),
+        (
+            "exclusive mode game behind a small window",
+            window((100, 700, 100, 900), PRIMARY, framed),
+            D3dFullScreen,
+            Verdict::Shell(D3dFullScreen),
+        ),
+        (
+            "presentation",
+            window((0, 0, 0, 0), PRIMARY, 0),
+            PresentationMode,
+            Verdict::Shell(PresentationMode),
+        ),
+        (
+            "cloaked app on another virtual desktop",
+            WindowFacts {
+                cloaked: true,
+                ..window(PRIMARY, PRIMARY, 0)
+            },
+            AcceptsNotifications,
+            Verdict::Windowed,
+        ),
+        (
+            "hidden window",
+            WindowFacts {
+                visible: false,
+                ..window(PRIMARY, PRIMARY, 0)
+            },
+            AcceptsNotifications,
+            Verdict::Windowed,
+        ),
+        (
+            "child window",
+            window(PRIMARY, PRIMARY, WS_CHILD),
+            AcceptsNotifications,
+            Verdict::Windowed,
+        ),
+        (
+            "no monitor",
+            WindowFacts {
+                monitor: None,
+                ..window(PRIMARY, PRIMARY, 0)
+            },
+            App,
+            Verdict::Windowed,
+        ),
+    ];
+    for (name, facts, state, verdict) in fixtures.iter() {
+        assert_eq!(classify(facts, *state), *verdict, "{}", name);
+    }
+}
+
+#[test]
+fn notification_states_from_the_shell() {
+    assert_eq!(
+        NotificationState::from_quns(3),
+        NotificationState::D3dFullScreen
+    );
+    assert_eq!(NotificationState::from_quns(2), NotificationState::Busy);
+    assert_eq!(
+        NotificationState::from_quns(0),
+        NotificationState::AcceptsNotifications
+    );
+    assert!(NotificationState::PresentationMode.is_full_screen());
+    assert!(!NotificationState::QuietTime.is_full_screen());
+}
+
+#[test]
+fn desktop_checks_the_monitor_the_window_is_on() {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    shell.add_monitor(Monitor::new(0, 1440, 1920, 4480));
+    let video = shell.add(FakeWindow::new("mpv", "video").pos(0, 1440, 1920, 4480));
+    let editor = shell.add(
+        FakeWindow::new("Notepad", "notes.txt")
+            .pos(-8, 1088, -8, 1928)
+            .style(WS_CAPTION | WS_THICKFRAME | WS_MAXIMIZE),
+    );
+    let mut desktop = Desktop::new(shell, 100).unwrap();
+
+    desktop.foreground_changed(video);
+    assert!(ShellState::full_screen_program(&mut desktop));
+    desktop.foreground_changed(editor);
+    assert!(!ShellState::full_screen_program(&mut desktop));
+
+    desktop.backend.notification_state = NotificationState::D3dFullScreen;
+    assert!(ShellState::full_screen_program(&mut desktop));
+}
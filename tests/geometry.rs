@@ -0,0 +1,168 @@
+use win_gnome::desktop::Desktop;
+use win_gnome::dpi::Scale;
+use win_gnome::geometry::{Corner, CornerSize, TrayGeometry, TrayOrientation};
+use win_gnome::region::Shape;
+use win_gnome::shell::FakeShell;
+
+fn hot((width, height): (i32, i32), size: CornerSize, scale: Scale) -> (i32, i32) {
+    let geometry = TrayGeometry::new(TrayOrientation::Bottom, (width, height), (48, 40), 100)
+        .with_scale(scale)
+        .with_corner_size(size);
+    (geometry.hot_width, geometry.hot_height)
+}
+
+#[test]
+fn parses_corner_sizes() {
+    let sizes = [
+        ("32px", CornerSize::Pixels(32)),
+        (" 32 px ", CornerSize::Pixels(32)),
+        ("150%", CornerSize::Button(150)),
+        ("50 %", CornerSize::Button(50)),
+        ("2% screen", CornerSize::Screen(2)),
+        ("2%screen", CornerSize::Screen(2)),
+    ];
+    for (text, size) in sizes.iter() {
+        assert_eq!(CornerSize::parse(text), Ok(*size), "{}", text);
+    }
+    for text in ["", "32", "px", "%", "abc%", "2 screen", "1.5%", "-2px"].iter() {
+        assert!(CornerSize::parse(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn corner_size_limits() {
+    assert_eq!(CornerSize::parse("500px"), Ok(CornerSize::Pixels(500)));
+    assert_eq!(CornerSize::parse("500%"), Ok(CornerSize::Button(500)));
+    assert_eq!(CornerSize::parse("50% screen"), Ok(CornerSize::Screen(50)));
+    assert_eq!(
+        CornerSize::parse("0px"),
+        Err("Corner size must be between 1px and 500px")
+    );
+    assert_eq!(
+        CornerSize::parse("501%"),
+        Err("Corner size must be between 1% and 500% of the start button")
+    );
+    assert_eq!(
+        CornerSize::parse("51% screen"),
+        Err("Corner size must be between 1% and 50% of the screen")
+    );
+}
+
+#[test]
+fn corner_sizes_round_trip_as_text() {
+    for size in [
+        CornerSize::Pixels(24),
+        CornerSize::Button(150),
+        CornerSize::Screen(3),
+    ]
+    .iter()
+    {
+        assert_eq!(CornerSize::parse(&size.to_string()), Ok(*size));
+        let json = serde_json::to_string(size).unwrap();
+        assert_eq!(serde_json::from_str::<CornerSize>(&json).unwrap(), *size);
+    }
+    assert_eq!(CornerSize::Screen(3).to_string(), "3% screen");
+    assert!(serde_json::from_str::<CornerSize>("\"0%\"").is_err());
+}
+
+#[test]
+fn resolves_corner_sizes() {
+    let screen = (1920, 1080);
+    let normal = Scale::default();
+    assert_eq!(hot(screen, CornerSize::Button(100), normal), (48, 40));
+    assert_eq!(hot(screen, CornerSize::Button(50), normal), (24, 20));
+    // Bigger than the start button
+    assert_eq!(hot(screen, CornerSize::Button(250), normal), (120, 100));
+    // Pixels and percents of the screen are square
+    assert_eq!(hot(screen, CornerSize::Pixels(32), normal), (32, 32));
+    assert_eq!(hot(screen, CornerSize::Screen(5), normal), (54, 54));
+    assert_eq!(hot((1080, 1920), CornerSize::Screen(5), normal), (54, 54));
+    // Never more than the screen
+    assert_eq!(hot((400, 300), CornerSize::Pixels(500), normal), (400, 300));
+    assert_eq!(hot((100, 100), CornerSize::Button(500), normal), (100, 100));
+}
+
+#[test]
+fn pixels_follow_the_monitor_scale() {
+    let screen = (2560, 1440);
+    let scaled = Scale::new(144);
+    assert_eq!(hot(screen, CornerSize::Pixels(32), scaled), (48, 48));
+    // The start button and screen are measured in physical pixels already
+    assert_eq!(hot(screen, CornerSize::Button(100), scaled), (48, 40));
+    assert_eq!(hot(screen, CornerSize::Screen(5), scaled), (72, 72));
+
+    // Moving a corner to a monitor at another scale sizes it again
+    let geometry = TrayGeometry::new(TrayOrientation::Bottom, screen, (48, 40), 100)
+        .with_corner_size(CornerSize::Pixels(32));
+    assert_eq!((geometry.hot_width, geometry.hot_height), (32, 32));
+    let geometry = geometry.with_scale(scaled);
+    assert_eq!((geometry.hot_width, geometry.hot_height), (48, 48));
+}
+
+#[test]
+fn other_corners_size_in_pixels() {
+    let geometry = TrayGeometry::new(TrayOrientation::Bottom, (1920, 1080), (48, 40), 100);
+    let size = CornerSize::Pixels(64);
+    // Bigger than the start button both ways
+    assert!(geometry.is_in_sized(Corner::TopRight, Shape::Rect, size, 1857, 63));
+    assert!(!geometry.is_in_sized(Corner::TopRight, Shape::Rect, size, 1856, 0));
+    assert!(!geometry.is_in_sized(Corner::TopRight, Shape::Rect, size, 1919, 64));
+    assert!(geometry.is_in_sized(Corner::TopRight, Shape::Radius, size, 1880, 20));
+    assert!(!geometry.is_in_sized(Corner::TopRight, Shape::Radius, size, 1860, 60));
+
+    // Grows with the monitor's scale like the start corner
+    let scaled = geometry.with_scale(Scale::new(144));
+    assert!(scaled.is_in_sized(Corner::TopRight, Shape::Rect, size, 1825, 95));
+    assert!(!scaled.is_in_sized(Corner::TopRight, Shape::Rect, size, 1824, 95));
+
+    // Without a size of their own, corners are a percent of the global one
+    let geometry = geometry.with_corner_size(size);
+    assert!(geometry.is_in_corner(Corner::BottomRight, 100, 1857, 1017));
+    assert!(!geometry.is_in_corner(Corner::BottomRight, 100, 1856, 1079));
+    assert!(geometry.is_in_corner(Corner::BottomRight, 50, 1889, 1049));
+    assert!(!geometry.is_in_corner(Corner::BottomRight, 50, 1888, 1079));
+}
+
+#[test]
+fn sensitivity_is_a_percent_of_the_button() {
+    let geometry = TrayGeometry::new(TrayOrientation::Bottom, (1920, 1080), (48, 40), 60);
+    assert_eq!(geometry.corner_size, CornerSize::Button(60));
+    assert_eq!(geometry.corner_size.button_percent(), Some(60));
+    assert_eq!(CornerSize::Pixels(60).button_percent(), None);
+    assert_eq!(
+        geometry.with_corner_size(CornerSize::Button(60)),
+        geometry.with_sensitivity(60)
+    );
+}
+
+#[test]
+fn desktop_resizes_every_corner() {
+    let mut desktop = Desktop::new(FakeShell::windows10(1920, 1080), 100).unwrap();
+    let start = (
+        desktop.tray.geometry.start_width,
+        desktop.tray.geometry.start_height,
+    );
+    desktop.set_corner_size(CornerSize::Pixels(10));
+    assert_eq!(desktop.corner_size, CornerSize::Pixels(10));
+    for geometry in desktop.screens.iter().chain(Some(&desktop.tray.geometry)) {
+        assert_eq!((geometry.hot_width, geometry.hot_height), (10, 10));
+    }
+
+    // A taskbar switched to small icons keeps a corner sized in pixels
+    desktop.refresh().unwrap();
+    assert_eq!(
+        (
+            desktop.tray.geometry.hot_width,
+            desktop.tray.geometry.hot_height
+        ),
+        (10, 10)
+    );
+    desktop.set_sensitivity(100);
+    assert_eq!(
+        (
+            desktop.tray.geometry.hot_width,
+            desktop.tray.geometry.hot_height
+        ),
+        start
+    );
+}
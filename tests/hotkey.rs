@@ -0,0 +1,116 @@
+use win_gnome::config::Config;
+use win_gnome::control::Request;
+use win_gnome::hotkey::{
+    modifiers, taken_message, unknown_message, Hotkey, HotkeyCommand, HotkeyMap, Unbound,
+};
+use win_gnome::shell::vk;
+
+#[test]
+fn parses_combinations() {
+    assert_eq!(
+        Hotkey::parse("Ctrl+Alt+H").unwrap(),
+        Hotkey::new(modifiers::CONTROL | modifiers::ALT, b'H')
+    );
+    assert_eq!(
+        Hotkey::parse("win+esc").unwrap(),
+        Hotkey::new(modifiers::WIN, vk::ESCAPE)
+    );
+    assert_eq!(Hotkey::parse("F9").unwrap(), Hotkey::new(0, vk::F1 + 8));
+
+    assert!(Hotkey::parse("Ctrl+Alt").is_err());
+    assert!(Hotkey::parse("Ctrl+A+B").is_err());
+    assert!(Hotkey::parse("Ctrl+Nope").is_err());
+}
+
+#[test]
+fn prints_modifiers_in_order() {
+    let hotkey = Hotkey::parse("Shift+Alt+Win+Ctrl+T").unwrap();
+    assert_eq!(hotkey.to_string(), "Win+Ctrl+Alt+Shift+T");
+    assert_eq!(Hotkey::parse(&hotkey.to_string()).unwrap(), hotkey);
+}
+
+#[test]
+fn reads_hotkeys_table() {
+    assert_eq!(
+        Config::parse("")
+            .unwrap()
+            .hotkeys
+            .command(Hotkey::new(modifiers::WIN, vk::ESCAPE)),
+        Some(HotkeyCommand::Toggle)
+    );
+
+    let config =
+        Config::parse("[hotkeys]\n\"Ctrl+Alt+H\" = \"toggle\"\n\"Win+F12\" = \"trigger-corner\"\n")
+            .unwrap();
+    assert_eq!(config.hotkeys.bindings.len(), 2);
+    assert_eq!(
+        config.hotkeys.command(Hotkey::parse("Win+F12").unwrap()),
+        Some(HotkeyCommand::TriggerCorner)
+    );
+    // Replaces the default rather than adding to it
+    assert_eq!(
+        config
+            .hotkeys
+            .command(Hotkey::new(modifiers::WIN, vk::ESCAPE)),
+        None
+    );
+
+    assert!(Config::parse("[hotkeys]\n\"Ctrl+Alt\" = \"toggle\"\n").is_err());
+    assert!(Config::parse("[hotkeys]\n\"Ctrl+H\" = \"launch\"\n").is_err());
+    let duplicate = Config::parse("[hotkeys]\n\"Ctrl+H\" = \"toggle\"\n\"control+h\" = \"quit\"\n")
+        .unwrap_err();
+    assert!(
+        duplicate.contains("Ctrl+H is bound to both"),
+        "{}",
+        duplicate
+    );
+}
+
+#[test]
+fn commands_match_requests() {
+    assert_eq!(HotkeyCommand::Toggle.request(), Some(Request::Toggle));
+    assert_eq!(HotkeyCommand::Quit.request(), Some(Request::Quit));
+    assert_eq!(HotkeyCommand::PeekTaskbar.request(), None);
+
+    let mut map = HotkeyMap::new();
+    map.bind(Hotkey::parse("Win+F12").unwrap(), HotkeyCommand::Reload)
+        .unwrap();
+    assert!(map
+        .bind(Hotkey::parse("Win+F12").unwrap(), HotkeyCommand::Quit)
+        .is_err());
+}
+
+#[test]
+fn reports_taken_combinations() {
+    assert_eq!(taken_message(&[]), None);
+    let toggle = (Hotkey::parse("Win+Esc").unwrap(), HotkeyCommand::Toggle);
+    let quit = (Hotkey::parse("Ctrl+Q").unwrap(), HotkeyCommand::Quit);
+    assert_eq!(
+        taken_message(&[toggle]).unwrap(),
+        "Win+Esc (toggle) is already taken by another program, pick another combination in [hotkeys]"
+    );
+    assert!(taken_message(&[toggle, quit])
+        .unwrap()
+        .starts_with("Win+Esc (toggle), Ctrl+Q (quit) are already taken"));
+}
+
+#[test]
+fn missing_keys_are_not_blamed_on_other_programs() {
+    let toggle = (Hotkey::parse("Win+Esc").unwrap(), HotkeyCommand::Toggle);
+    let quit = (Hotkey::parse("Ctrl+F24").unwrap(), HotkeyCommand::Quit);
+    assert_eq!(unknown_message(&[]), None);
+    assert_eq!(
+        unknown_message(&[quit]).unwrap(),
+        "Ctrl+F24 (quit) uses a key this keyboard doesn't have, pick another combination in [hotkeys]"
+    );
+
+    let mut unbound = Unbound::new();
+    assert_eq!(unbound.message(), None);
+    unbound.unknown.push(quit);
+    assert!(!unbound.message().unwrap().contains("taken"));
+    unbound.taken.push(toggle);
+    let message = unbound.message().unwrap();
+    let lines: Vec<&str> = message.lines().collect();
+    assert!(lines[0].starts_with("Win+Esc (toggle) is already taken"));
+    assert!(lines[1].starts_with("Ctrl+F24 (quit) uses a key"));
+}
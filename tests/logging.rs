@@ -0,0 +1,111 @@
+mod common;
+
+use common::temp_dir;
+use log::{Level, LevelFilter, Log, Record};
+use std::fs;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use win_gnome::logging::{format_time, Filter, Logger, RotatingFile};
+
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn filters_pick_the_longest_module() {
+    let filter = Filter::parse("warn, win_gnome=info ,win_gnome::desktop=trace").unwrap();
+    assert_eq!(filter.default, LevelFilter::Warn);
+    assert_eq!(filter.level_for("win_gnome::desktop"), LevelFilter::Trace);
+    assert_eq!(
+        filter.level_for("win_gnome::desktop::tray"),
+        LevelFilter::Trace
+    );
+    assert_eq!(filter.level_for("win_gnome::engine"), LevelFilter::Info);
+    // Only whole path segments match
+    assert_eq!(filter.level_for("win_gnome_extra"), LevelFilter::Warn);
+    assert_eq!(filter.level_for("x11rb"), LevelFilter::Warn);
+    assert_eq!(filter.max_level(), LevelFilter::Trace);
+
+    assert_eq!(Filter::parse("").unwrap(), Filter::default());
+    assert_eq!(Filter::parse("DEBUG").unwrap().default, LevelFilter::Debug);
+    assert!(Filter::parse("loud").is_err());
+    assert!(Filter::parse("win_gnome=loud").is_err());
+    assert!(Filter::parse("=debug").is_err());
+}
+
+#[test]
+fn formats_utc_time() {
+    assert_eq!(format_time(Duration::ZERO), "1970-01-01 00:00:00.000");
+    assert_eq!(
+        format_time(Duration::from_millis(951_782_400_250)),
+        "2000-02-29 00:00:00.250"
+    );
+    assert_eq!(
+        format_time(Duration::from_secs(1_792_321_199)),
+        "2026-10-18 10:59:59.000"
+    );
+}
+
+#[test]
+fn logger_filters_by_target() {
+    let buffer = Buffer::default();
+    let logger = Logger::new(
+        Filter::parse("warn,win_gnome::desktop=debug").unwrap(),
+        Box::new(buffer.clone()),
+    );
+    let log = |level, target, message| {
+        logger.log(
+            &Record::builder()
+                .level(level)
+                .target(target)
+                .args(format_args!("{}", message))
+                .build(),
+        )
+    };
+    log(Level::Debug, "win_gnome::desktop", "looked at a window");
+    log(Level::Debug, "win_gnome::engine", "dropped");
+    log(Level::Warn, "win_gnome::engine", "kept");
+
+    let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with(" DEBUG win_gnome::desktop: looked at a window"));
+    assert!(lines[1].ends_with(" WARN  win_gnome::engine: kept"));
+}
+
+#[test]
+fn rotates_once_full() {
+    let path = temp_dir("log-rotate").join("win-gnome.log");
+    let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+    for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+        file.write_all(line.as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+    assert_eq!(fs::read_to_string(file.rotated_path(1)).unwrap(), "third\n");
+    assert_eq!(
+        fs::read_to_string(file.rotated_path(2)).unwrap(),
+        "second\n"
+    );
+    assert!(!file.rotated_path(3).exists());
+
+    // Reopening appends and counts what is already there
+    drop(file);
+    let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+    file.write_all(b"fifth\n").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fifth\n");
+    assert_eq!(
+        fs::read_to_string(file.rotated_path(1)).unwrap(),
+        "fourth\n"
+    );
+}
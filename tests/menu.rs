@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+use win_gnome::control::Request;
+use win_gnome::engine::CornerAction;
+use win_gnome::menu::{self, MenuAction, MenuCommand, MenuEntry, MenuState};
+
+fn state() -> MenuState {
+    MenuState {
+        enabled: true,
+        corner_action: CornerAction::DesktopSelector,
+        sensitivity: 50,
+        config_path: Some(PathBuf::from("config.toml")),
+        log_path: None,
+    }
+}
+
+fn checked(entries: &[MenuEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .flat_map(MenuEntry::items)
+        .filter(|item| item.checked)
+        .map(|item| item.label.clone())
+        .collect()
+}
+
+#[test]
+fn menu_shows_the_current_state() {
+    let entries = menu::build(&state());
+    assert_eq!(
+        checked(&entries),
+        vec!["Enabled", "Desktop selector", "50%"]
+    );
+
+    let disabled = MenuState {
+        enabled: false,
+        corner_action: CornerAction::Launch {
+            program: "wt.exe".to_string(),
+            args: vec![],
+        },
+        sensitivity: 30,
+        ..state()
+    };
+    assert!(checked(&menu::build(&disabled)).is_empty());
+    assert_eq!(menu::tooltip(false), "WinGnome (disabled)");
+
+    let grayed: Vec<MenuCommand> = entries
+        .iter()
+        .flat_map(MenuEntry::items)
+        .filter(|item| item.grayed)
+        .map(|item| item.command.clone())
+        .collect();
+    assert_eq!(grayed, vec![MenuCommand::ViewLog]);
+}
+
+#[test]
+fn every_item_maps_back_from_its_id() {
+    let entries = menu::build(&state());
+    let items: Vec<_> = entries.iter().flat_map(MenuEntry::items).collect();
+    assert_eq!(items.len(), 13);
+    let mut ids: Vec<u32> = items.iter().map(|item| item.command.id()).collect();
+    for item in &items {
+        assert_ne!(item.command.id(), 0);
+        assert_eq!(
+            MenuCommand::from_id(item.command.id()),
+            Some(item.command.clone())
+        );
+    }
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), items.len());
+
+    // Dismissing the menu gives 0
+    assert_eq!(MenuCommand::from_id(0), None);
+    assert_eq!(MenuCommand::from_id(199), None);
+    assert_eq!(MenuCommand::from_id(230), None);
+}
+
+#[test]
+fn commands_map_to_actions() {
+    let state = state();
+    assert_eq!(
+        MenuCommand::Toggle.action(&state),
+        Some(MenuAction::Request(Request::Toggle))
+    );
+    assert_eq!(
+        MenuCommand::Quit.action(&state),
+        Some(MenuAction::Request(Request::Quit))
+    );
+    assert_eq!(
+        MenuCommand::CornerAction(CornerAction::Lock).action(&state),
+        Some(MenuAction::SetCornerAction(CornerAction::Lock))
+    );
+    assert_eq!(
+        MenuCommand::Sensitivity(75).action(&state),
+        Some(MenuAction::SetSensitivity(75))
+    );
+    match MenuCommand::OpenConfig.action(&state) {
+        Some(MenuAction::Run(CornerAction::Launch { args, .. })) => {
+            assert_eq!(args, vec!["config.toml".to_string()])
+        }
+        other => panic!("Expected to launch an editor, got {:?}", other),
+    }
+    // No log file yet
+    assert_eq!(MenuCommand::ViewLog.action(&state), None);
+}
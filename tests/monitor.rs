@@ -0,0 +1,66 @@
+use win_gnome::monitor::{Layout, Monitor};
+
+/// A 1920x1080 primary with a 2560x1440 monitor to its left, bottoms aligned,
+/// and a portrait 1080x1920 monitor above it.
+fn layout() -> Layout {
+    Layout::new(vec![
+        Monitor::new(0, 1080, 0, 1920).primary(),
+        Monitor::new(-360, 1080, -2560, 0).dpi(144),
+        Monitor::new(-1920, 0, 0, 1080),
+    ])
+}
+
+#[test]
+fn bounds_cover_every_monitor() {
+    assert_eq!(layout().bounds(), (-1920, 1080, -2560, 1920));
+    assert_eq!(Layout::single(1920, 1080).bounds(), (0, 1080, 0, 1920));
+    assert_eq!(Layout::default().bounds(), (0, 0, 0, 0));
+}
+
+#[test]
+fn finds_the_primary_monitor() {
+    assert_eq!(layout().primary(), Some(0));
+    assert_eq!(
+        Layout::new(vec![Monitor::new(0, 10, 0, 10)]).primary(),
+        Some(0)
+    );
+    assert_eq!(Layout::default().primary(), None);
+}
+
+#[test]
+fn points_map_to_their_monitor() {
+    let layout = layout();
+
+    assert_eq!(layout.to_local(0, 0), Some((0, 0, 0)));
+    assert_eq!(layout.to_local(1919, 1079), Some((0, 1919, 1079)));
+    assert_eq!(layout.to_local(-1, 1079), Some((1, 2559, 1439)));
+    assert_eq!(layout.to_local(-2560, -360), Some((1, 0, 0)));
+    assert_eq!(layout.to_local(1079, -1), Some((2, 1079, 1919)));
+    assert!(layout.monitors[1].contains(-2560, 0));
+    assert!(!layout.monitors[1].contains(0, 0));
+}
+
+#[test]
+fn off_screen_points_snap_to_the_closest_monitor() {
+    let layout = layout();
+
+    assert_eq!(layout.monitor_at(-3000, 500), Some(1));
+    assert_eq!(layout.monitor_at(1500, -5), Some(0));
+    assert_eq!(layout.monitor_at(500, -2000), Some(2));
+    assert_eq!(Layout::default().monitor_at(0, 0), None);
+}
+
+#[test]
+fn windows_belong_to_the_monitor_they_mostly_cover() {
+    let layout = layout();
+
+    assert_eq!(layout.monitor_for((1040, 1080, 0, 1920)), Some(0));
+    assert_eq!(layout.monitor_for((1040, 1080, -2560, 0)), Some(1));
+    // Straddling two monitors, more of it is on the left one.
+    assert_eq!(layout.monitor_for((0, 100, -1000, 500)), Some(1));
+    assert_eq!(layout.monitor_for((5000, 5100, 5000, 5100)), Some(0));
+    assert_eq!(
+        layout.monitors[1].to_local_pos((1040, 1080, -2560, 0)),
+        (1400, 1440, 0, 2560)
+    );
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+use win_gnome::corners::CornerSettings;
+use win_gnome::desktop::Desktop;
+use win_gnome::engine::{Command, CornerAction, Event, HotCornerEngine, TimerKind};
+use win_gnome::geometry::Corner;
+use win_gnome::record::{replay, Action, Captured, Entry, Record, Recorder, WindowTree};
+use win_gnome::shell::{FakeShell, ShellBackend};
+
+fn windows10() -> Desktop<FakeShell> {
+    Desktop::new(FakeShell::windows10(1920, 1080), 100).unwrap()
+}
+
+fn recording(desktop: &Desktop<FakeShell>, entries: Vec<(u64, Entry)>) -> Vec<u8> {
+    let mut out = vec![];
+    let mut recorder = Recorder::new(&mut out);
+    recorder
+        .start(desktop, &HotCornerEngine::default())
+        .unwrap();
+    for (time, entry) in entries {
+        recorder.write(&Record { time, entry }).unwrap();
+    }
+    out
+}
+
+fn action(time: u64, event: Event, command: Command) -> Action {
+    Action {
+        time,
+        event,
+        command,
+    }
+}
+
+#[test]
+fn replays_corner_and_timer() {
+    let desktop = windows10();
+    let input = recording(
+        &desktop,
+        vec![
+            (10, Entry::Move { x: 0, y: 1079 }),
+            (50, Entry::Move { x: 0, y: 1079 }),
+        ],
+    );
+    let corner = Event::MouseMoved { x: 0, y: 1079 };
+
+    assert_eq!(
+        replay(&input[..]).unwrap(),
+        vec![
+            action(10, corner, Command::ShowTray),
+            action(10, corner, Command::Run(CornerAction::StartMenu)),
+            action(
+                10,
+                corner,
+                Command::StartTimer(TimerKind::Debounce, Duration::from_millis(300))
+            ),
+        ]
+    );
+}
+
+#[test]
+fn replays_start_menu_closing() {
+    let desktop = windows10();
+    let shell = &desktop.backend;
+    let menu = desktop.tray.start_menu;
+    let mut open_menu = Captured::new(shell, menu);
+    open_menu.window.cloaked = false;
+    let closed_menu = Captured::new(shell, menu);
+    let shell_window = Captured::new(shell, desktop.shell_window);
+
+    let input = recording(
+        &desktop,
+        vec![
+            (
+                10,
+                Entry::Foreground {
+                    window: menu,
+                    windows: vec![open_menu],
+                },
+            ),
+            (20, Entry::Move { x: 900, y: 500 }),
+            (
+                30,
+                Entry::Foreground {
+                    window: desktop.shell_window,
+                    windows: vec![shell_window, closed_menu],
+                },
+            ),
+        ],
+    );
+
+    assert_eq!(
+        replay(&input[..]).unwrap(),
+        vec![action(
+            30,
+            Event::ForegroundChanged { menu_closed: true },
+            Command::HideTray
+        )]
+    );
+}
+
+#[test]
+fn hotkey_round_trips_through_json() {
+    let input = recording(&windows10(), vec![(5, Entry::Hotkey), (6, Entry::Hotkey)]);
+    let text = String::from_utf8(input.clone()).unwrap();
+
+    assert_eq!(text.lines().count(), 3);
+    assert_eq!(
+        text.lines().nth(1).unwrap(),
+        r#"{"time":5,"event":"hotkey"}"#
+    );
+    assert_eq!(
+        replay(&input[..]).unwrap(),
+        vec![
+            action(5, Event::Hotkey, Command::ShowTray),
+            action(6, Event::Hotkey, Command::HideTray),
+        ]
+    );
+}
+
+#[test]
+fn rejects_bad_recordings() {
+    assert!(replay(&b""[..]).is_err());
+    assert!(replay(&br#"{"time":0,"event":"hotkey"}"#[..]).is_err());
+    assert!(replay(&b"not json\n"[..]).is_err());
+
+    // A snapshot without the taskbar can't be turned back into a desktop.
+    let mut desktop = windows10();
+    desktop.tray.bar = Default::default();
+    let input = recording(&desktop, vec![]);
+    assert!(replay(&input[..]).is_err());
+}
+
+#[test]
+fn corners_round_trip_through_json() {
+    let mut engine = HotCornerEngine::default();
+    engine.corners.set(
+        Corner::TopRight,
+        CornerSettings {
+            action: CornerAction::DesktopSelector,
+            ..CornerSettings::default()
+        },
+    );
+    let mut input = vec![];
+    let mut recorder = Recorder::new(&mut input);
+    recorder.start(&windows10(), &engine).unwrap();
+    recorder
+        .write(&Record {
+            time: 10,
+            entry: Entry::Move { x: 1919, y: 0 },
+        })
+        .unwrap();
+
+    let corner = Event::MouseMoved { x: 1919, y: 0 };
+    assert_eq!(
+        replay(&input[..]).unwrap()[..2],
+        [
+            action(10, corner, Command::ShowTray),
+            action(10, corner, Command::Run(CornerAction::DesktopSelector)),
+        ]
+    );
+}
+
+#[test]
+fn window_tree_round_trips_through_json() {
+    let shell = FakeShell::windows11(1920, 1080);
+    let tree = WindowTree::capture(&shell);
+    assert_eq!(tree.windows.len(), shell.windows().count());
+    assert_eq!(tree.build, Some(22631));
+
+    let json = serde_json::to_string(&tree).unwrap();
+    let copy = serde_json::from_str::<WindowTree>(&json)
+        .unwrap()
+        .into_shell()
+        .unwrap();
+    let original = Desktop::new(shell, 100).unwrap();
+    let copy = Desktop::new(copy, 100).unwrap();
+    assert_eq!(copy.tray.version, original.tray.version);
+    assert_eq!(
+        copy.backend.title(copy.tray.search),
+        original.backend.title(original.tray.search)
+    );
+    assert_eq!(copy.backend.foreground_window(), copy.shell_parent);
+}
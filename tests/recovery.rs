@@ -0,0 +1,150 @@
+mod common;
+
+use common::temp_dir;
+use win_gnome::autohide::{AppBarState, AutoHide};
+use win_gnome::engine::{Command, TimerKind};
+use win_gnome::ewmh::EwmhDesktop;
+use win_gnome::monitor::Monitor;
+use win_gnome::recovery::{self, DirtyMarker};
+use win_gnome::rules::Rules;
+use win_gnome::shell::{DockBackend, FakeShell, FakeWindow, ShellBackend, WindowId};
+
+fn hidden_shell() -> FakeShell {
+    let mut shell = FakeShell::windows10(1920, 1080);
+    shell.add_monitor(Monitor::new(0, 1080, 1920, 3840));
+    for bar in taskbars(&shell) {
+        shell.set_visible(bar, false);
+    }
+    shell
+}
+
+fn taskbars(shell: &FakeShell) -> Vec<WindowId> {
+    recovery::find_taskbars(shell, &Rules::builtin())
+}
+
+fn visible_taskbars(shell: &FakeShell) -> usize {
+    taskbars(shell)
+        .into_iter()
+        .filter(|bar| shell.is_visible(*bar))
+        .count()
+}
+
+#[test]
+fn shows_every_taskbar() {
+    let mut shell = hidden_shell();
+    assert_eq!(visible_taskbars(&shell), 0);
+    let bars = taskbars(&shell);
+    assert_eq!(shell.class_name(bars[0]).as_deref(), Some("Shell_TrayWnd"));
+    assert_eq!(recovery::show_taskbars(&mut shell, &bars), 2);
+    assert_eq!(visible_taskbars(&shell), 2);
+}
+
+#[test]
+fn taskbars_are_found_by_the_rules() {
+    let mut shell = FakeShell::new(1920, 1080);
+    let bar = shell.add(FakeWindow::new("StartAllBack_TaskbarWnd", ""));
+    shell.set_visible(bar, false);
+    let user = Rules::parse(
+        "version = 1\n[[rule]]\nrole = \"taskbar\"\nclass = \"StartAllBack_TaskbarWnd\"",
+    )
+    .unwrap();
+    let rules = Rules::builtin().overridden_by(user).unwrap();
+
+    assert!(recovery::find_taskbars(&shell, &Rules::builtin()).is_empty());
+    let bars = recovery::find_taskbars(&shell, &rules);
+    assert_eq!(bars, vec![bar]);
+    recovery::show_taskbars(&mut shell, &bars);
+    assert!(shell.is_visible(bar));
+}
+
+#[test]
+fn marker_follows_taskbar() {
+    let path = temp_dir("marker").join("taskbar-hidden");
+    let mut marker = DirtyMarker::open(Some(path.clone()));
+    assert!(!marker.is_dirty());
+
+    marker.track(&Command::HideTray).unwrap();
+    assert!(path.exists());
+    marker
+        .track(&Command::StartTimer(
+            TimerKind::Debounce,
+            Default::default(),
+        ))
+        .unwrap();
+    assert!(path.exists());
+    marker.track(&Command::ShowTray).unwrap();
+    assert!(!path.exists());
+    marker.track(&Command::ShowTray).unwrap();
+
+    // Without a path only the state is kept
+    let mut marker = DirtyMarker::new();
+    marker.track(&Command::HideTray).unwrap();
+    assert!(marker.is_dirty());
+}
+
+#[test]
+fn next_run_recovers_hidden_taskbar() {
+    let path = temp_dir("recover").join("taskbar-hidden");
+    let mut crashed = DirtyMarker::open(Some(path.clone()));
+    crashed.track(&Command::HideTray).unwrap();
+    let mut shell = hidden_shell();
+
+    let mut marker = DirtyMarker::open(Some(path.clone()));
+    assert!(marker.is_dirty());
+    let bars = taskbars(&shell);
+    assert!(marker.recover(&mut shell, &bars).unwrap());
+    assert_eq!(visible_taskbars(&shell), 2);
+    assert!(!path.exists());
+    assert!(!marker.recover(&mut shell, &bars).unwrap());
+}
+
+#[test]
+fn restore_puts_everything_back() {
+    let dir = temp_dir("restore");
+    let marker_path = dir.join("taskbar-hidden");
+    let auto_hide_path = dir.join("auto-hide.toml");
+    let mut shell = hidden_shell();
+    let bars = taskbars(&shell);
+    AutoHide::open(Some(auto_hide_path.clone()))
+        .apply(true, &mut shell, bars[0])
+        .unwrap();
+    DirtyMarker::open(Some(marker_path.clone()))
+        .track(&Command::HideTray)
+        .unwrap();
+
+    recovery::restore(&mut shell, &bars, Some(&marker_path), Some(&auto_hide_path));
+    assert_eq!(visible_taskbars(&shell), 2);
+    assert_eq!(shell.app_bar, Some(AppBarState::new(false, true)));
+    assert!(!marker_path.exists());
+    assert!(!auto_hide_path.exists());
+}
+
+#[test]
+fn next_run_gives_docks_their_struts_back() {
+    const STRUT: [u32; 12] = [0, 0, 0, 30, 0, 0, 0, 0, 0, 0, 0, 1919];
+    let path = temp_dir("docks").join("taskbar-hidden");
+    let mut shell = FakeShell::new(1920, 1080);
+    let panel = shell.add(
+        FakeWindow::new("Polybar", "polybar-main")
+            .pos(1050, 1080, 0, 1920)
+            .dock(Some(STRUT)),
+    );
+    let closed = shell.add(FakeWindow::new("Tint2", "tint2").dock(None));
+
+    // A run hides the docks and gets killed before it can show them again
+    let mut desktop = EwmhDesktop::new(shell, 100);
+    let mut crashed = DirtyMarker::open(Some(path.clone()));
+    crashed.hide_docks(&desktop.hidden_docks()).unwrap();
+    desktop.hide_docks();
+    let mut shell = desktop.backend;
+    assert_eq!(shell.strut(panel), None);
+    shell.remove(closed);
+
+    let mut marker = DirtyMarker::open(Some(path.clone()));
+    assert_eq!(marker.hidden_docks().len(), 2);
+    assert!(marker.recover_docks(&mut shell).unwrap());
+    assert!(shell.is_visible(panel));
+    assert_eq!(shell.strut(panel), Some(STRUT));
+    assert!(!path.exists());
+    assert!(!marker.recover_docks(&mut shell).unwrap());
+}
@@ -0,0 +1,382 @@
+use win_gnome::config::Config;
+use win_gnome::corners::{CornerMap, CornerSettings};
+use win_gnome::dpi::Scale;
+use win_gnome::engine::{Command, CornerAction, Event, HotCornerEngine, ShellState};
+use win_gnome::geometry::{Corner, TrayGeometry, TrayOrientation};
+use win_gnome::region::{DeadZone, Regions, Shape};
+
+const SHAPES: [Shape; 3] = [Shape::Rect, Shape::Triangle, Shape::Radius];
+const SIZES: [(i32, i32); 6] = [(1, 1), (2, 3), (40, 40), (48, 40), (7, 100), (100, 7)];
+
+fn geometry(orientation: TrayOrientation) -> TrayGeometry {
+    TrayGeometry::new(orientation, (1920, 1080), (48, 40), 100)
+}
+
+fn points((width, height): (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+    (-2..width + 2).flat_map(move |dx| (-2..height + 2).map(move |dy| (dx, dy)))
+}
+
+/// The shapes worked out on the real plane, against the integer versions.
+fn reference(shape: Shape, (dx, dy): (i32, i32), (width, height): (i32, i32)) -> bool {
+    let x = f64::from(dx.max(0)) / f64::from(width);
+    let y = f64::from(dy.max(0)) / f64::from(height);
+    match shape {
+        Shape::Rect => x < 1.0 && y < 1.0,
+        Shape::Triangle => x + y < 1.0,
+        Shape::Radius => x * x + y * y < 1.0,
+    }
+}
+
+#[test]
+fn shapes_match_their_formulas() {
+    for &shape in SHAPES.iter() {
+        for &size in SIZES.iter() {
+            for point in points(size) {
+                assert_eq!(
+                    shape.contains(point, size),
+                    reference(shape, point, size),
+                    "{:?} {:?} {:?}",
+                    shape,
+                    size,
+                    point
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn shapes_nest_inside_each_other() {
+    for &size in SIZES.iter() {
+        for point in points(size) {
+            let triangle = Shape::Triangle.contains(point, size);
+            let radius = Shape::Radius.contains(point, size);
+            let rect = Shape::Rect.contains(point, size);
+            assert!(!triangle || radius, "{:?} {:?}", size, point);
+            assert!(!radius || rect, "{:?} {:?}", size, point);
+        }
+    }
+}
+
+#[test]
+fn shapes_shrink_towards_the_corner() {
+    for &shape in SHAPES.iter() {
+        for &size in SIZES.iter() {
+            // The corner itself and past the screen's edges are always in
+            assert!(shape.contains((0, 0), size));
+            assert!(shape.contains((-5, -5), size));
+            for (dx, dy) in points(size).filter(|&point| shape.contains(point, size)) {
+                assert!(
+                    shape.contains((dx - 1, dy), size),
+                    "{:?} {:?}",
+                    shape,
+                    (dx, dy)
+                );
+                assert!(
+                    shape.contains((dx, dy - 1), size),
+                    "{:?} {:?}",
+                    shape,
+                    (dx, dy)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn square_shapes_are_symmetric() {
+    for &shape in SHAPES.iter() {
+        for side in 1..50 {
+            for point in points((side, side)) {
+                assert_eq!(
+                    shape.contains(point, (side, side)),
+                    shape.contains((point.1, point.0), (side, side)),
+                    "{:?} {} {:?}",
+                    shape,
+                    side,
+                    point
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn shapes_cover_their_area() {
+    let size = (400, 300);
+    let area = |shape: Shape| {
+        points(size)
+            .filter(|&point| shape.contains(point, size))
+            .count()
+    };
+    // Points past the edges count as on them, leave them out
+    let inside = |shape: Shape| {
+        points(size)
+            .filter(|&(dx, dy)| dx >= 0 && dy >= 0 && shape.contains((dx, dy), size))
+            .count() as f64
+    };
+    assert_eq!(inside(Shape::Rect), 120_000.0);
+    assert!((inside(Shape::Triangle) / 60_000.0 - 1.0).abs() < 0.01);
+    assert!((inside(Shape::Radius) / (std::f64::consts::PI * 30_000.0) - 1.0).abs() < 0.01);
+    assert!(area(Shape::Triangle) < area(Shape::Radius));
+}
+
+#[test]
+fn empty_shapes_hold_nothing() {
+    for &shape in SHAPES.iter() {
+        for &size in [(0, 0), (0, 10), (10, 0), (-5, 10)].iter() {
+            assert!(!shape.contains((0, 0), size), "{:?} {:?}", shape, size);
+        }
+    }
+}
+
+#[test]
+fn corner_offsets_point_inwards() {
+    let geometry = geometry(TrayOrientation::Bottom);
+    let cases = [
+        (Corner::TopLeft, (0, 0), (0, 0)),
+        (Corner::TopLeft, (10, 20), (10, 20)),
+        (Corner::TopRight, (1919, 0), (1, 0)),
+        (Corner::TopRight, (1900, 20), (20, 20)),
+        (Corner::BottomLeft, (0, 1079), (0, 1)),
+        (Corner::BottomLeft, (10, 1060), (10, 20)),
+        (Corner::BottomRight, (1919, 1079), (1, 1)),
+        (Corner::BottomRight, (1920, 1085), (0, -5)),
+    ];
+    for &(corner, (x, y), offsets) in cases.iter() {
+        assert_eq!(
+            geometry.corner_offsets(corner, x, y),
+            offsets,
+            "{:?}",
+            corner
+        );
+    }
+}
+
+#[test]
+fn rect_is_the_old_hot_corner() {
+    for &orientation in [
+        TrayOrientation::Bottom,
+        TrayOrientation::Top,
+        TrayOrientation::Left,
+        TrayOrientation::Right,
+    ]
+    .iter()
+    {
+        let geometry = geometry(orientation);
+        for x in (-3..60).chain(1860..1923) {
+            for y in (-3..50).chain(1030..1083) {
+                assert_eq!(
+                    geometry.is_hot_shape(Shape::Rect, x, y),
+                    geometry.is_hot_corner(x, y),
+                    "{:?} {:?}",
+                    orientation,
+                    (x, y)
+                );
+                for &corner in Corner::ALL.iter() {
+                    assert_eq!(
+                        geometry.is_in_shape(corner, Shape::Rect, 50, x, y),
+                        geometry.is_in_corner(corner, 50, x, y)
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn shaped_corners_skip_the_inner_corner() {
+    for &corner in Corner::ALL[..4].iter() {
+        let geometry = geometry(TrayOrientation::Bottom);
+        // Near the far side of each corner, inside the rectangle but outside the curve
+        let (x, y) = match corner {
+            Corner::TopLeft => (40, 30),
+            Corner::TopRight => (1880, 30),
+            Corner::BottomLeft => (40, 1050),
+            _ => (1880, 1050),
+        };
+        assert!(geometry.is_in_shape(corner, Shape::Rect, 100, x, y));
+        assert!(!geometry.is_in_shape(corner, Shape::Triangle, 100, x, y));
+        assert!(!geometry.is_in_shape(corner, Shape::Radius, 100, x, y));
+    }
+    // Edges have no shape
+    let geometry = geometry(TrayOrientation::Bottom);
+    assert!(geometry.is_in_shape(Corner::TopEdge, Shape::Radius, 100, 960, 0));
+}
+
+#[test]
+fn sliding_along_the_bottom_edge() {
+    let geometry = geometry(TrayOrientation::Bottom);
+    let hits = |shape: Shape, y: i32| {
+        (0..200)
+            .filter(|&x| geometry.is_hot_shape(shape, x, y))
+            .count()
+    };
+    // Just above the bottom row the corner is narrower, the higher the more so
+    for y in 1041..1079 {
+        assert!(hits(Shape::Triangle, y) <= hits(Shape::Radius, y));
+        assert!(hits(Shape::Radius, y) <= hits(Shape::Rect, y));
+    }
+    assert_eq!(hits(Shape::Rect, 1050), 48);
+    assert_eq!(hits(Shape::Triangle, 1050), 12);
+    assert_eq!(hits(Shape::Radius, 1050), 32);
+}
+
+#[test]
+fn dead_zones_count_from_either_side() {
+    let screen = (1920, 1080);
+    let zone = DeadZone::new(-400, -48, 400, 48);
+    assert!(zone.contains(screen, 1520, 1032));
+    assert!(zone.contains(screen, 1919, 1079));
+    assert!(!zone.contains(screen, 1519, 1079));
+    assert!(!zone.contains(screen, 1919, 1031));
+    // Pushed past the edge of the screen
+    assert!(zone.contains(screen, 1930, 1090));
+
+    let zone = DeadZone::new(0, 0, 10, 10);
+    for x in -2..12 {
+        for y in -2..12 {
+            assert_eq!(
+                zone.contains(screen, x, y),
+                x < 10 && y < 10,
+                "{:?}",
+                (x, y)
+            );
+        }
+    }
+    assert!(DeadZone::new(0, 0, 0, 10).validate().is_err());
+    assert!(DeadZone::new(0, 0, 10, -1).validate().is_err());
+    assert!(!DeadZone::new(0, 0, 0, 10).contains(screen, 0, 0));
+}
+
+#[test]
+fn dead_zones_follow_the_monitor_scale() {
+    let regions = Regions {
+        dead_zones: vec![DeadZone::new(0, -20, 40, 20)],
+        ..Regions::new()
+    };
+    let normal = geometry(TrayOrientation::Bottom);
+    let scaled = normal.with_scale(Scale::new(144));
+    assert!(regions.is_dead(&normal, 39, 1060));
+    assert!(!regions.is_dead(&normal, 40, 1060));
+    assert!(!regions.is_dead(&normal, 0, 1059));
+    assert!(regions.is_dead(&scaled, 59, 1050));
+    assert!(!regions.is_dead(&scaled, 60, 1050));
+}
+
+#[test]
+fn regions_pick_shapes_per_corner() {
+    let regions = Regions {
+        shape: Shape::Radius,
+        top_right: Some(Shape::Triangle),
+        bottom_left: Some(Shape::Rect),
+        ..Regions::new()
+    };
+    assert_eq!(regions.shape_of(Corner::TopLeft), Shape::Radius);
+    assert_eq!(regions.shape_of(Corner::TopRight), Shape::Triangle);
+    assert_eq!(regions.shape_of(Corner::BottomLeft), Shape::Rect);
+    assert_eq!(regions.shape_of(Corner::BottomRight), Shape::Radius);
+    for &edge in Corner::ALL[4..].iter() {
+        assert_eq!(regions.shape_of(edge), Shape::Rect);
+    }
+    assert_eq!(Regions::default().shape_of(Corner::TopLeft), Shape::Rect);
+}
+
+#[test]
+fn hits_use_shapes_and_dead_zones() {
+    let geometry = geometry(TrayOrientation::Bottom);
+    let mut corners = CornerMap::new();
+    corners.set(
+        Corner::TopRight,
+        CornerSettings {
+            action: CornerAction::DesktopSelector,
+            ..CornerSettings::default()
+        },
+    );
+    let regions = Regions {
+        shape: Shape::Triangle,
+        dead_zones: vec![DeadZone::new(-10, 0, 10, 10)],
+        ..Regions::new()
+    };
+    let hit = |regions: &Regions, x, y| {
+        corners
+            .hit_in(&geometry, regions, &CornerAction::StartMenu, x, y)
+            .map(|(corner, _)| corner)
+    };
+
+    assert_eq!(hit(&Regions::new(), 40, 1050), Some(Corner::BottomLeft));
+    assert_eq!(hit(&regions, 40, 1050), None);
+    assert_eq!(hit(&regions, 5, 1075), Some(Corner::BottomLeft));
+    assert_eq!(hit(&Regions::new(), 1919, 0), Some(Corner::TopRight));
+    assert_eq!(hit(&regions, 1919, 0), None);
+    assert_eq!(hit(&regions, 1905, 0), Some(Corner::TopRight));
+    assert_eq!(
+        corners.hit(&geometry, &CornerAction::StartMenu, 40, 1050),
+        hit(&Regions::new(), 40, 1050).map(|corner| (corner, &CornerAction::StartMenu))
+    );
+}
+
+#[test]
+fn regions_from_config() {
+    let config = Config::parse(
+        "[regions]\nshape = \"radius\"\ntop_left = \"triangle\"\n\n\
+         [[regions.dead_zone]]\nx = -400\ny = -48\nwidth = 400\nheight = 48",
+    )
+    .unwrap();
+    assert_eq!(
+        config.regions,
+        Regions {
+            shape: Shape::Radius,
+            top_left: Some(Shape::Triangle),
+            dead_zones: vec![DeadZone::new(-400, -48, 400, 48)],
+            ..Regions::new()
+        }
+    );
+    assert_eq!(Config::default().regions, Regions::new());
+
+    assert!(Config::parse("[regions]\nshape = \"circle\"").is_err());
+    assert!(Config::parse("[regions]\ntop_edge = \"radius\"").is_err());
+    assert!(Config::parse("[[regions.dead_zone]]\nx = 0\ny = 0\nwidth = 0\nheight = 5").is_err());
+    assert!(Config::parse("[[regions.dead_zone]]\nx = 0\ny = 0\nwidth = 5").is_err());
+}
+
+struct Shell(TrayGeometry);
+
+impl ShellState for Shell {
+    fn geometry(&self) -> &TrayGeometry {
+        &self.0
+    }
+    fn full_screen_program(&mut self) -> bool {
+        false
+    }
+    fn is_tray_open(&self) -> bool {
+        false
+    }
+    fn start_menu_focused(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn engine_never_fires_in_a_dead_zone() {
+    let mut shell = Shell(geometry(TrayOrientation::Bottom));
+    let mut engine = HotCornerEngine::default();
+    engine.regions = Regions {
+        shape: Shape::Radius,
+        dead_zones: vec![DeadZone::new(0, -1, 20, 1)],
+        ..Regions::new()
+    };
+
+    // Outside the curve, then along the bottom row where it's dead
+    assert_eq!(
+        engine.handle(Event::MouseMoved { x: 40, y: 1050 }, &mut shell),
+        vec![]
+    );
+    assert_eq!(
+        engine.handle(Event::MouseMoved { x: 10, y: 1079 }, &mut shell),
+        vec![]
+    );
+    assert!(engine
+        .handle(Event::MouseMoved { x: 10, y: 1070 }, &mut shell)
+        .contains(&Command::ShowTray));
+}
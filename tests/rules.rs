@@ -0,0 +1,221 @@
+mod common;
+
+use common::temp_dir;
+use std::fs;
+use win_gnome::record::WindowTree;
+use win_gnome::rules::{Context, Role, Rules, RULES_VERSION};
+use win_gnome::shell::{FakeShell, FakeWindow, ShellBackend};
+use win_gnome::taskbar::ShellVersion;
+
+fn fixture(json: &str) -> FakeShell {
+    serde_json::from_str::<WindowTree>(json)
+        .unwrap()
+        .into_shell()
+        .unwrap()
+}
+
+fn class_of(shell: &FakeShell, window: Option<win_gnome::shell::WindowId>) -> Option<String> {
+    window.and_then(|window| shell.class_name(window))
+}
+
+#[test]
+fn builtin_rules_parse() {
+    let rules = Rules::builtin();
+    assert_eq!(rules.version, RULES_VERSION);
+    assert!(rules.validate().is_ok());
+    assert!(rules.for_role(Role::StartMenu, None).next().is_none());
+    assert_eq!(
+        rules
+            .for_role(Role::StartMenu, Some(ShellVersion::Windows10))
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn builtin_rules_find_every_role_in_fixtures() {
+    let rules = Rules::builtin();
+    let cases = [
+        (
+            fixture(include_str!("fixtures/windows10-19045.json")),
+            ShellVersion::Windows10,
+            (1920, 1080),
+            "NotifyIconOverflowWindow",
+            "WorkerW",
+        ),
+        (
+            fixture(include_str!("fixtures/windows11-22631.json")),
+            ShellVersion::Windows11,
+            (2560, 1440),
+            "TopLevelWindowForOverflowXamlIsland",
+            "Progman",
+        ),
+    ];
+    for (shell, version, desktop, overflow, host) in cases.iter() {
+        let context = Context::new(Some(*version), *desktop);
+        let find = |role| class_of(shell, rules.find(shell, role, &context));
+
+        assert_eq!(find(Role::Taskbar).as_deref(), Some("Shell_TrayWnd"));
+        assert_eq!(find(Role::IconOverflow).as_deref(), Some(*overflow));
+        assert_eq!(find(Role::DesktopView).as_deref(), Some("SHELLDLL_DefView"));
+        let view = rules.find(shell, Role::DesktopView, &context).unwrap();
+        assert_eq!(class_of(shell, shell.parent(view)).as_deref(), Some(*host));
+        let search = rules.find(shell, Role::Search, &context).unwrap();
+        assert_eq!(shell.title(search).as_deref(), Some("Search"));
+        assert!(rules.is(shell, search, Role::Search, &context));
+        assert!(!rules.is(shell, search, Role::StartMenu, &context));
+    }
+}
+
+#[test]
+fn rules_only_apply_to_their_version() {
+    let rules = Rules::builtin();
+    let shell = fixture(include_str!("fixtures/windows11-22631.json"));
+    let bar = rules
+        .find(&shell, Role::Taskbar, &Context::default())
+        .unwrap();
+    let windows10 = Context::new(Some(ShellVersion::Windows10), (2560, 1440));
+    let windows11 = Context::new(Some(ShellVersion::Windows11), (2560, 1440));
+
+    // Windows 10 takes any `Start` child, Windows 11 only a visible one with a size
+    assert!(rules
+        .find_in(&shell, Role::StartButton, bar, &windows10)
+        .is_some());
+    assert!(rules
+        .find_in(&shell, Role::StartButton, bar, &windows11)
+        .is_none());
+    assert!(rules
+        .find_in(&shell, Role::XamlIsland, bar, &Context::default())
+        .is_some());
+    assert_eq!(
+        rules.find(&shell, Role::IconOverflow, &Context::default()),
+        None
+    );
+}
+
+#[test]
+fn position_constraints() {
+    let rules = Rules::builtin();
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let desktop = shell.desktop_window();
+    let menu = shell.add(
+        FakeWindow::new("Windows.UI.Core.CoreWindow", "Start")
+            .parent(desktop)
+            .pos(440, 1040, 48, 688),
+    );
+    let context = Context::new(Some(ShellVersion::Windows10), (1920, 1080));
+    // Where the start menu opens isn't known until the start button is
+    assert!(!rules.is(&shell, menu, Role::StartMenu, &context));
+    let context = Context {
+        menu_offsets: Some((None, None, Some(48), None)),
+        ..context
+    };
+    assert!(rules.is(&shell, menu, Role::StartMenu, &context));
+
+    // A WorkerW has to cover the desktop to host the icons
+    let small = shell.add(FakeWindow::new("WorkerW", "").pos(0, 100, 0, 100));
+    assert!(!rules.is(&shell, small, Role::DesktopHost, &context));
+    assert_eq!(rules.find_all(&shell, Role::DesktopHost, &context).len(), 1);
+}
+
+#[test]
+fn parents_are_checked() {
+    let rules = Rules::builtin();
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let context = Context::new(Some(ShellVersion::Windows10), (1920, 1080));
+    let stray = shell.add(FakeWindow::new("SHELLDLL_DefView", ""));
+    let view = rules.find(&shell, Role::DesktopView, &context).unwrap();
+
+    assert_ne!(view, stray);
+    assert!(rules.is(&shell, view, Role::DesktopView, &context));
+    assert!(!rules.is(&shell, stray, Role::DesktopView, &context));
+}
+
+#[test]
+fn user_rules_replace_a_role() {
+    let user = Rules::parse(
+        r#"
+        version = 1
+
+        [[rule]]
+        role = "icon_overflow"
+        class = "NewOverflowWindow"
+        "#,
+    )
+    .unwrap();
+    let rules = Rules::builtin().overridden_by(user).unwrap();
+    let mut shell = FakeShell::windows10(1920, 1080);
+    let overflow = shell.add(FakeWindow::new("NewOverflowWindow", ""));
+    let context = Context::new(Some(ShellVersion::Windows10), (1920, 1080));
+
+    assert_eq!(rules.for_role(Role::IconOverflow, None).count(), 1);
+    assert_eq!(
+        rules.find(&shell, Role::IconOverflow, &context),
+        Some(overflow)
+    );
+    // Other roles keep the built-in rules
+    assert!(rules.find(&shell, Role::Taskbar, &context).is_some());
+}
+
+#[test]
+fn user_file_is_optional() {
+    let dir = temp_dir("rules");
+    let path = dir.join("rules.toml");
+
+    assert_eq!(Rules::with_user_file(Some(&path)), Ok(Rules::builtin()));
+    assert_eq!(Rules::with_user_file(None), Ok(Rules::builtin()));
+
+    fs::write(
+        &path,
+        "version = 1\n[[rule]]\nrole = \"search\"\ntitle = \"Suche\"\n",
+    )
+    .unwrap();
+    let rules = Rules::with_user_file(Some(&path)).unwrap();
+    assert_eq!(
+        rules
+            .for_role(Role::Search, None)
+            .next()
+            .unwrap()
+            .title
+            .as_deref(),
+        Some("Suche")
+    );
+
+    fs::write(&path, "version = 1\n[[rule]]\nrole = \"search\"\n").unwrap();
+    assert!(Rules::with_user_file(Some(&path)).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn invalid_rules() {
+    let errors = [
+        (
+            "version = 2",
+            "Rules version 2 is not one this win-gnome understands, it reads up to 1",
+        ),
+        (
+            "version = 1\n[[rule]]\nrole = \"taskbar\"\nshell = \"windows11\"",
+            "A taskbar rule needs a class or title",
+        ),
+        (
+            "version = 1\n[[rule]]\nrole = \"taskbar\"\nclass = \"A\"\nparent = \"search\"\n\
+             [[rule]]\nrole = \"search\"\nclass = \"B\"\nparent = \"taskbar\"",
+            "The taskbar rules are their own parent",
+        ),
+    ];
+    for (text, error) in errors.iter() {
+        assert_eq!(Rules::parse(text), Err(error.to_string()));
+    }
+    assert!(Rules::parse("version = 1\n[[rule]]\nrole = \"dock\"\nclass = \"A\"").is_err());
+    assert!(Rules::parse("version = 1\n[[rule]]\nrole = \"taskbar\"\nclas = \"A\"").is_err());
+
+    // A user file can't sneak a loop past the rules it leaves in place
+    let user = Rules::parse(
+        "version = 1\n[[rule]]\nrole = \"desktop_host\"\nclass = \"A\"\nparent = \"desktop_view\"",
+    )
+    .unwrap();
+    assert_eq!(
+        Rules::builtin().overridden_by(user),
+        Err("The desktop_view rules are their own parent".to_string())
+    );
+}
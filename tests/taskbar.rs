@@ -0,0 +1,145 @@
+use win_gnome::desktop::Desktop;
+use win_gnome::engine::ShellState;
+use win_gnome::geometry::TrayOrientation;
+use win_gnome::record::WindowTree;
+use win_gnome::shell::{FakeShell, ShellBackend, WindowId};
+use win_gnome::taskbar::ShellVersion;
+
+fn fixture(json: &str) -> WindowTree {
+    serde_json::from_str(json).unwrap()
+}
+
+fn windows10() -> WindowTree {
+    fixture(include_str!("fixtures/windows10-19045.json"))
+}
+
+fn windows11() -> WindowTree {
+    fixture(include_str!("fixtures/windows11-22631.json"))
+}
+
+fn describe(desktop: &Desktop<FakeShell>, window: WindowId) -> (String, String, String) {
+    let shell = &desktop.backend;
+    (
+        shell.class_name(window).unwrap_or_default(),
+        shell.title(window).unwrap_or_default(),
+        shell.process_name(window).unwrap_or_default(),
+    )
+}
+
+fn core_window(title: &str, process: &str) -> (String, String, String) {
+    (
+        "Windows.UI.Core.CoreWindow".to_string(),
+        title.to_string(),
+        process.to_string(),
+    )
+}
+
+#[test]
+fn versions_by_build() {
+    assert_eq!(ShellVersion::from_build(19045), ShellVersion::Windows10);
+    assert_eq!(ShellVersion::from_build(21996), ShellVersion::Windows10);
+    assert_eq!(ShellVersion::from_build(22000), ShellVersion::Windows11);
+    assert_eq!(ShellVersion::from_build(26100), ShellVersion::Windows11);
+}
+
+#[test]
+fn finds_windows10_fixture() {
+    let desktop = Desktop::new(windows10().into_shell().unwrap(), 100).unwrap();
+    let tray = &desktop.tray;
+
+    assert_eq!(tray.version, ShellVersion::Windows10);
+    assert_eq!(describe(&desktop, tray.start_button).0, "Start");
+    assert_eq!(
+        describe(&desktop, tray.start_menu),
+        core_window("Start", "StartMenuExperienceHost.exe")
+    );
+    assert_eq!(
+        describe(&desktop, tray.search),
+        core_window("Search", "SearchApp.exe")
+    );
+    assert_eq!(
+        describe(&desktop, tray.icon_overflow).0,
+        "NotifyIconOverflowWindow"
+    );
+    assert_eq!(
+        (tray.geometry.start_width, tray.geometry.start_height),
+        (48, 40)
+    );
+    assert_eq!(describe(&desktop, desktop.shell_parent).0, "WorkerW");
+}
+
+#[test]
+fn finds_windows11_fixture() {
+    let desktop = Desktop::new(windows11().into_shell().unwrap(), 100).unwrap();
+    let tray = &desktop.tray;
+
+    assert_eq!(tray.version, ShellVersion::Windows11);
+    // The hidden, empty `Start` window is left alone
+    assert!(tray.start_button.is_null());
+    assert_eq!(
+        (tray.geometry.start_width, tray.geometry.start_height),
+        (72, 72)
+    );
+    assert_eq!(tray.geometry.orientation, TrayOrientation::Bottom);
+    assert_eq!(tray.geometry.scale.percent(), 150);
+    assert_eq!(
+        describe(&desktop, tray.start_menu),
+        core_window("Start", "StartMenuExperienceHost.exe")
+    );
+    assert_eq!(
+        describe(&desktop, tray.search),
+        core_window("Search", "SearchHost.exe")
+    );
+    assert_eq!(
+        describe(&desktop, tray.icon_overflow).0,
+        "TopLevelWindowForOverflowXamlIsland"
+    );
+    assert_eq!(describe(&desktop, desktop.shell_parent).0, "Progman");
+}
+
+#[test]
+fn detects_version_from_taskbar_without_build() {
+    for (tree, version) in [
+        (windows10(), ShellVersion::Windows10),
+        (windows11(), ShellVersion::Windows11),
+    ] {
+        let mut tree = tree;
+        tree.build = None;
+        let desktop = Desktop::new(tree.into_shell().unwrap(), 100).unwrap();
+        assert_eq!(desktop.tray.version, version);
+    }
+}
+
+#[test]
+fn windows11_search_counts_as_start_menu() {
+    let mut desktop = Desktop::new(windows11().into_shell().unwrap(), 100).unwrap();
+    desktop.hide_tray();
+    let search = desktop.tray.search;
+    let other = desktop.backend.foreground_window();
+
+    desktop.backend.window_mut(search).unwrap().cloaked = false;
+    assert!(!desktop.foreground_changed(search));
+    assert!(desktop.tray.startmenu_showing);
+    assert!(desktop.tray.showing);
+    assert!(desktop.start_menu_focused());
+
+    desktop.backend.window_mut(search).unwrap().cloaked = true;
+    assert!(desktop.foreground_changed(other));
+    assert!(!desktop.tray.startmenu_showing);
+}
+
+#[test]
+fn windows11_start_menu_is_not_found_by_position() {
+    let mut shell = FakeShell::windows11(1920, 1080);
+    let menu = shell
+        .windows()
+        .find(|(_, window)| window.title == "Start" && window.class_name != "Start")
+        .map(|(id, _)| *id)
+        .unwrap();
+    shell.window_mut(menu).unwrap().title = String::new();
+
+    assert_eq!(
+        Desktop::new(shell, 100).err(),
+        Some("Unable to find start menu")
+    );
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+use win_gnome::timer::{Clock, ManualClock, Timers};
+
+#[test]
+fn timers_come_out_earliest_first() {
+    let mut timers = Timers::new();
+    timers.schedule(0, Duration::from_millis(300), "delay");
+    timers.schedule(0, Duration::from_millis(50), "menu");
+    timers.schedule(10, Duration::from_millis(100), "dwell");
+
+    assert_eq!(timers.next_due(), Some(50));
+    assert_eq!(timers.pop_due(49), None);
+    assert_eq!(timers.pop_due(200), Some((50, "menu")));
+    assert_eq!(timers.pop_due(200), Some((110, "dwell")));
+    assert_eq!(timers.pop_due(200), None);
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers.pop_due(1000), Some((300, "delay")));
+    assert!(timers.is_empty());
+}
+
+#[test]
+fn ties_keep_their_order() {
+    let mut timers = Timers::new();
+    for timer in 0..4 {
+        timers.schedule(0, Duration::from_millis(20), timer);
+    }
+    let fired: Vec<_> = std::iter::from_fn(|| timers.pop_due(20))
+        .map(|(_, timer)| timer)
+        .collect();
+    assert_eq!(fired, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn delay_until_the_next_timer() {
+    let mut timers = Timers::new();
+    assert_eq!(timers.delay_until_next(0), None);
+
+    timers.schedule(100, Duration::from_millis(300), ());
+    assert_eq!(
+        timers.delay_until_next(150),
+        Some(Duration::from_millis(250))
+    );
+    // Overdue timers are due right away.
+    assert_eq!(timers.delay_until_next(500), Some(Duration::ZERO));
+
+    timers.clear();
+    assert_eq!(timers.delay_until_next(500), None);
+}
+
+#[test]
+fn manual_clock_drives_timers() {
+    let clock = ManualClock::new(1000);
+    let mut timers = Timers::new();
+    timers.schedule(clock.now(), Duration::from_millis(150), ());
+
+    clock.advance(Duration::from_millis(100));
+    assert_eq!(timers.pop_due(clock.now()), None);
+    clock.advance(Duration::from_millis(50));
+    assert_eq!(timers.pop_due(clock.now()), Some((1150, ())));
+
+    clock.set(0);
+    assert_eq!(clock.now(), 0);
+}
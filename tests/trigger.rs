@@ -0,0 +1,340 @@
+use std::time::Duration;
+use win_gnome::config::Config;
+use win_gnome::engine::{
+    Command, CornerAction, Event, HotCornerEngine, ShellState, TimerKind, CORNER_DELAY,
+};
+use win_gnome::geometry::{TrayGeometry, TrayOrientation};
+use win_gnome::trigger::{unclipped, Decision, TriggerFilter, TriggerSettings};
+
+fn filter(dwell: u64, pressure: i32, max_speed: f64) -> TriggerFilter {
+    TriggerFilter::new(TriggerSettings {
+        dwell,
+        pressure,
+        max_speed,
+    })
+}
+
+/// Feeds `(time, x, y, in_corner, overshoot)` samples, returns the decision for each.
+fn trace(filter: &mut TriggerFilter, samples: &[(u64, i32, i32, bool, i32)]) -> Vec<Decision> {
+    samples
+        .iter()
+        .map(|&(time, x, y, in_corner, overshoot)| filter.sample(time, x, y, in_corner, overshoot))
+        .collect()
+}
+
+#[test]
+fn fires_at_once_by_default() {
+    let mut filter = TriggerFilter::new(TriggerSettings::default());
+    assert_eq!(
+        trace(
+            &mut filter,
+            &[(0, 500, 500, false, 0), (8, 0, 1079, true, 0)]
+        ),
+        vec![Decision::Idle, Decision::Fire]
+    );
+}
+
+#[test]
+fn dwell_waits_for_the_cursor_to_rest() {
+    let mut filter = filter(150, 0, 0.0);
+    assert_eq!(
+        trace(
+            &mut filter,
+            &[(0, 2, 1078, true, 0), (40, 1, 1079, true, 0)]
+        ),
+        vec![Decision::Wait(Duration::from_millis(150)), Decision::Idle]
+    );
+    assert_eq!(
+        filter.tick(100, true),
+        Decision::Wait(Duration::from_millis(50))
+    );
+    assert_eq!(filter.tick(150, true), Decision::Fire);
+    assert_eq!(filter.tick(300, true), Decision::Idle);
+}
+
+#[test]
+fn leaving_the_corner_cancels_the_dwell() {
+    let mut filter = filter(150, 0, 0.0);
+    assert_eq!(
+        trace(
+            &mut filter,
+            &[
+                (0, 2, 1078, true, 0),
+                (60, 200, 900, false, 0),
+                (120, 2, 1078, true, 0)
+            ]
+        ),
+        vec![
+            Decision::Wait(Duration::from_millis(150)),
+            Decision::Idle,
+            Decision::Wait(Duration::from_millis(150)),
+        ]
+    );
+    assert_eq!(
+        filter.tick(150, true),
+        Decision::Wait(Duration::from_millis(120))
+    );
+    assert_eq!(filter.tick(270, true), Decision::Fire);
+    assert_eq!(filter.tick(300, false), Decision::Idle);
+}
+
+#[test]
+fn pressure_builds_up_past_the_edge() {
+    let mut filter = filter(0, 100, 0.0);
+    assert_eq!(
+        trace(
+            &mut filter,
+            &[
+                (0, 0, 1079, true, 0),
+                (8, -30, 1079, true, 30),
+                (16, -40, 1089, true, 50),
+                (24, 10, 1079, false, 0),
+                (32, -30, 1079, true, 30),
+                (40, -80, 1079, true, 80),
+            ]
+        ),
+        vec![
+            Decision::Idle,
+            Decision::Idle,
+            Decision::Idle,
+            Decision::Idle,
+            Decision::Idle,
+            Decision::Fire,
+        ]
+    );
+}
+
+#[test]
+fn either_dwell_or_pressure_fires() {
+    let mut filter = filter(500, 50, 0.0);
+    assert_eq!(
+        trace(
+            &mut filter,
+            &[(0, 0, 1079, true, 0), (10, -60, 1079, true, 60)]
+        ),
+        vec![Decision::Wait(Duration::from_millis(500)), Decision::Fire]
+    );
+    // The dwell timer that's still running finds nothing to do.
+    assert_eq!(filter.tick(500, true), Decision::Idle);
+}
+
+#[test]
+fn flinging_through_the_corner_does_not_fire() {
+    let mut filter = filter(0, 0, 2.0);
+    assert_eq!(
+        trace(
+            &mut filter,
+            &[
+                (0, 400, 700, false, 0),
+                (8, 0, 1079, true, 0),
+                (16, 3, 1076, true, 0),
+            ]
+        ),
+        vec![Decision::Idle, Decision::Idle, Decision::Fire]
+    );
+
+    // With a dwell, coming to rest after the fling is enough.
+    let mut filter = self::filter(100, 0, 2.0);
+    assert_eq!(
+        trace(
+            &mut filter,
+            &[(0, 400, 700, false, 0), (8, 0, 1079, true, 0)]
+        ),
+        vec![Decision::Idle, Decision::Wait(Duration::from_millis(100))]
+    );
+    assert_eq!(filter.tick(108, true), Decision::Fire);
+}
+
+#[test]
+fn overshoot_is_measured_from_the_screen_edge() {
+    let geometry = TrayGeometry::new(TrayOrientation::Bottom, (1920, 1080), (48, 40), 100);
+    assert_eq!(geometry.overshoot(0, 1079), 0);
+    assert_eq!(geometry.overshoot(-12, 1079), 12);
+    assert_eq!(geometry.overshoot(-12, 1085), 18);
+    assert_eq!(geometry.overshoot(1925, -1), 7);
+}
+
+#[test]
+fn clipped_samples_push_past_the_edge() {
+    // X11 only says where the pointer ended up, clipped to the screen
+    let clipped = [(0, 1, 1079, -5), (8, 0, 1079, -30), (16, 0, 1079, -50)];
+    let mut last = 6;
+    let samples: Vec<_> = clipped
+        .iter()
+        .map(|&(time, x, y, delta)| {
+            let past = unclipped(last, delta, x, 1920);
+            last = x;
+            (time, past, y, true, (-past).max(0))
+        })
+        .collect();
+    assert_eq!(
+        samples,
+        vec![
+            (0, 1, 1079, true, 0),
+            (8, -29, 1079, true, 29),
+            (16, -50, 1079, true, 50),
+        ]
+    );
+    assert_eq!(
+        trace(&mut filter(0, 60, 0.0), &samples),
+        vec![Decision::Idle, Decision::Idle, Decision::Fire]
+    );
+
+    // Taken as they are, the samples never build up any pressure
+    let clamped: Vec<_> = clipped
+        .iter()
+        .map(|&(time, x, y, _)| (time, x, y, true, 0))
+        .collect();
+    assert_eq!(
+        trace(&mut filter(0, 60, 0.0), &clamped),
+        vec![Decision::Idle; 3]
+    );
+
+    // Only pushing towards an edge the pointer is on counts
+    assert_eq!(unclipped(100, -20, 80, 1920), 80);
+    assert_eq!(unclipped(1915, 10, 1919, 1920), 1925);
+    assert_eq!(unclipped(1919, -10, 1909, 1920), 1909);
+}
+
+#[test]
+fn trigger_from_config() {
+    let config = Config::parse("[trigger]\ndwell = 150\nmax_speed = 1.5").unwrap();
+    assert_eq!(
+        config.trigger,
+        TriggerSettings {
+            dwell: 150,
+            pressure: 0,
+            max_speed: 1.5,
+        }
+    );
+    assert!(Config::parse("[trigger]\npressure = -1").is_err());
+    assert!(Config::parse("[trigger]\nmax_speed = -0.5").is_err());
+    assert!(Config::parse("[trigger]\ndelay = 5").is_err());
+}
+
+struct Shell(TrayGeometry);
+
+impl ShellState for Shell {
+    fn geometry(&self) -> &TrayGeometry {
+        &self.0
+    }
+    fn full_screen_program(&mut self) -> bool {
+        false
+    }
+    fn is_tray_open(&self) -> bool {
+        false
+    }
+    fn start_menu_focused(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn engine_waits_out_the_dwell() {
+    let mut shell = Shell(TrayGeometry::new(
+        TrayOrientation::Bottom,
+        (1920, 1080),
+        (48, 40),
+        100,
+    ));
+    let mut engine = HotCornerEngine::default();
+    engine.trigger.settings.dwell = 200;
+    let corner = Event::MouseMoved { x: 1, y: 1079 };
+
+    assert_eq!(
+        engine.handle_at(1000, corner, &mut shell),
+        vec![Command::StartTimer(
+            TimerKind::Dwell,
+            Duration::from_millis(200)
+        )]
+    );
+    assert_eq!(engine.handle_at(1100, corner, &mut shell), vec![]);
+    assert_eq!(
+        engine.handle_at(1200, Event::TimerElapsed(TimerKind::Dwell), &mut shell),
+        vec![
+            Command::ShowTray,
+            Command::Run(CornerAction::StartMenu),
+            Command::StartTimer(TimerKind::Debounce, Duration::from_millis(300)),
+        ]
+    );
+}
+
+#[test]
+fn pressure_and_speed_are_device_independent() {
+    let mut pressure = filter(0, 100, 2.0);
+    pressure.scale = win_gnome::dpi::Scale::new(144);
+    // 100 DIPs of pressure is 150 pixels at 150%
+    assert_eq!(
+        trace(
+            &mut pressure,
+            &[
+                (0, 0, 1079, true, 60),
+                (10, 0, 1079, true, 60),
+                (20, 0, 1079, true, 60)
+            ]
+        ),
+        vec![Decision::Idle, Decision::Idle, Decision::Fire]
+    );
+    // 25 pixels in 10ms is under 2 DIPs per millisecond at 150%
+    let mut speed = filter(0, 0, 2.0);
+    speed.scale = win_gnome::dpi::Scale::new(144);
+    assert_eq!(
+        trace(
+            &mut speed,
+            &[(0, 25, 1079, false, 0), (10, 0, 1079, true, 0)]
+        ),
+        vec![Decision::Idle, Decision::Fire]
+    );
+}
+
+#[test]
+fn dwell_timer_does_not_end_the_debounce() {
+    let mut shell = Shell(TrayGeometry::new(
+        TrayOrientation::Bottom,
+        (1920, 1080),
+        (48, 40),
+        100,
+    ));
+    let mut engine = HotCornerEngine::default();
+    engine.trigger.settings.dwell = 150;
+    engine.trigger.settings.pressure = 10;
+    let fired = vec![
+        Command::ShowTray,
+        Command::Run(CornerAction::StartMenu),
+        Command::StartTimer(TimerKind::Debounce, CORNER_DELAY),
+    ];
+
+    assert_eq!(
+        engine.handle_at(1000, Event::MouseMoved { x: 0, y: 1079 }, &mut shell),
+        vec![Command::StartTimer(
+            TimerKind::Dwell,
+            Duration::from_millis(150)
+        )]
+    );
+    // Pushed against the edge before the dwell is up
+    assert_eq!(
+        engine.handle_at(1020, Event::MouseMoved { x: 0, y: 1089 }, &mut shell),
+        fired
+    );
+    // The dwell timer still comes, well inside the debounce
+    let dwell = Event::TimerElapsed(TimerKind::Dwell);
+    assert_eq!(engine.handle_at(1150, dwell, &mut shell), vec![]);
+    assert_eq!(
+        engine.handle_at(1160, Event::MouseMoved { x: 900, y: 500 }, &mut shell),
+        vec![]
+    );
+    assert_eq!(
+        engine.handle_at(1170, Event::MouseMoved { x: 0, y: 1089 }, &mut shell),
+        vec![]
+    );
+
+    // Only the debounce timer lets the corner fire again
+    let debounce = Event::TimerElapsed(TimerKind::Debounce);
+    assert_eq!(engine.handle_at(1320, debounce, &mut shell), vec![]);
+    engine.handle_at(1330, Event::MouseMoved { x: 900, y: 500 }, &mut shell);
+    engine.handle_at(1340, Event::MouseMoved { x: 0, y: 1079 }, &mut shell);
+    assert_eq!(
+        engine.handle_at(1350, Event::MouseMoved { x: 0, y: 1089 }, &mut shell),
+        fired
+    );
+}
@@ -0,0 +1,142 @@
+#![cfg(target_os = "linux")]
+
+use win_gnome::ewmh::EwmhDesktop;
+use win_gnome::shell::x11::{keysym, X11Shell};
+use win_gnome::shell::{vk, DockBackend, ShellBackend, WindowId};
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{AtomEnum, ConnectionExt, CreateWindowAux, PropMode, WindowClass};
+use x11rb::wrapper::ConnectionExt as _;
+
+#[test]
+fn keysyms_for_virtual_keys() {
+    assert_eq!(keysym(vk::LWIN), Some(0xffeb));
+    assert_eq!(keysym(vk::TAB), Some(0xff09));
+    assert_eq!(keysym(b'D'), Some(u32::from(b'd')));
+    assert_eq!(keysym(b'7'), Some(u32::from(b'7')));
+    assert_eq!(keysym(vk::F1 + 11), Some(0xffc9));
+    assert_eq!(keysym(0xff), None);
+}
+
+/// Runs against `$DISPLAY`, e.g. `xvfb-run cargo test --test x11`. Skipped without one.
+#[test]
+fn finds_and_hides_windows_on_a_real_server() {
+    if std::env::var_os("DISPLAY").is_none() {
+        println!("No $DISPLAY, skipping");
+        return;
+    }
+    let (conn, screen) = x11rb::connect(None).unwrap();
+    let root = conn.setup().roots[screen].root;
+    let window = conn.generate_id().unwrap();
+    conn.create_window(
+        0,
+        window,
+        root,
+        10,
+        20,
+        300,
+        200,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        0,
+        &CreateWindowAux::new(),
+    )
+    .unwrap();
+    conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        AtomEnum::WM_CLASS,
+        AtomEnum::STRING,
+        b"dock\0WinGnomeTest\0",
+    )
+    .unwrap();
+    conn.change_property8(
+        PropMode::REPLACE,
+        window,
+        AtomEnum::WM_NAME,
+        AtomEnum::STRING,
+        b"panel",
+    )
+    .unwrap();
+    conn.map_window(window).unwrap();
+    conn.sync().unwrap();
+
+    let mut shell = X11Shell::connect(None).unwrap();
+    let found = shell
+        .find_window(Some("WinGnomeTest"), Some("panel"))
+        .unwrap();
+    assert_eq!(found.0 as u32, window);
+    assert_eq!(shell.window_dimensions(found), (300, 200));
+    assert!(shell.is_visible(found));
+
+    assert!(shell.set_visible(found, false));
+    shell.flush();
+    assert!(!shell.is_visible(found));
+    assert!(!shell.monitors().is_empty());
+}
+
+/// A dock client like polybar would be, reserving 30px along the bottom.
+#[test]
+fn hides_a_dummy_dock() {
+    if std::env::var_os("DISPLAY").is_none() {
+        println!("No $DISPLAY, skipping");
+        return;
+    }
+    let (conn, screen) = x11rb::connect(None).unwrap();
+    let root = conn.setup().roots[screen].root;
+    let intern = |name: &[u8]| conn.intern_atom(false, name).unwrap().reply().unwrap().atom;
+    let (window_type, dock_type, strut_partial) = (
+        intern(b"_NET_WM_WINDOW_TYPE"),
+        intern(b"_NET_WM_WINDOW_TYPE_DOCK"),
+        intern(b"_NET_WM_STRUT_PARTIAL"),
+    );
+    let strut = [0, 0, 0, 30, 0, 0, 0, 0, 0, 0, 0, 299];
+    let dock = conn.generate_id().unwrap();
+    conn.create_window(
+        0,
+        dock,
+        root,
+        0,
+        0,
+        300,
+        30,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        0,
+        &CreateWindowAux::new(),
+    )
+    .unwrap();
+    conn.change_property32(
+        PropMode::REPLACE,
+        dock,
+        window_type,
+        AtomEnum::ATOM,
+        &[dock_type],
+    )
+    .unwrap();
+    conn.change_property32(
+        PropMode::REPLACE,
+        dock,
+        strut_partial,
+        AtomEnum::CARDINAL,
+        &strut,
+    )
+    .unwrap();
+    conn.map_window(dock).unwrap();
+    conn.sync().unwrap();
+
+    let shell = X11Shell::connect(None).unwrap();
+    let found = WindowId(dock as usize);
+    assert!(shell.docks().contains(&found));
+    assert_eq!(shell.strut(found), Some(strut));
+
+    let mut desktop = EwmhDesktop::new(shell, 100);
+    desktop.hide_docks();
+    desktop.backend.flush();
+    assert!(!desktop.backend.is_visible(found));
+    assert_eq!(desktop.backend.strut(found), None);
+
+    desktop.show_docks();
+    desktop.backend.flush();
+    assert!(desktop.backend.is_visible(found));
+    assert_eq!(desktop.backend.strut(found), Some(strut));
+}
@@ -38,6 +38,10 @@ if ($task_exists) {
     Write-Host "Removing previous  '$taskname' scheduled task"
     Unregister-ScheduledTask -TaskName $taskname -Confirm:$false
 }
+if(Test-Path "$destination\$target"){
+    Write-Host "Restoring the taskbar's auto-hide setting"
+    & "$destination\$target" --restore-auto-hide | Out-Null
+}
 Write-Host "Removing installed directories"
 Remove-Item -Recurse -Force $destination
 